@@ -2,6 +2,7 @@ use tiny_bench::black_box;
 
 fn main() {
     let label = "compare_functions";
+    tiny_bench::report_relative_to_first(true);
     tiny_bench::bench_labeled(label, my_slow_function);
     tiny_bench::bench_labeled(label, my_faster_function);
 }
@@ -0,0 +1,31 @@
+//! Compares the cost of `black_box` on a small `Copy` value with copying it out of a barrier
+//! on a reference, which is what a specialized `black_box_copy` would do, run with
+//! `cargo run --release --example black_box_copy`
+use tiny_bench::{black_box, BenchmarkConfig};
+
+/// Hides a reference from the optimizer and copies the value out of it
+fn black_box_copy<T: Copy>(dummy: T) -> T {
+    *black_box(&dummy)
+}
+
+fn main() {
+    let cfg = BenchmarkConfig {
+        dump_results_to_disk: false,
+        ..BenchmarkConfig::default()
+    };
+    tiny_bench::report_relative_to_first(true);
+    tiny_bench::bench_with_configuration_labeled("black_box", &cfg, || {
+        let mut sum = 0u64;
+        for i in 0..1_000u64 {
+            sum = sum.wrapping_add(black_box(i));
+        }
+        sum
+    });
+    tiny_bench::bench_with_configuration_labeled("black_box_copy", &cfg, || {
+        let mut sum = 0u64;
+        for i in 0..1_000u64 {
+            sum = sum.wrapping_add(black_box_copy(i));
+        }
+        sum
+    });
+}
@@ -1,11 +1,52 @@
-use crate::output::analysis::criterion::calculate_iterations;
+mod affinity;
+#[cfg(feature = "alloc-count")]
+mod alloc_count;
+mod calibration;
+mod cdf;
+mod complexity;
+mod environment;
+mod exclusive;
+mod group;
+mod load;
+mod open_loop;
+#[cfg(feature = "linux-rusage")]
+mod rusage;
+
+use crate::output::analysis::criterion::{
+    bootstrap_mean_interval, calculate_iterations, ColdStart, ElisionCheck, Extremes, OutputFormat,
+    SamplingDataSimpleAnalysis, StopWhen, WarmUpAction, WarmUpCheck, ZeroTimeSamples,
+};
+use crate::output::analysis::random::Rng;
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::analysis::welch::normal_quantile;
+use crate::output::stream::{out, outln};
 use crate::output::{
-    fallback_to_anonymous_on_invalid_label, fmt_num, fmt_time, wrap_bold_green,
-    wrap_high_intensity_white, Output,
+    fallback_to_anonymous_on_invalid_label, fmt_num_with, fmt_time, persisted_label, resolve_label,
+    wrap_bold_green, wrap_high_intensity_white, wrap_yellow, Access, Output,
 };
 use crate::{black_box, BenchmarkConfig};
+use std::io::{BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock, PoisonError};
 use std::time::{Duration, Instant};
 
+#[cfg(feature = "alloc-count")]
+pub use alloc_count::{
+    assert_no_alloc_bench, assert_no_alloc_bench_configuration, CountingAllocator,
+};
+pub use calibration::calibration_score;
+pub(crate) use calibration::machine_name;
+pub use cdf::{bench_latency_cdf, load_latency_cdf, LatencyCdf};
+pub use complexity::{
+    bench_complexity, bench_complexity_configuration, Complexity, ComplexityFit, ComplexityReport,
+};
+pub use environment::{environment, load_environment, Environment};
+pub use group::{
+    bench_group, bench_group_configuration, bench_variants, bench_variants_configuration,
+    PairwiseComparison,
+};
+pub use open_loop::{bench_open_loop, bench_open_loop_configuration, OpenLoopStats};
+
 /// Will run the closure and print statistics from the benchmarking to stdout.
 /// Will persist results under the anonymous label which is shared, making comparisons impossible
 /// if running more than one (different) benchmark on the same project, ie. benching two different
@@ -31,7 +72,57 @@ pub fn bench_labeled<T, F: FnMut() -> T>(label: &'static str, closure: F) {
     bench_with_configuration_labeled(label, &BenchmarkConfig::default(), closure);
 }
 
+/// Runs like [`bench_labeled`] but never reads or writes persisted results, for one-off
+/// measurements that shouldn't be compared with or replace the stored baseline, like while
+/// debugging
+/// ```no_run
+/// use tiny_bench::bench_ephemeral;
+/// bench_ephemeral("my_benchmark", || {
+///     // Some code that should be benched
+/// })
+/// ```
+pub fn bench_ephemeral<T, F: FnMut() -> T>(label: &'static str, closure: F) {
+    bench_with_configuration_labeled(label, &ephemeral_configuration(), closure);
+}
+
+/// Runs like [`bench_with_setup_labeled`] but never reads or writes persisted results, see
+/// [`bench_ephemeral`]
+/// ```no_run
+/// use tiny_bench::bench_ephemeral_with_setup;
+/// bench_ephemeral_with_setup("my_benchmark", || vec![3, 1, 2], |mut v| {
+///     v.sort();
+///     v
+/// })
+/// ```
+pub fn bench_ephemeral_with_setup<T, R, F: FnMut(R) -> T, S: FnMut() -> R>(
+    label: &'static str,
+    setup: S,
+    closure: F,
+) {
+    bench_with_setup_configuration_labeled(label, &ephemeral_configuration(), setup, closure);
+}
+
+fn ephemeral_configuration() -> BenchmarkConfig {
+    BenchmarkConfig {
+        dump_results_to_disk: false,
+        ..BenchmarkConfig::default()
+    }
+}
+
+/// A short measurement and warm up that isn't persisted, so that tests run quickly
+#[cfg(test)]
+pub(crate) fn fast_cfg() -> BenchmarkConfig {
+    BenchmarkConfig {
+        measurement_time: Duration::from_millis(10),
+        warm_up_time: Duration::from_millis(5),
+        dump_results_to_disk: false,
+        ..BenchmarkConfig::default()
+    }
+}
+
 /// Will run the benchmark with the supplied configuration
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
 /// ```no_run
 /// use std::time::Duration;
 /// use tiny_bench::{bench_with_configuration, BenchmarkConfig};
@@ -46,7 +137,58 @@ pub fn bench_with_configuration<T, F: FnMut() -> T>(cfg: &BenchmarkConfig, closu
     bench_with_configuration_labeled("anonymous", cfg, closure);
 }
 
+/// Benches a generic function without arguments once per type, labeled `<label>/<type>`,
+/// and returns the `BenchStats` of each in order. Rust can't iterate over types at runtime,
+/// so this expands to one benchmark per type, which all show up in [`print_summary`].
+/// Results are nested under the label when persisted, a configuration passed as the second
+/// argument should set `label_separator: Some("/")` for that, or the labels are handled by
+/// its `label_policy`.
+/// ```no_run
+/// use tiny_bench::{bench_generic, black_box, BenchmarkConfig};
+/// fn sum<T: From<u8> + std::iter::Sum>() -> T {
+///     (0..100u8).map(|i| black_box(T::from(i))).sum()
+/// }
+/// bench_generic!("sum", sum, [u32, u64, f32, f64]);
+/// let cfg = BenchmarkConfig {
+///     label_separator: Some("/"),
+///     ..BenchmarkConfig::default()
+/// };
+/// bench_generic!("sum", &cfg, sum, [u16, i64]);
+/// ```
+#[macro_export]
+macro_rules! bench_generic {
+    ($label:literal, $($function:ident)::+, [$($ty:ty),+ $(,)?]) => {
+        $crate::bench_generic!(
+            $label,
+            &$crate::BenchmarkConfig {
+                label_separator: Some("/"),
+                ..$crate::BenchmarkConfig::default()
+            },
+            $($function)::+,
+            [$($ty),+]
+        )
+    };
+    ($label:literal, $cfg:expr, $($function:ident)::+, [$($ty:ty),+ $(,)?]) => {
+        $crate::bench_generic!(@each $label, $cfg, [$($function)::+], [$($ty),+])
+    };
+    // The function is passed on as a single token tree so that it can be repeated for each type
+    (@each $label:literal, $cfg:expr, $function:tt, [$($ty:ty),+]) => {
+        vec![$(
+            $crate::bench_with_configuration_labeled_returning(
+                concat!($label, "/", stringify!($ty)),
+                $cfg,
+                $crate::bench_generic!(@instantiate $function, $ty),
+            )
+        ),+]
+    };
+    (@instantiate [$($function:tt)+], $ty:ty) => {
+        $($function)+::<$ty>
+    };
+}
+
 /// Will run the benchmark with the supplied configuration and a label
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
 /// ```no_run
 /// use tiny_bench::{bench_with_configuration_labeled, BenchmarkConfig};
 /// bench_with_configuration_labeled("my_benchmark", &BenchmarkConfig::default(), || {
@@ -56,74 +198,57 @@ pub fn bench_with_configuration<T, F: FnMut() -> T>(cfg: &BenchmarkConfig, closu
 pub fn bench_with_configuration_labeled<T, F: FnMut() -> T>(
     label: &'static str,
     cfg: &BenchmarkConfig,
-    mut closure: F,
+    closure: F,
 ) {
-    let label = fallback_to_anonymous_on_invalid_label(label);
-    println!(
-        "{} warming up for {}",
-        wrap_bold_green(label),
-        wrap_high_intensity_white(&fmt_time(cfg.warm_up_time.as_nanos() as f64))
-    );
-    let wu = run_warm_up(&mut closure, cfg.warm_up_time);
-    let mean_execution_time = wu.elapsed.as_nanos() as f64 / wu.iterations as f64;
-    let sample_size = cfg.num_samples as u64;
-    let (iters, total_iters) =
-        calculate_iters_and_total_iters(cfg, mean_execution_time, sample_size);
-
-    println!(
-        "{} mean warm up execution time {} running {} iterations",
-        wrap_bold_green(label),
-        wrap_high_intensity_white(&fmt_time(mean_execution_time)),
-        wrap_high_intensity_white(&fmt_num(total_iters as f64))
-    );
-    let sampling_data = run(iters, closure);
-    if cfg.dump_results_to_disk {
-        crate::output::ComparedStdout.dump_sampling_data(label, &sampling_data, cfg, total_iters);
-    } else {
-        crate::output::SimpleStdout.dump_sampling_data(label, &sampling_data, cfg, total_iters);
-    }
+    run_routine(label, cfg, &mut Plain(closure));
 }
 
-fn calculate_iters_and_total_iters(
+/// Will run the benchmark with the supplied configuration and a label, printing like
+/// `bench_with_configuration_labeled` and also returning the statistics, down to the mean of each
+/// sample, for reporting them in a custom harness
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_with_configuration_labeled_returning, BenchmarkConfig};
+/// let stats = bench_with_configuration_labeled_returning("my_benchmark", &BenchmarkConfig::default(), || {
+///     // Some code that should be benched
+/// });
+/// println!("Warm up estimated {}ns, measured {}ns", stats.estimated_mean_ns, stats.mean);
+/// ```
+pub fn bench_with_configuration_labeled_returning<T, F: FnMut() -> T>(
+    label: &'static str,
     cfg: &BenchmarkConfig,
-    mut mean_execution_time_ns: f64,
-    sample_size: u64,
-) -> (Vec<u64>, u128) {
-    if let Some(max_it) = cfg.max_iterations {
-        (vec![max_it], u128::from(max_it))
-    } else {
-        // This can be arbitrarily small, causing an absurd amount of iterations.
-        // Raise it to 1 nano -> max 5B iterations
-        mean_execution_time_ns = mean_execution_time_ns.max(1.0);
-        let iters = calculate_iterations(mean_execution_time_ns, sample_size, cfg.measurement_time);
-        let mut total_iters = 0u128;
-        for count in iters.iter().copied() {
-            total_iters = total_iters.saturating_add(u128::from(count));
-        }
-        (iters, total_iters)
-    }
+    closure: F,
+) -> BenchStats {
+    run_routine(label, cfg, &mut Plain(closure))
 }
 
-fn run<T, F: FnMut() -> T>(sample_sizes: Vec<u64>, mut closure: F) -> SamplingData {
-    let times = sample_sizes
-        .iter()
-        .copied()
-        .map(|it_count| {
-            let start = Instant::now();
-            for _ in 0..it_count {
-                black_box(closure());
-            }
-            start.elapsed().as_nanos()
-        })
-        .collect();
-    SamplingData {
-        samples: sample_sizes,
-        times,
-    }
+/// Like `bench_with_configuration_labeled` but takes the configuration by value and hands it
+/// back, so that it can be threaded through a parameter sweep without rebuilding it
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_returning_config, black_box, BenchmarkConfig};
+/// let mut cfg = BenchmarkConfig::default();
+/// for size in [10, 100, 1000] {
+///     cfg = bench_returning_config("sum", cfg, || black_box((0..size).sum::<u64>()));
+/// }
+/// ```
+#[must_use = "the configuration is handed back to be reused"]
+pub fn bench_returning_config<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: BenchmarkConfig,
+    closure: F,
+) -> BenchmarkConfig {
+    run_routine(label, &cfg, &mut Plain(closure));
+    cfg
 }
 
 /// Fitting if some setup for the benchmark is required, and that setup should not be timed.
 /// The setup will be run prior to each benchmarking run.
+/// Inputs are set up in batches ahead of timing the closure over them, both while warming up and
+/// while measuring, so the warm up estimates the time of the closure alone. Because setup isn't
+/// counted, fewer iterations fit in `warm_up_time` when the setup is slow.
 /// ```no_run
 /// use tiny_bench::{bench_with_configuration_labeled, BenchmarkConfig};
 /// bench_with_configuration_labeled("my_benchmark", &BenchmarkConfig::default(), || {
@@ -156,6 +281,8 @@ pub fn bench_with_setup_labeled<T, R, F: FnMut(R) -> T, S: FnMut() -> R>(
 }
 
 /// Run bench with setup and configuration
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
 /// ```no_run
 /// use std::time::Duration;
 /// use tiny_bench::{bench_with_setup_configuration, BenchmarkConfig};
@@ -172,6 +299,8 @@ pub fn bench_with_setup_configuration<T, R, F: FnMut(R) -> T, S: FnMut() -> R>(
 }
 
 /// Run bench with setup, configuration, and a label
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
 /// ```no_run
 /// use std::time::Duration;
 /// use tiny_bench::{bench_with_setup_configuration_labeled, BenchmarkConfig};
@@ -182,141 +311,3503 @@ pub fn bench_with_setup_configuration<T, R, F: FnMut(R) -> T, S: FnMut() -> R>(
 pub fn bench_with_setup_configuration_labeled<T, R, F: FnMut(R) -> T, S: FnMut() -> R>(
     label: &'static str,
     cfg: &BenchmarkConfig,
-    mut setup: S,
-    mut closure: F,
+    setup: S,
+    closure: F,
 ) {
-    let label = fallback_to_anonymous_on_invalid_label(label);
-    let mut wu_routine = || {
-        let input = (setup)();
-        (closure)(input);
-    };
-    println!(
-        "{} warming up for {}",
-        wrap_bold_green(label),
-        wrap_high_intensity_white(&fmt_time(cfg.warm_up_time.as_nanos() as f64))
+    run_routine(label, cfg, &mut WithSetup { setup, closure });
+}
+
+/// Like [`bench_with_setup_labeled`] but the closure borrows the input instead of taking it,
+/// so that it can return data borrowing from it. The input is dropped after the result,
+/// outside of the measurement.
+/// ```no_run
+/// use tiny_bench::bench_with_setup_borrowing_labeled;
+/// // Some code that should be benched, returning a view into the input
+/// fn parse(input: &String) -> Option<(&str, &str)> {
+///     input.split_once('=')
+/// }
+/// bench_with_setup_borrowing_labeled("my_benchmark", || String::from("key=value"), parse)
+/// ```
+pub fn bench_with_setup_borrowing_labeled<R, S, F>(label: &'static str, setup: S, closure: F)
+where
+    S: FnMut() -> R,
+    F: for<'a> BorrowingFn<'a, R>,
+{
+    bench_with_setup_borrowing_configuration_labeled(
+        label,
+        &BenchmarkConfig::default(),
+        setup,
+        closure,
     );
-    let wu = run_warm_up(&mut wu_routine, cfg.warm_up_time);
-    let mean_execution_time = wu.elapsed.as_nanos() as f64 / wu.iterations as f64;
+}
 
-    let sample_size = cfg.num_samples as u64;
+/// Like [`bench_with_setup_configuration_labeled`] but the closure borrows the input,
+/// see [`bench_with_setup_borrowing_labeled`]
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_with_setup_borrowing_configuration_labeled, BenchmarkConfig};
+/// // Some code that should be benched, returning a view into the input
+/// fn parse(input: &String) -> Option<(&str, &str)> {
+///     input.split_once('=')
+/// }
+/// bench_with_setup_borrowing_configuration_labeled("my_benchmark", &BenchmarkConfig::default(), || String::from("key=value"), parse)
+/// ```
+pub fn bench_with_setup_borrowing_configuration_labeled<R, S, F>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    setup: S,
+    closure: F,
+) where
+    S: FnMut() -> R,
+    F: for<'a> BorrowingFn<'a, R>,
+{
+    run_routine(label, cfg, &mut WithSetupBorrowing { setup, closure });
+}
 
-    let (iters, total_iters) =
-        calculate_iters_and_total_iters(cfg, mean_execution_time, sample_size);
-    println!(
-        "{} mean warm up execution time {} running {} iterations",
-        wrap_bold_green(label),
-        wrap_high_intensity_white(&fmt_time(mean_execution_time)),
-        wrap_high_intensity_white(&fmt_num(total_iters as f64))
+/// Runs the setup once before each sample and the teardown after it, the closure mutates the
+/// state in between. Fitting for benchmarks that degrade over many iterations, like inserting into
+/// a filling map, where setting up for every iteration would be too expensive.
+/// Neither setup nor teardown are timed.
+/// ```no_run
+/// use std::collections::HashMap;
+/// use tiny_bench::bench_per_sample_setup;
+/// let mut key = 0u64;
+/// bench_per_sample_setup("my_benchmark", HashMap::new, |map| {
+///     key += 1;
+///     map.insert(key, key)
+/// }, drop)
+/// ```
+pub fn bench_per_sample_setup<T, R, S, F, D>(label: &'static str, setup: S, closure: F, teardown: D)
+where
+    S: FnMut() -> R,
+    F: FnMut(&mut R) -> T,
+    D: FnMut(R),
+{
+    bench_per_sample_setup_configuration(
+        label,
+        &BenchmarkConfig::default(),
+        setup,
+        closure,
+        teardown,
     );
-    let sampling_data = run_with_setup(iters, setup, closure);
-    if cfg.dump_results_to_disk {
-        crate::output::ComparedStdout.dump_sampling_data(label, &sampling_data, cfg, total_iters);
-    } else {
-        crate::output::SimpleStdout.dump_sampling_data(label, &sampling_data, cfg, total_iters);
-    }
 }
 
-fn run_with_setup<T, R, F: FnMut(R) -> T, S: FnMut() -> R>(
-    sample_sizes: Vec<u64>,
-    mut setup: S,
+/// Like [`bench_per_sample_setup`] with the supplied configuration
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_per_sample_setup_configuration, BenchmarkConfig};
+/// bench_per_sample_setup_configuration("my_benchmark", &BenchmarkConfig::default(), Vec::new, |v| {
+///     v.push(1)
+/// }, drop)
+/// ```
+pub fn bench_per_sample_setup_configuration<T, R, S, F, D>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    setup: S,
+    closure: F,
+    teardown: D,
+) where
+    S: FnMut() -> R,
+    F: FnMut(&mut R) -> T,
+    D: FnMut(R),
+{
+    run_routine(
+        label,
+        cfg,
+        &mut WithSampleSetup {
+            setup,
+            closure,
+            teardown,
+        },
+    );
+}
+
+/// Runs the closure with each input in turn, starting over from the first when all inputs
+/// have been used.
+/// ```no_run
+/// use tiny_bench::bench_over_inputs_labeled;
+/// let inputs = vec![vec![3, 1, 2], vec![1, 2, 3], vec![2, 3, 1]];
+/// bench_over_inputs_labeled("my_benchmark", &inputs, |v| {
+///     // Some code that should be benched
+/// })
+/// ```
+/// # Panics
+/// If `inputs` is empty
+pub fn bench_over_inputs_labeled<I, T, F: FnMut(&I) -> T>(
+    label: &'static str,
+    inputs: &[I],
+    closure: F,
+) {
+    bench_over_inputs_configuration_labeled(label, &BenchmarkConfig::default(), inputs, closure);
+}
+
+/// Run bench over inputs with configuration and a label.
+/// If `shuffle_inputs` is set in the configuration the order of the inputs is shuffled before
+/// each sample, this measures realistic mixed-input performance for data-dependent code where
+/// the CPU would otherwise learn the pattern of a fixed input order, like branchy parsers or
+/// comparators.
+/// ```no_run
+/// use tiny_bench::{bench_over_inputs_configuration_labeled, BenchmarkConfig};
+/// let inputs = vec![vec![3, 1, 2], vec![1, 2, 3], vec![2, 3, 1]];
+/// bench_over_inputs_configuration_labeled("my_benchmark", &BenchmarkConfig {
+///     shuffle_inputs: true,
+///     seed: Some(1234),
+///     ..BenchmarkConfig::default()
+/// }, &inputs, |v| {
+///     // Some code that should be benched
+/// })
+/// ```
+/// # Panics
+/// If `inputs` is empty, or if the label or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+pub fn bench_over_inputs_configuration_labeled<I, T, F: FnMut(&I) -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    inputs: &[I],
+    closure: F,
+) {
+    assert!(
+        !inputs.is_empty(),
+        "Can't bench over an empty set of inputs"
+    );
+    let rng = cfg.shuffle_inputs.then(|| Rng::from_seed_or_time(cfg.seed));
+    run_routine(
+        label,
+        cfg,
+        &mut OverInputs {
+            order: inputs.iter().collect(),
+            rng,
+            closure,
+        },
+    );
+}
+
+/// Benches the closure over records read from the reader, one record per line, for benchmarks
+/// driven by a corpus on disk. Records are read and parsed outside of the timed region as the
+/// samples need them, so a corpus larger than the benchmark needs is never read in full.
+/// Records that `parse` returns `None` for are skipped. Once the reader is exhausted the inputs
+/// read so far are cycled like in [`bench_over_inputs_labeled`], which also keeps them in memory.
+/// ```no_run
+/// use std::fs::File;
+/// use tiny_bench::bench_from_reader;
+/// let corpus = File::open("corpus.txt").unwrap();
+/// bench_from_reader("parse_numbers", corpus, |record| std::str::from_utf8(record).ok().map(str::to_string), |line| {
+///     line.parse::<u64>()
+/// })
+/// ```
+/// # Panics
+/// If the reader has no records that parse
+pub fn bench_from_reader<I, T, R, P, F>(label: &'static str, reader: R, parse: P, closure: F)
+where
+    R: Read,
+    P: FnMut(&[u8]) -> Option<I>,
+    F: FnMut(&I) -> T,
+{
+    bench_from_reader_configuration(label, &BenchmarkConfig::default(), reader, parse, closure);
+}
+
+/// Like [`bench_from_reader`] with the supplied configuration
+/// ```no_run
+/// use std::fs::File;
+/// use tiny_bench::{bench_from_reader_configuration, BenchmarkConfig};
+/// let corpus = File::open("corpus.txt").unwrap();
+/// bench_from_reader_configuration("parse_numbers", &BenchmarkConfig::default(), corpus, |record| std::str::from_utf8(record).ok().map(str::to_string), |line| {
+///     line.parse::<u64>()
+/// })
+/// ```
+/// # Panics
+/// If the reader has no records that parse, or if the label or `run_id` is invalid and
+/// `label_policy` is `LabelPolicy::Reject`
+pub fn bench_from_reader_configuration<I, T, R, P, F>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    reader: R,
+    parse: P,
+    closure: F,
+) where
+    R: Read,
+    P: FnMut(&[u8]) -> Option<I>,
+    F: FnMut(&I) -> T,
+{
+    run_routine(
+        label,
+        cfg,
+        &mut FromReader {
+            reader: Some(BufReader::new(reader)),
+            record: Vec::new(),
+            parse,
+            closure,
+            inputs: Vec::new(),
+            consumed: 0,
+        },
+    );
+}
+
+/// Checks the closure's output with `validate` once before benching it, outside of the
+/// measurement, so that a change that made the code faster by making it wrong fails instead of
+/// looking like an improvement
+/// # Panics
+/// If `validate` returns false
+/// ```no_run
+/// use tiny_bench::bench_checked_output;
+/// let mut v = vec![3, 1, 2];
+/// bench_checked_output("sort", || {
+///     v.sort_unstable();
+///     v.clone()
+/// }, |sorted| sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+/// ```
+pub fn bench_checked_output<T, F: FnMut() -> T, V: Fn(&T) -> bool>(
+    label: &'static str,
+    closure: F,
+    validate: V,
+) {
+    bench_checked_output_configuration(label, &BenchmarkConfig::default(), closure, validate);
+}
+
+/// Like [`bench_checked_output`] with the supplied configuration
+/// # Panics
+/// If `validate` returns false, or if the label or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+pub fn bench_checked_output_configuration<T, F: FnMut() -> T, V: Fn(&T) -> bool>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
     mut closure: F,
-) -> SamplingData {
-    const BATCH_SIZE: usize = 10_000;
-    let times = sample_sizes
-        .iter()
-        .copied()
-        .map(|it_count| {
-            if it_count < BATCH_SIZE as u64 {
-                let inputs = (0..it_count).map(|_| setup()).collect::<Vec<_>>();
-                let start = Instant::now();
-                for i in inputs {
-                    black_box(closure(i));
-                }
-                start.elapsed().as_nanos()
-            } else {
-                let mut elapsed = Duration::ZERO;
-                let mut batch = Vec::with_capacity(BATCH_SIZE);
-                for _ in 0..it_count {
-                    batch.push(setup());
-                    if batch.len() >= BATCH_SIZE {
-                        let start = Instant::now();
-                        for i in batch.drain(..) {
-                            black_box(closure(i));
-                        }
-                        elapsed += start.elapsed();
-                    }
-                }
-                if !batch.is_empty() {
-                    let start = Instant::now();
-                    for i in batch {
-                        black_box(closure(i));
-                    }
-                    elapsed += start.elapsed();
-                }
-                elapsed.as_nanos()
-            }
-        })
-        .collect();
-    SamplingData {
-        samples: sample_sizes,
-        times,
-    }
+    validate: V,
+) {
+    let output = black_box(closure());
+    assert!(
+        validate(&output),
+        "{label} produced an invalid output, not benching it"
+    );
+    run_routine(label, cfg, &mut Plain(closure));
 }
 
-fn run_warm_up<T, F: FnMut() -> T>(closure: &mut F, warmup_time: Duration) -> WarmupResults {
-    let mut elapsed = Duration::ZERO;
-    let mut iterations = 0u128;
-    let mut run_iterations = 1u64;
-    loop {
-        let start = Instant::now();
-        for _ in 0..run_iterations {
-            closure();
-        }
-        elapsed += start.elapsed();
-        iterations += u128::from(run_iterations);
-        run_iterations = run_iterations.wrapping_mul(2);
-        if elapsed >= warmup_time {
-            return WarmupResults {
-                iterations,
-                elapsed,
-            };
-        }
-    }
+/// Benches the closure, which returns a secondary metric to measure alongside time, like the
+/// number of comparisons a sort made or bytes it allocated. The metric is summed over each
+/// sample and reported per iteration, and compared with the last run like time is
+/// ```no_run
+/// use tiny_bench::{bench_with_metric, black_box};
+/// bench_with_metric("sort", || {
+///     let mut v = black_box(vec![3, 1, 2]);
+///     let mut comparisons = 0;
+///     v.sort_by(|a, b| {
+///         comparisons += 1;
+///         a.cmp(b)
+///     });
+///     comparisons
+/// });
+/// ```
+pub fn bench_with_metric<F: FnMut() -> u64>(label: &'static str, closure: F) {
+    bench_with_metric_configuration(label, &BenchmarkConfig::default(), closure);
 }
 
-#[derive(Debug)]
-struct WarmupResults {
-    iterations: u128,
-    elapsed: Duration,
+/// Like [`bench_with_metric`] with the supplied configuration, returning the statistics
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+pub fn bench_with_metric_configuration<F: FnMut() -> u64>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    closure: F,
+) -> BenchStats {
+    run_routine(label, cfg, &mut WithMetric { closure, metric: 0 })
 }
 
-#[derive(Debug)]
-#[cfg(feature = "bench")]
-#[cfg_attr(test, derive(Eq, PartialEq))]
-pub(crate) struct SamplingData {
-    pub(crate) samples: Vec<u64>,
-    pub(crate) times: Vec<u128>,
+/// Benches a reference closure and then the closure, reporting the closure's mean time as a
+/// ratio of the reference's alongside the absolute times.
+/// The reference captures the current speed of the machine, making the ratio more portable
+/// between machines than nanoseconds. Returns the ratio.
+/// ```no_run
+/// use tiny_bench::bench_relative;
+/// let ratio = bench_relative("my_benchmark", || {
+///     // A well known operation to compare against
+/// }, || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_relative<T, U, R: FnMut() -> T, F: FnMut() -> U>(
+    label: &'static str,
+    reference: R,
+    closure: F,
+) -> f64 {
+    bench_relative_with_configuration(label, &BenchmarkConfig::default(), reference, closure)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::Duration;
+/// Benches a reference closure and then the closure with the supplied configuration,
+/// see [`bench_relative`]. The reference is neither printed nor persisted.
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_relative_with_configuration, BenchmarkConfig};
+/// let ratio = bench_relative_with_configuration("my_benchmark", &BenchmarkConfig::default(), || {
+///     // A well known operation to compare against
+/// }, || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_relative_with_configuration<T, U, R: FnMut() -> T, F: FnMut() -> U>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    reference: R,
+    closure: F,
+) -> f64 {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let reference = analyze_sampling_data(
+        &measure(label, cfg, &mut Plain(reference), false).sampling_data,
+        cfg,
+    );
+    let stats = run_routine(label, cfg, &mut Plain(closure));
+    let ratio = stats.mean / reference.average;
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_relative(reference.average, ratio);
+    }
+    ratio
+}
 
-    #[test]
-    fn benches() {
-        let closure = || {
-            let mut sum = 0;
-            for _ in 0..100 {
-                sum += black_box(1);
-            }
-            assert_eq!(black_box(100), sum);
-        };
-        let cfg = BenchmarkConfig {
-            measurement_time: Duration::from_millis(10),
-            warm_up_time: Duration::from_millis(5),
-            ..BenchmarkConfig::default()
-        };
-        bench_with_configuration(&cfg, closure);
+/// Benches a control closure, the surrounding work without the operation, and a treatment closure,
+/// the same work with the operation, and reports the difference of their means as the cost of the
+/// operation alone. The difference is printed with its 95% confidence interval, neither closure is
+/// persisted. Returns the difference in nanoseconds per iteration.
+/// ```no_run
+/// use tiny_bench::{bench_subtracted, black_box};
+/// let values = vec![1u64; 1000];
+/// let cost_of_multiply = bench_subtracted("multiply", || {
+///     values.iter().map(|v| black_box(*v)).sum::<u64>()
+/// }, || {
+///     values.iter().map(|v| black_box(*v) * 3).sum::<u64>()
+/// });
+/// ```
+pub fn bench_subtracted<T, U, C: FnMut() -> T, F: FnMut() -> U>(
+    label: &'static str,
+    control: C,
+    treatment: F,
+) -> f64 {
+    bench_subtracted_with_configuration(label, &BenchmarkConfig::default(), control, treatment)
+}
+
+/// Like [`bench_subtracted`] with the supplied configuration
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_subtracted_with_configuration, black_box, BenchmarkConfig};
+/// let cost_of_add = bench_subtracted_with_configuration("add", &BenchmarkConfig::default(), || {
+///     black_box(1u64)
+/// }, || {
+///     black_box(1u64) + black_box(2u64)
+/// });
+/// ```
+pub fn bench_subtracted_with_configuration<T, U, C: FnMut() -> T, F: FnMut() -> U>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    control: C,
+    treatment: F,
+) -> f64 {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let control = analyze_sampling_data(
+        &measure(label, cfg, &mut Plain(control), false).sampling_data,
+        cfg,
+    );
+    let treatment = analyze_sampling_data(
+        &measure(label, cfg, &mut Plain(treatment), false).sampling_data,
+        cfg,
+    );
+    let difference = treatment.average - control.average;
+    // The means are independent, their variances add
+    let standard_error = (control.variance / control.per_sample_average.len() as f64
+        + treatment.variance / treatment.per_sample_average.len() as f64)
+        .sqrt();
+    let half_width =
+        normal_quantile(1.0 - crate::output::SIGNIFICANCE_LEVEL / 2.0) * standard_error;
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_subtracted(
+            label,
+            control.average,
+            treatment.average,
+            difference,
+            half_width,
+        );
+    }
+    difference
+}
+
+/// Benches the closure `runs` times and persists the least noisy run, the one with the lowest
+/// relative standard deviation of its samples, as the baseline that later runs are compared with.
+/// A single run may have been disturbed by something else on the machine, the best of a few
+/// makes for a cleaner reference. Prints every run and which one was selected, and returns the
+/// index of the selected run. The baseline is persisted even if `dump_results_to_disk` is false.
+/// # Panics
+/// If `runs` is 0, or if the label or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_establish_baseline, BenchmarkConfig};
+/// bench_establish_baseline("my_benchmark", &BenchmarkConfig::default(), 5, || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_establish_baseline<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    runs: usize,
+    closure: F,
+) -> usize {
+    assert!(
+        runs > 0,
+        "establishing a baseline requires at least one run"
+    );
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let mut routine = Plain(closure);
+    let mut measured = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let sampling_data = measure(label, cfg, &mut routine, false).sampling_data;
+        let analysis = analyze_sampling_data(&sampling_data, cfg);
+        let relative_stddev = analysis.stddev / analysis.average * 100.0;
+        measured.push((sampling_data, analysis.average, relative_stddev));
+    }
+    let selected = measured
+        .iter()
+        .enumerate()
+        .min_by(|(_, (_, _, a)), (_, (_, _, b))| a.total_cmp(b))
+        .map_or(0, |(index, _)| index);
+    let runs = measured
+        .iter()
+        .map(|(_, mean, relative_stddev)| (*mean, *relative_stddev))
+        .collect::<Vec<_>>();
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_baseline_runs(label, &runs, selected);
+    }
+    crate::output::write_last_sampling(label, cfg, &measured[selected].0);
+    selected
+}
+
+/// Benches the closure and checks its mean against an absolute target, for fixed latency
+/// requirements where the question is whether it's fast enough rather than whether it changed.
+/// Prints how far under or over the target the mean is and returns whether it's under.
+/// ```no_run
+/// use std::time::Duration;
+/// use tiny_bench::bench_against_target;
+/// let fast_enough = bench_against_target("my_benchmark", Duration::from_micros(5), || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_against_target<T, F: FnMut() -> T>(
+    label: &'static str,
+    target: Duration,
+    closure: F,
+) -> bool {
+    bench_against_target_configuration(label, &BenchmarkConfig::default(), target, closure)
+}
+
+/// Like [`bench_against_target`] with the supplied configuration
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use std::time::Duration;
+/// use tiny_bench::{bench_against_target_configuration, BenchmarkConfig};
+/// let fast_enough = bench_against_target_configuration("my_benchmark", &BenchmarkConfig::default(), Duration::from_micros(5), || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_against_target_configuration<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    target: Duration,
+    closure: F,
+) -> bool {
+    let stats = run_routine(label, cfg, &mut Plain(closure));
+    let target_ns = target.as_nanos() as f64;
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_target(stats.mean, target_ns);
+    }
+    stats.mean <= target_ns
+}
+
+/// Benches the closure like [`bench_against_target_configuration`] and panics if its mean is over
+/// the target, to guard a latency budget in a test
+/// ```no_run
+/// use std::time::Duration;
+/// use tiny_bench::{assert_bench_against_target, BenchmarkConfig};
+/// assert_bench_against_target("my_benchmark", &BenchmarkConfig::default(), Duration::from_micros(5), || {
+///     // Some code that should be benched
+/// });
+/// ```
+/// # Panics
+/// If the mean is over the target, or if the label or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+pub fn assert_bench_against_target<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    target: Duration,
+    closure: F,
+) {
+    assert!(
+        bench_against_target_configuration(label, cfg, target, closure),
+        "{label} is over its target of {}",
+        fmt_time(target.as_nanos() as f64)
+    );
+}
+
+/// Predicts how long benching the closure with the configuration would take, from a pilot warm up
+/// of a tenth of `warm_up_time` and the iterations that mean plans, without benching it.
+/// Includes the warm up and `settle_time`, capped by `hard_time_limit`. For deciding whether an
+/// expensive benchmark fits the time available, or tuning the configuration until it does
+/// ```no_run
+/// use std::time::Duration;
+/// use tiny_bench::{bench_with_configuration, estimate_duration, BenchmarkConfig};
+/// let cfg = BenchmarkConfig::default();
+/// let closure = || (0..1000u64).sum::<u64>();
+/// if estimate_duration(&cfg, closure) < Duration::from_secs(30) {
+///     bench_with_configuration(&cfg, closure);
+/// }
+/// ```
+pub fn estimate_duration<T, F: FnMut() -> T>(cfg: &BenchmarkConfig, closure: F) -> Duration {
+    let pilot = run_warm_up(&mut Plain(closure), cfg.warm_up_time / 10);
+    let mean = pilot.elapsed.as_nanos() as f64 / pilot.iterations as f64;
+    let (_, total_iters) = calculate_iters_and_total_iters(cfg, mean, cfg.num_samples as u64);
+    let estimate = cfg.warm_up_time
+        + cfg.settle_time.unwrap_or_default()
+        + nanos_to_duration(total_iters as f64 * mean);
+    cfg.hard_time_limit
+        .map_or(estimate, |limit| estimate.min(limit))
+}
+
+/// Benches the closure to estimate the smallest change of its mean, in percent, that comparing two
+/// runs with this configuration can reliably detect. `power` is the probability of detecting
+/// a change of that size, 0.8 is a common choice. Returns the estimate, and prints it if the
+/// output format is pretty, nothing is persisted. Use it to pick `num_samples` instead of guessing, the detectable change shrinks
+/// with the square root of the number of samples.
+/// ```no_run
+/// use tiny_bench::{estimate_minimum_detectable_effect, BenchmarkConfig};
+/// let percent = estimate_minimum_detectable_effect("my_benchmark", &BenchmarkConfig::default(), 0.8, || {
+///     // Some code that should be benched
+/// });
+/// ```
+/// # Panics
+/// If `power` isn't between 0 and 1, or if the label or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+pub fn estimate_minimum_detectable_effect<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    power: f64,
+    closure: F,
+) -> f64 {
+    assert!(
+        power > 0.0 && power < 1.0,
+        "power has to be between 0 and 1, was {power}"
+    );
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let measurement = measure(
+        label,
+        cfg,
+        &mut Plain(closure),
+        cfg.output_format.prints_progress(),
+    );
+    let analysis = analyze_sampling_data(&measurement.sampling_data, cfg);
+    let samples = analysis.per_sample_average.len();
+    let effect = crate::output::analysis::criterion::minimum_detectable_effect(
+        analysis.average,
+        analysis.variance,
+        samples,
+        crate::output::SIGNIFICANCE_LEVEL,
+        power,
+    );
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_minimum_detectable_effect(label, samples, effect, power);
+    }
+    effect
+}
+
+/// Benches the closure without analyzing, printing, or persisting anything, returning the mean
+/// time per iteration in nanoseconds for each sample in the order they were measured.
+/// This is the rawest useful output, for applying your own statistical methods.
+/// The label is only used in warnings.
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_collect, BenchmarkConfig};
+/// let per_sample_averages = bench_collect("my_benchmark", &BenchmarkConfig::default(), || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_collect<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    closure: F,
+) -> Vec<f64> {
+    let label = resolve_label(label, cfg);
+    measure(label, cfg, &mut Plain(closure), false)
+        .sampling_data
+        .per_sample_average()
+}
+
+/// Benches two closures against each other on the same inputs, for a fair A/B comparison of two
+/// implementations. Each sample generates one input with `setup` and runs both closures on it,
+/// alternating which closure runs first to cancel out systematic bias from running order.
+/// The difference is tested with a paired t-test which is more powerful than the unpaired test
+/// used when comparing against earlier runs. Results are not persisted.
+/// ```no_run
+/// use tiny_bench::bench_compare_with_setup;
+/// bench_compare_with_setup(
+///     "sorting",
+///     || vec![5, 3, 1, 4, 2],
+///     |v| {
+///         let mut v = v.clone();
+///         v.sort();
+///         v
+///     },
+///     |v| {
+///         let mut v = v.clone();
+///         v.sort_unstable();
+///         v
+///     },
+/// )
+/// ```
+pub fn bench_compare_with_setup<T, U, R, S, A, B>(
+    label: &'static str,
+    setup: S,
+    closure_a: A,
+    closure_b: B,
+) where
+    S: FnMut() -> R,
+    A: FnMut(&R) -> T,
+    B: FnMut(&R) -> U,
+{
+    bench_compare_with_setup_configuration(
+        label,
+        &BenchmarkConfig::default(),
+        setup,
+        closure_a,
+        closure_b,
+    );
+}
+
+/// Run `bench_compare_with_setup` with configuration
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_compare_with_setup_configuration, BenchmarkConfig};
+/// bench_compare_with_setup_configuration(
+///     "sum",
+///     &BenchmarkConfig::default(),
+///     || (0..100).collect::<Vec<u64>>(),
+///     |v| v.iter().sum::<u64>(),
+///     |v| v.iter().fold(0, |acc, i| acc + i),
+/// )
+/// ```
+pub fn bench_compare_with_setup_configuration<T, U, R, S, A, B>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    mut setup: S,
+    mut closure_a: A,
+    mut closure_b: B,
+) where
+    S: FnMut() -> R,
+    A: FnMut(&R) -> T,
+    B: FnMut(&R) -> U,
+{
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    outln!(
+        "{} warming up for {}",
+        wrap_bold_green(label),
+        wrap_high_intensity_white(&fmt_time(cfg.warm_up_time.as_nanos() as f64))
+    );
+    let wu = run_warm_up(
+        &mut Plain(|| {
+            let input = setup();
+            black_box(closure_a(&input));
+            black_box(closure_b(&input));
+        }),
+        cfg.warm_up_time,
+    );
+    let mean_execution_time = wu.elapsed.as_nanos() as f64 / wu.iterations as f64;
+    let (iters, total_iters) =
+        calculate_iters_and_total_iters(cfg, mean_execution_time, cfg.num_samples as u64);
+    outln!(
+        "{} mean warm up execution time {} running {} iterations of each closure",
+        wrap_bold_green(label),
+        wrap_high_intensity_white(&fmt_time(mean_execution_time)),
+        wrap_high_intensity_white(&fmt_num_with(total_iters as f64, cfg.number_format))
+    );
+    let mut times_a = Vec::with_capacity(iters.len());
+    let mut times_b = Vec::with_capacity(iters.len());
+    for (index, it_count) in iters.iter().copied().enumerate() {
+        let input = setup();
+        let mut measure_a = || {
+            let start = Instant::now();
+            for _ in 0..it_count {
+                black_box(closure_a(&input));
+            }
+            start.elapsed().as_nanos()
+        };
+        let mut measure_b = || {
+            let start = Instant::now();
+            for _ in 0..it_count {
+                black_box(closure_b(&input));
+            }
+            start.elapsed().as_nanos()
+        };
+        if index % 2 == 0 {
+            times_a.push(measure_a());
+            times_b.push(measure_b());
+        } else {
+            times_b.push(measure_b());
+            times_a.push(measure_a());
+        }
+    }
+    let data_a = SamplingData {
+        samples: iters.clone(),
+        times: times_a,
+        metrics: Vec::new(),
+    };
+    let data_b = SamplingData {
+        samples: iters,
+        times: times_b,
+        metrics: Vec::new(),
+    };
+    crate::output::print_paired_comparison(label, &data_a, &data_b, total_iters, cfg);
+}
+
+/// Analyzes and prints durations that were measured elsewhere, each duration is treated as a
+/// sample of a single iteration. Nothing is persisted.
+/// ```
+/// use std::time::Duration;
+/// use tiny_bench::report_durations;
+/// let durations = [15, 12, 17, 13, 14].map(Duration::from_micros);
+/// report_durations("measured_elsewhere", &durations);
+/// ```
+pub fn report_durations(label: &'static str, durations: &[Duration]) {
+    let label = fallback_to_anonymous_on_invalid_label(label);
+    if durations.is_empty() {
+        outln!(
+            "{} {}",
+            wrap_bold_green(label),
+            wrap_yellow("no durations to report")
+        );
+        return;
+    }
+    let sampling_data = SamplingData::from_durations(durations);
+    let cfg = BenchmarkConfig::default();
+    let analysis = analyze_sampling_data(&sampling_data, &cfg);
+    crate::output::SimpleStdout.dump_sampling_data(
+        label,
+        &sampling_data,
+        &analysis,
+        &cfg,
+        durations.len() as u128,
+    );
+}
+
+/// Whether the last persisted run of the label differs significantly from the one before it,
+/// `p <= 0.05`, regardless of whether the change is over the noise threshold. `None` if there
+/// aren't two runs to compare or they can't be read. Only reads, nothing is persisted
+/// ```no_run
+/// use tiny_bench::{bench_labeled, is_significantly_different};
+/// bench_labeled("my_benchmark", || {
+///     // Some code that should be benched
+/// });
+/// if is_significantly_different("my_benchmark") == Some(true) {
+///     println!("detectably changed");
+/// }
+/// ```
+#[must_use]
+pub fn is_significantly_different(label: &'static str) -> Option<bool> {
+    is_significantly_different_with_configuration(label, &BenchmarkConfig::default())
+}
+
+/// Like [`is_significantly_different`], with the configuration the benchmark was persisted with.
+/// With `GitBranch::CompareWith` the last run is compared with the last run of that branch
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+#[must_use]
+pub fn is_significantly_different_with_configuration(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+) -> Option<bool> {
+    let label = resolve_label(label, cfg);
+    let (own, separator) = persisted_label(label, cfg, Access::Write);
+    let (baseline, baseline_separator) = persisted_label(label, cfg, Access::Read);
+    let current = crate::output::disk::try_read_last_simpling(own, separator, cfg.codec).ok()??;
+    let baseline = if own == baseline {
+        crate::output::disk::try_read_old_simpling(own, separator, cfg.codec)
+    } else {
+        crate::output::disk::try_read_last_simpling(baseline, baseline_separator, cfg.codec)
+    }
+    .ok()??;
+    let comparison = crate::output::compare_sampling(
+        &analyze_sampling_data(&current, cfg),
+        &analyze_sampling_data(&baseline, cfg),
+        cfg,
+    );
+    Some(comparison.p <= crate::output::SIGNIFICANCE_LEVEL)
+}
+
+/// The samples of the label's run persisted under the run id, see `BenchmarkConfig::run_id`,
+/// `None` if there's no such run
+/// # Errors
+/// If the run can't be read
+/// ```no_run
+/// use tiny_bench::{bench_with_configuration_labeled, load_run, BenchmarkConfig};
+/// let cfg = BenchmarkConfig {
+///     run_id: Some("experiment-1"),
+///     ..BenchmarkConfig::default()
+/// };
+/// bench_with_configuration_labeled("my_benchmark", &cfg, || (0..100).sum::<u64>());
+/// let samples = load_run("my_benchmark", "experiment-1").unwrap().unwrap();
+/// ```
+pub fn load_run(
+    label: &'static str,
+    run_id: &'static str,
+) -> crate::error::Result<Option<Vec<SampleResult>>> {
+    load_run_with_configuration(label, &BenchmarkConfig::default(), run_id)
+}
+
+/// Like [`load_run`], with the configuration the run was persisted with
+/// # Errors
+/// If the run can't be read
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+pub fn load_run_with_configuration(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    run_id: &'static str,
+) -> crate::error::Result<Option<Vec<SampleResult>>> {
+    let Some(sampling_data) = read_run(label, cfg, run_id)? else {
+        return Ok(None);
+    };
+    Ok(Some(
+        crate::output::codec::from_sampling_data(&sampling_data).samples,
+    ))
+}
+
+/// Compares the label's run persisted under `run_id` with the one under `baseline_run_id`, like
+/// a run is compared with the last one. `None` if either run doesn't exist
+/// # Errors
+/// If either run can't be read
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{compare_runs, BenchmarkConfig};
+/// let comparison = compare_runs("my_benchmark", &BenchmarkConfig::default(), "treatment", "control");
+/// if let Ok(Some(comparison)) = comparison {
+///     println!("{:+.2}%", comparison.mean_change);
+/// }
+/// ```
+pub fn compare_runs(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    run_id: &'static str,
+    baseline_run_id: &'static str,
+) -> crate::error::Result<Option<RunComparison>> {
+    let (Some(run), Some(baseline)) = (
+        read_run(label, cfg, run_id)?,
+        read_run(label, cfg, baseline_run_id)?,
+    ) else {
+        return Ok(None);
+    };
+    Ok(Some(
+        crate::output::compare_sampling(
+            &analyze_sampling_data(&run, cfg),
+            &analyze_sampling_data(&baseline, cfg),
+            cfg,
+        )
+        .to_run_comparison(),
+    ))
+}
+
+fn read_run(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    run_id: &'static str,
+) -> crate::error::Result<Option<SamplingData>> {
+    let label = resolve_label(label, cfg);
+    let (label, separator) = crate::output::run_label(label, cfg, run_id);
+    crate::output::disk::try_read_last_simpling(label, separator, cfg.codec)
+}
+
+/// Fits a line through the means of all persisted runs of the label to tell whether it's slowly
+/// getting better or worse, which comparing with the last run can't. Prints and returns the report.
+/// ```no_run
+/// use tiny_bench::{regression_report, Trend};
+/// let report = regression_report("my_benchmark");
+/// assert_ne!(Trend::Regressing, report.trend);
+/// ```
+#[must_use]
+pub fn regression_report(label: &'static str) -> RegressionReport {
+    regression_report_with_configuration(label, &BenchmarkConfig::default())
+}
+
+/// Like [`regression_report`], with the configuration the benchmark was persisted with. The report
+/// is only printed if the output format is pretty
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{regression_report_with_configuration, BenchmarkConfig};
+/// let cfg = BenchmarkConfig {
+///     label_separator: Some("/"),
+///     ..BenchmarkConfig::default()
+/// };
+/// let report = regression_report_with_configuration("codec/encode", &cfg);
+/// println!("{}% per run", report.relative_slope);
+/// ```
+#[must_use]
+pub fn regression_report_with_configuration(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+) -> RegressionReport {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let (persisted, separator) = persisted_label(label, cfg, Access::Write);
+    let means = crate::output::disk::try_read_history(persisted, separator).unwrap_or_else(|e| {
+        outln!("{} {e}", wrap_yellow("Failed to read history, cause"));
+        Vec::new()
+    });
+    let report = RegressionReport::from_means(&means);
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_regression_report(label, &report, cfg.change_decimals);
+    }
+    report
+}
+
+fn run_routine<R: Routine>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    routine: &mut R,
+) -> BenchStats {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let Measurement {
+        sampling_data,
+        total_iters,
+        warm_up: wu,
+        estimated_mean_ns: mean_execution_time,
+        paused,
+        cold_start,
+        iteration_extremes,
+        #[cfg(feature = "linux-rusage")]
+        usage,
+    } = measure(label, cfg, routine, cfg.output_format.prints_progress());
+    let analysis = analyze_sampling_data(&sampling_data, cfg);
+    let comparison = dump(label, cfg, &sampling_data, &analysis, total_iters);
+    // Extra lines would break machine readable formats
+    if let (Some(cold_start), OutputFormat::Pretty) = (cold_start, cfg.output_format) {
+        crate::output::print_cold_start(cold_start, analysis.average);
+    }
+    if let (Some((min, max)), OutputFormat::Pretty) = (iteration_extremes, cfg.output_format) {
+        crate::output::print_iteration_extremes(min, max);
+    }
+    if paused > Duration::ZERO && cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_paused(paused);
+    }
+    if let (Some(tolerance), OutputFormat::Pretty) = (cfg.convergence_tolerance, cfg.output_format)
+    {
+        crate::output::print_convergence(&sampling_data.per_sample_average(), tolerance);
+    }
+    if let (Some(width), OutputFormat::Pretty) = (cfg.plot_width, cfg.output_format) {
+        if crate::output::stream::is_terminal() {
+            crate::output::print_plot(&sampling_data.per_sample_average(), width);
+        }
+    }
+    #[cfg(feature = "linux-rusage")]
+    if let (Some(usage), OutputFormat::Pretty) = (usage, &cfg.output_format) {
+        crate::output::print_usage(
+            usage.involuntary_context_switches,
+            usage.major_page_faults,
+            total_iters,
+        );
+    }
+    let stats = summarize(
+        cfg,
+        &sampling_data,
+        &analysis,
+        total_iters,
+        (wu.iterations, wu.elapsed),
+        mean_execution_time,
+        cold_start,
+    );
+    record_result(label, cfg, &stats, comparison);
+    stats
+}
+
+/// Re-analyzes the last persisted run of the label under the configuration, without running the
+/// benchmark again. For iterating on how the data is interpreted, like the `aggregation` or the
+/// number of resamples, after paying for collecting it once.
+/// Printed like a run with `dump_results_to_disk` turned off if the output format is pretty,
+/// nothing is persisted. The warm up isn't persisted, its statistics are 0.
+/// `None` if the label has no persisted run
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{replay, Aggregation, BenchmarkConfig};
+/// let cfg = BenchmarkConfig {
+///     aggregation: Aggregation::Median,
+///     ..BenchmarkConfig::default()
+/// };
+/// if let Some(stats) = replay("my_benchmark", &cfg).unwrap() {
+///     println!("median {}ns", stats.median);
+/// }
+/// ```
+/// # Errors
+/// If the persisted run can't be read
+pub fn replay(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+) -> crate::error::Result<Option<BenchStats>> {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let (persisted, separator) = persisted_label(label, cfg, Access::Write);
+    let Some(sampling_data) =
+        crate::output::disk::try_read_last_simpling(persisted, separator, cfg.codec)?
+    else {
+        return Ok(None);
+    };
+    let analysis = analyze_sampling_data(&sampling_data, cfg);
+    let total_iters = sampling_data.total_iterations();
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::SimpleStdout.dump_sampling_data(
+            label,
+            &sampling_data,
+            &analysis,
+            cfg,
+            total_iters,
+        );
+    }
+    Ok(Some(summarize(
+        cfg,
+        &sampling_data,
+        &analysis,
+        total_iters,
+        (0, Duration::ZERO),
+        0.0,
+        None,
+    )))
+}
+
+/// Bootstraps the confidence interval of the mean and gathers the statistics of a run
+fn summarize(
+    cfg: &BenchmarkConfig,
+    sampling_data: &SamplingData,
+    analysis: &SamplingDataSimpleAnalysis,
+    total_iters: u128,
+    (warmup_iterations, warmup_elapsed): (u128, Duration),
+    estimated_mean_ns: f64,
+    cold_start: Option<Duration>,
+) -> BenchStats {
+    let mut per_sample_average = sampling_data.per_sample_average();
+    if analysis.outliers.filtered {
+        // The filter keeps the averages within its fences, keep the same ones in measured order
+        if let (Some(&low), Some(&high)) = (
+            analysis.per_sample_average.first(),
+            analysis.per_sample_average.last(),
+        ) {
+            per_sample_average.retain(|average| (low..=high).contains(average));
+        }
+    }
+    let num_resamples = cfg.num_resamples.count(per_sample_average.len());
+    let mean_confidence_interval = (num_resamples > 0).then(|| {
+        // The first streams of the seed shuffle inputs and compare with the last run
+        let mut rng = Rng::from_seed_or_time(cfg.seed);
+        let _shuffle = rng.split();
+        let _compare = rng.split();
+        bootstrap_mean_interval(
+            &mut rng,
+            &per_sample_average,
+            num_resamples,
+            MEAN_CONFIDENCE,
+        )
+    });
+    if let (Some((low, high)), OutputFormat::Pretty) = (mean_confidence_interval, cfg.output_format)
+    {
+        crate::output::print_mean_interval(low, high, MEAN_CONFIDENCE);
+    }
+    let resolution = clock_resolution();
+    // Extra lines would break machine readable formats
+    if analysis.average < resolution.as_nanos() as f64 && cfg.output_format == OutputFormat::Pretty
+    {
+        outln!(
+            "{} mean {} is below the clock resolution of {}, individual iterations can't be resolved and only the total time of each sample is meaningful",
+            wrap_yellow("Warning:"),
+            fmt_time(analysis.average),
+            fmt_time(resolution.as_nanos() as f64),
+        );
+    }
+    BenchStats {
+        total_iters,
+        elapsed: analysis.elapsed,
+        mean: analysis.average,
+        median: analysis.median,
+        min: analysis.min,
+        max: analysis.max,
+        variance: analysis.variance,
+        stddev: analysis.stddev,
+        per_sample_average,
+        warmup_iterations,
+        warmup_elapsed,
+        estimated_mean_ns,
+        mean_confidence_interval,
+        metric_per_iteration: sampling_data.metric_per_iteration(),
+        cold_start,
+        throughput_per_sec: 1_000_000_000.0 / analysis.average,
+    }
+}
+
+/// Records the result in the process wide collector, and prints the speedup relative to the
+/// first result if the session reports that
+fn record_result(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    stats: &BenchStats,
+    comparison: Option<RunComparison>,
+) {
+    let mut results = RESULTS.lock().unwrap_or_else(PoisonError::into_inner);
+    if RELATIVE_TO_FIRST.load(Ordering::Relaxed) && cfg.output_format == OutputFormat::Pretty {
+        match results.first() {
+            Some(first) => {
+                crate::output::print_session_relative(first.label, stats.mean / first.stats.mean);
+            }
+            None => crate::output::print_session_reference(),
+        }
+    }
+    results.push(BenchResult {
+        label,
+        stats: stats.clone(),
+        comparison,
+    });
+}
+
+/// Prints the results in the configured format, and returns the comparison with the last
+/// persisted run if there was one
+fn dump(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    sampling_data: &SamplingData,
+    analysis: &SamplingDataSimpleAnalysis,
+    total_iters: u128,
+) -> Option<RunComparison> {
+    match cfg.output_format {
+        OutputFormat::Pretty if cfg.dump_results_to_disk => crate::output::ComparedStdout
+            .dump_sampling_data(label, sampling_data, analysis, cfg, total_iters),
+        OutputFormat::Pretty => crate::output::SimpleStdout.dump_sampling_data(
+            label,
+            sampling_data,
+            analysis,
+            cfg,
+            total_iters,
+        ),
+        #[cfg(feature = "tap")]
+        OutputFormat::Tap => crate::output::tap::TapStdout.dump_sampling_data(
+            label,
+            sampling_data,
+            analysis,
+            cfg,
+            total_iters,
+        ),
+        #[cfg(feature = "criterion-json")]
+        OutputFormat::CriterionJson => crate::output::criterion_json::CriterionJsonStdout
+            .dump_sampling_data(label, sampling_data, analysis, cfg, total_iters),
+        OutputFormat::DiffText => crate::output::diff_text::DiffTextStdout.dump_sampling_data(
+            label,
+            sampling_data,
+            analysis,
+            cfg,
+            total_iters,
+        ),
+        OutputFormat::Json => crate::output::json::JsonStdout.dump_sampling_data(
+            label,
+            sampling_data,
+            analysis,
+            cfg,
+            total_iters,
+        ),
+    }
+}
+
+/// Confidence level of `BenchStats::mean_confidence_interval`
+const MEAN_CONFIDENCE: f64 = 0.95;
+
+/// Every benchmark run in this process, see [`take_results`]
+static RESULTS: Mutex<Vec<BenchResult>> = Mutex::new(Vec::new());
+
+/// Takes the results of all benchmarks run in this process so far, leaving none behind.
+/// Every `bench*` function records into a process wide collector, so that a whole suite can be
+/// summarized or exported at the end without passing anything around.
+/// ```no_run
+/// use tiny_bench::{bench_labeled, take_results};
+/// bench_labeled("a", || 1 + 1);
+/// bench_labeled("b", || 2 + 2);
+/// for result in take_results() {
+///     println!("{} {}ns", result.label, result.stats.mean);
+/// }
+/// ```
+#[must_use]
+pub fn take_results() -> Vec<BenchResult> {
+    std::mem::take(&mut *RESULTS.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Labels of the results collected so far, without taking them. Tests run in parallel and share
+/// the collector, taking the results would take those of other tests too
+#[cfg(test)]
+fn collected_labels() -> Vec<&'static str> {
+    RESULTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|result| result.label)
+        .collect()
+}
+
+/// Whether results are reported relative to the first one, see [`report_relative_to_first`]
+static RELATIVE_TO_FIRST: AtomicBool = AtomicBool::new(false);
+
+/// Reports every following benchmark as a speedup relative to the first one run in this process,
+/// which is the implicit `1.00×` reference. For comparing alternatives in one process, like
+/// `0.72× (28% faster)`. The reference is the first of the results collected, so it restarts
+/// after [`take_results`]. Only printed by the pretty output.
+/// ```no_run
+/// use tiny_bench::{bench_labeled, report_relative_to_first};
+/// report_relative_to_first(true);
+/// bench_labeled("linear_search", || (0..100).find(|i| *i == 50));
+/// bench_labeled("binary_search", || (0..100).collect::<Vec<_>>().binary_search(&50));
+/// ```
+pub fn report_relative_to_first(enabled: bool) {
+    RELATIVE_TO_FIRST.store(enabled, Ordering::Relaxed);
+}
+
+/// Results exported from a benchmark session, see [`export_session`]
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Hostname of the machine the session ran on
+    pub machine: String,
+    /// When the session was exported, in seconds since the Unix epoch
+    pub exported_at_secs: u64,
+    /// The results in the order the benchmarks ran
+    pub results: Vec<BenchResult>,
+}
+
+impl Session {
+    /// Prints one line per benchmark, like [`print_summary`]
+    pub fn print_summary(&self) {
+        out!("{}", crate::output::fmt_summary(&self.results));
+    }
+}
+
+/// Writes every result collected in this process so far, see [`take_results`], to a single file
+/// with the name of the machine and the time, for archiving or comparing with someone else's
+/// run. The file is versioned and checksummed like the persisted results. The results are kept
+/// for [`take_results`].
+/// # Errors
+/// If the file can't be written
+/// ```no_run
+/// use tiny_bench::{bench_labeled, export_session, import_session};
+/// bench_labeled("my_benchmark", || (0..100).sum::<u64>());
+/// export_session("session.bin").unwrap();
+/// import_session("session.bin").unwrap().print_summary();
+/// ```
+pub fn export_session(path: impl AsRef<std::path::Path>) -> crate::error::Result<()> {
+    let path = path.as_ref();
+    let session = Session {
+        machine: machine_name(),
+        exported_at_secs: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs()),
+        results: RESULTS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone(),
+    };
+    std::fs::write(path, crate::output::ser::ser_session(&session)).map_err(|e| {
+        crate::Error::new(format!(
+            "Failed to export session to {}, cause {e}",
+            path.display()
+        ))
+    })
+}
+
+/// What [`commit_session`] wrote
+#[derive(Debug)]
+pub struct CommitManifest {
+    /// Labels whose run was written, nested labels separated by `/`, in the order they ran
+    pub written: Vec<String>,
+    /// Labels whose run couldn't be written, with why
+    pub failed: Vec<(String, crate::Error)>,
+}
+
+/// Writes every run held back by `Persist::OnCommit` since the last commit, rotating each
+/// label's persisted run to its baseline once, and returns what was written.
+/// Gives tooling a single point where a session's results are committed, rather than each run
+/// writing as it goes, which makes a label benched twice in a run compare with itself
+/// ```no_run
+/// use tiny_bench::{bench_with_configuration_labeled, commit_session, BenchmarkConfig, Persist};
+/// let cfg = BenchmarkConfig {
+///     persist: Persist::OnCommit,
+///     ..BenchmarkConfig::default()
+/// };
+/// bench_with_configuration_labeled("my_benchmark", &cfg, || (0..100).sum::<u64>());
+/// let manifest = commit_session();
+/// assert!(manifest.failed.is_empty());
+/// ```
+#[must_use = "runs that couldn't be written are only reported in the manifest"]
+pub fn commit_session() -> CommitManifest {
+    crate::output::commit_pending()
+}
+
+/// Reads a session written by [`export_session`]
+/// # Errors
+/// If the file can't be read or isn't a valid session
+pub fn import_session(path: impl AsRef<std::path::Path>) -> crate::error::Result<Session> {
+    let path = path.as_ref();
+    let data = std::fs::read(path).map_err(|e| {
+        crate::Error::new(format!(
+            "Failed to read session from {}, cause {e}",
+            path.display()
+        ))
+    })?;
+    crate::output::ser::try_de_session(&data)
+}
+
+/// Prints one line per benchmark run in this process so far, with its mean and the change from
+/// its last persisted run. Call it once after all benchmarks have run, the results are kept for
+/// [`take_results`].
+pub fn print_summary() {
+    let results = RESULTS.lock().unwrap_or_else(PoisonError::into_inner);
+    out!("{}", crate::output::fmt_summary(&results));
+}
+
+/// Everything a benchmark measured, before any analysis
+struct Measurement {
+    sampling_data: SamplingData,
+    total_iters: u128,
+    warm_up: WarmupResults,
+    estimated_mean_ns: f64,
+    /// Time spent pausing between samples, outside of the measurement
+    paused: Duration,
+    /// Time of the first call, if measured, see `ColdStart::Measure`
+    cold_start: Option<Duration>,
+    /// Nanos of the fastest and slowest iteration, if timed, see `Extremes::Iteration`
+    iteration_extremes: Option<(u128, u128)>,
+    /// Counters accumulated while sampling, if they could be read
+    #[cfg(feature = "linux-rusage")]
+    usage: Option<rusage::Usage>,
+}
+
+/// How much the mean of a short warm up may differ from a recent baseline, in percent, for the
+/// baseline to be used instead of a full warm up
+const BASELINE_TOLERANCE: f64 = 25.0;
+
+/// Warms up, returning the results and the mean execution time to plan the samples with.
+/// The mean comes from a recent persisted run instead if it's configured and confirmed
+fn warm_up<R: Routine>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    routine: &mut R,
+    verbose: bool,
+) -> (WarmupResults, f64) {
+    if let Some(baseline_mean) = recent_baseline_mean(label, cfg) {
+        let quick = run_warm_up(routine, cfg.warm_up_time / 10);
+        let quick_mean = quick.elapsed.as_nanos() as f64 / quick.iterations as f64;
+        if ((quick_mean / baseline_mean - 1.0) * 100.0).abs() <= BASELINE_TOLERANCE {
+            if verbose {
+                outln!(
+                    "{} reusing mean {} of the last run, confirmed by a warm up of {}",
+                    wrap_bold_green(label),
+                    wrap_high_intensity_white(&fmt_time(baseline_mean)),
+                    fmt_time(quick.elapsed.as_nanos() as f64)
+                );
+            }
+            return (quick, baseline_mean);
+        }
+        if verbose {
+            outln!(
+                "{} mean {} differs from {} of the last run, warming up fully",
+                wrap_bold_green(label),
+                fmt_time(quick_mean),
+                fmt_time(baseline_mean)
+            );
+        }
+    }
+    if verbose {
+        outln!(
+            "{} warming up for {}",
+            wrap_bold_green(label),
+            wrap_high_intensity_white(&fmt_time(cfg.warm_up_time.as_nanos() as f64))
+        );
+    }
+    let mut wu = run_warm_up(routine, cfg.warm_up_time);
+    if wu.suspected_elision && cfg.elision_check == ElisionCheck::Warn {
+        outln!(
+            "{} {} the time per iteration kept halving as warm up batches doubled, the benched code may have been optimized away and the benchmark may be measuring nothing. Pass inputs through `black_box` and use the result",
+            wrap_bold_green(label),
+            wrap_yellow("Warning:")
+        );
+    }
+    if let Some(check) = cfg.warm_up_check {
+        wu = check_warm_up(label, cfg, check, routine, wu);
+    }
+    let mean_execution_time = wu.elapsed.as_nanos() as f64 / wu.iterations as f64;
+    (wu, mean_execution_time)
+}
+
+/// The mean of the last persisted run if it's recent enough to reuse, see
+/// `BenchmarkConfig::reuse_recent_baseline`
+fn recent_baseline_mean(label: &'static str, cfg: &BenchmarkConfig) -> Option<f64> {
+    let max_age = cfg.reuse_recent_baseline?;
+    if !cfg.dump_results_to_disk {
+        return None;
+    }
+    // This branch's own run, reusing another branch's would skip measuring the changes
+    let (label, separator) = persisted_label(label, cfg, Access::Write);
+    let age = crate::output::disk::try_last_simpling_age(label, separator).ok()??;
+    if age > max_age {
+        return None;
+    }
+    let last = crate::output::disk::try_read_last_simpling(label, separator, cfg.codec).ok()??;
+    Some(analyze_sampling_data(&last, cfg).average)
+}
+
+/// Warms up and runs all samples, printing progress if `verbose`
+fn measure<R: Routine>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    routine: &mut R,
+    verbose: bool,
+) -> Measurement {
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    // Held until measured, before the load check since waiting for the resource changes the load
+    let _exclusive = cfg.exclusive_resource.map(exclusive::lock);
+    if let Some(load_check) = cfg.load_check {
+        load::check(label, load_check);
+    }
+    let _pin = cfg.pin_to_core.and_then(|core| {
+        affinity::pin_current_thread(core)
+            .map_err(|e| {
+                outln!(
+                    "{} {core}, cause {e}, continuing unpinned",
+                    wrap_yellow("Failed to pin to core")
+                );
+            })
+            .ok()
+    });
+    environment::check_build(label, cfg.debug_build, verbose);
+    if verbose {
+        crate::output::print_environment_once();
+    }
+    let deadline = cfg.hard_time_limit.map(|limit| Instant::now() + limit);
+    let cold_start = (cfg.cold_start == ColdStart::Measure)
+        .then(|| Duration::from_nanos(u64::try_from(routine.sample(1)).unwrap_or(u64::MAX)));
+    let (wu, mean_execution_time) = warm_up(label, cfg, routine, verbose);
+    if let Some(settle_time) = cfg.settle_time {
+        cfg.settle_by.settle(settle_time);
+        if verbose {
+            outln!(
+                "{} settled for {} ({:?})",
+                wrap_bold_green(label),
+                fmt_time(settle_time.as_nanos() as f64),
+                cfg.settle_by
+            );
+        }
+    }
+    let sample_size = cfg.num_samples as u64;
+    let (iters, total_iters) =
+        calculate_iters_and_total_iters(cfg, mean_execution_time, sample_size);
+
+    if verbose {
+        outln!(
+            "{} mean warm up execution time {} running {} iterations",
+            wrap_bold_green(label),
+            wrap_high_intensity_white(&fmt_time(mean_execution_time)),
+            wrap_high_intensity_white(&fmt_num_with(total_iters as f64, cfg.number_format))
+        );
+    }
+    #[cfg(feature = "linux-rusage")]
+    let usage_before = rusage::read();
+    let planned_samples = iters.len();
+    let (mut sampling_data, paused, iteration_extremes, stopped) =
+        run(iters, routine, cfg, deadline);
+    #[cfg(feature = "linux-rusage")]
+    let usage = rusage::read()
+        .zip(usage_before)
+        .map(|(after, before)| after.since(before));
+    let mut total_iters = total_iters;
+    if stopped && sampling_data.samples.len() < planned_samples {
+        outln!(
+            "{} on_sample stopped {label}, analyzing {} of {planned_samples} samples",
+            wrap_yellow("Truncated:"),
+            sampling_data.samples.len()
+        );
+        total_iters = sampling_data.total_iterations();
+    } else if sampling_data.samples.len() < planned_samples {
+        outln!(
+            "{} {label} exceeded the hard time limit of {}, analyzing {} of {planned_samples} samples",
+            wrap_yellow("Truncated:"),
+            fmt_time(cfg.hard_time_limit.unwrap_or_default().as_nanos() as f64),
+            sampling_data.samples.len()
+        );
+        total_iters = sampling_data.total_iterations();
+    }
+    if cfg.discard_first_samples > 0 {
+        if cfg.discard_first_samples < sampling_data.samples.len() {
+            sampling_data = sampling_data.discard_first(cfg.discard_first_samples);
+            total_iters = sampling_data.total_iterations();
+        } else {
+            outln!(
+                "{} discard_first_samples = {} leaves no samples to analyze, keeping all {}",
+                wrap_yellow("Not discarding samples:"),
+                cfg.discard_first_samples,
+                sampling_data.samples.len()
+            );
+        }
+    }
+    if sampling_data.times.contains(&0) {
+        sampling_data = handle_zero_time_samples(label, cfg, sampling_data);
+        total_iters = sampling_data.total_iterations();
+    }
+    Measurement {
+        sampling_data,
+        total_iters,
+        warm_up: wu,
+        estimated_mean_ns: mean_execution_time,
+        paused,
+        cold_start,
+        iteration_extremes,
+        #[cfg(feature = "linux-rusage")]
+        usage,
+    }
+}
+
+/// Warns about samples that took no measurable time, and discards them if configured to
+fn handle_zero_time_samples(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    sampling_data: SamplingData,
+) -> SamplingData {
+    let samples = sampling_data.times.len();
+    let zero_time = sampling_data
+        .times
+        .iter()
+        .filter(|&&time| time == 0)
+        .count();
+    let discard = cfg.zero_time_samples == ZeroTimeSamples::Discard && samples - zero_time >= 2;
+    outln!(
+        "{} {zero_time} of {samples} samples of {label} took no measurable time, {}. The clock may be too coarse or the work optimized away",
+        wrap_yellow("Warning:"),
+        if discard { "discarding them" } else { "keeping them" }
+    );
+    if discard {
+        sampling_data.without_zero_time()
+    } else {
+        sampling_data
+    }
+}
+
+fn calculate_iters_and_total_iters(
+    cfg: &BenchmarkConfig,
+    mut mean_execution_time_ns: f64,
+    sample_size: u64,
+) -> (Vec<u64>, u128) {
+    if let Some(max_it) = cfg
+        .max_iterations
+        .filter(|_| cfg.stop_when != StopWhen::FixedSamples)
+    {
+        (vec![max_it], u128::from(max_it))
+    } else {
+        // This can be arbitrarily small, causing an absurd amount of iterations.
+        // Raise it to 1 nano -> max 5B iterations
+        mean_execution_time_ns = mean_execution_time_ns.max(1.0);
+        let mut iters = calculate_iterations(
+            mean_execution_time_ns,
+            sample_size,
+            cfg.measurement_time,
+            cfg.stop_when,
+        );
+        // The first sample runs the step of the ramp, raising it raises every sample
+        if iters
+            .first()
+            .is_some_and(|&step| step < cfg.min_iterations_per_sample)
+        {
+            iters = (1..=sample_size)
+                .map(|sample| sample.saturating_mul(cfg.min_iterations_per_sample))
+                .collect();
+        }
+        let mut total_iters = 0u128;
+        for count in iters.iter().copied() {
+            total_iters = total_iters.saturating_add(u128::from(count));
+        }
+        (iters, total_iters)
+    }
+}
+
+/// Runs all samples, returning them, the time spent pausing between them, the fastest and
+/// slowest iteration if `Extremes::Iteration` is configured, and whether `on_sample` stopped it.
+/// Stops early, between samples, once `deadline` has passed or `on_sample` breaks, at least two
+/// samples are always collected so that there's something to analyze
+fn run<R: Routine>(
+    mut sample_sizes: Vec<u64>,
+    routine: &mut R,
+    cfg: &BenchmarkConfig,
+    deadline: Option<Instant>,
+) -> (SamplingData, Duration, Option<(u128, u128)>, bool) {
+    let mut paused = Duration::ZERO;
+    let mut extremes = None;
+    let mut stopped = false;
+    let mut times = Vec::with_capacity(sample_sizes.len());
+    let mut metrics = Vec::new();
+    // Whatever accumulated while warming up isn't part of any sample
+    let _ = routine.take_metric();
+    for (index, it_count) in sample_sizes.iter().copied().enumerate() {
+        if index > 0 {
+            if index >= 2
+                && (stopped || deadline.is_some_and(|deadline| Instant::now() >= deadline))
+            {
+                break;
+            }
+            if let Some(between_samples) = &cfg.between_samples {
+                between_samples();
+            }
+            paused += cfg.pause_between_samples.pause();
+        }
+        let elapsed_nanos = match cfg.extremes {
+            Extremes::SampleAverage => routine.sample(it_count),
+            Extremes::Iteration => routine.sample_timed_iterations(it_count, &mut extremes),
+        };
+        if let Some(metric) = routine.take_metric() {
+            metrics.push(metric);
+        }
+        let sample = SampleResult {
+            index,
+            iterations: it_count,
+            elapsed_nanos,
+        };
+        cfg.stream_samples.emit(&sample);
+        if let Some(on_sample) = &cfg.on_sample {
+            stopped |= on_sample(sample).is_break();
+        }
+        times.push(elapsed_nanos);
+    }
+    sample_sizes.truncate(times.len());
+    (
+        SamplingData {
+            samples: sample_sizes,
+            times,
+            metrics,
+        },
+        paused,
+        extremes,
+        stopped,
+    )
+}
+
+/// Times a single iteration, widening `extremes` to include it
+fn time_iteration<T>(
+    extremes: &mut Option<(u128, u128)>,
+    iteration: impl FnOnce() -> T,
+) -> Duration {
+    let start = Instant::now();
+    black_box(iteration());
+    let elapsed = start.elapsed();
+    let nanos = elapsed.as_nanos();
+    let (min, max) = extremes.get_or_insert((nanos, nanos));
+    *min = (*min).min(nanos);
+    *max = (*max).max(nanos);
+    elapsed
+}
+
+/// The smallest observed difference between two readings of the clock, measured once per process
+fn clock_resolution() -> Duration {
+    static RESOLUTION: OnceLock<Duration> = OnceLock::new();
+    *RESOLUTION.get_or_init(|| {
+        let mut resolution = Duration::MAX;
+        for _ in 0..100 {
+            let start = Instant::now();
+            let mut now = Instant::now();
+            while now == start {
+                now = Instant::now();
+            }
+            resolution = resolution.min(now.duration_since(start));
+        }
+        resolution
+    })
+}
+
+/// What differs between the kinds of benchmarks, how to warm up and how to time a sample
+trait Routine {
+    /// Runs `iterations` iterations, the elapsed time is only used to estimate execution time
+    fn warm_up(&mut self, iterations: u64) -> Duration;
+
+    /// Runs `iterations` iterations and returns the elapsed nanos spent in the benched code
+    fn sample(&mut self, iterations: u64) -> u128;
+
+    /// Like `sample`, as one sample with the same setup and teardown, but times every iteration
+    /// on its own, widening `extremes` to include them, see `Extremes::Iteration`
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128;
+
+    /// The secondary metric accumulated since the last call, `None` if the routine doesn't
+    /// measure one
+    fn take_metric(&mut self) -> Option<u64> {
+        None
+    }
+}
+
+struct Plain<F>(F);
+
+impl<T, F: FnMut() -> T> Routine for Plain<F> {
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            (self.0)();
+        }
+        start.elapsed()
+    }
+
+    fn sample(&mut self, iterations: u64) -> u128 {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box((self.0)());
+        }
+        start.elapsed().as_nanos()
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..iterations {
+            elapsed += time_iteration(extremes, &mut self.0);
+        }
+        elapsed.as_nanos()
+    }
+}
+
+/// Accumulates what the closure returns as the secondary metric
+struct WithMetric<F> {
+    closure: F,
+    metric: u64,
+}
+
+impl<F: FnMut() -> u64> Routine for WithMetric<F> {
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            self.metric = self.metric.saturating_add((self.closure)());
+        }
+        start.elapsed()
+    }
+
+    fn sample(&mut self, iterations: u64) -> u128 {
+        let start = Instant::now();
+        for _ in 0..iterations {
+            self.metric = self.metric.saturating_add(black_box((self.closure)()));
+        }
+        start.elapsed().as_nanos()
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..iterations {
+            let mut metric = 0;
+            elapsed += time_iteration(extremes, || metric = (self.closure)());
+            self.metric = self.metric.saturating_add(metric);
+        }
+        elapsed.as_nanos()
+    }
+
+    fn take_metric(&mut self) -> Option<u64> {
+        Some(std::mem::take(&mut self.metric))
+    }
+}
+
+struct WithSetup<S, F> {
+    setup: S,
+    closure: F,
+}
+
+impl<T, R, F: FnMut(R) -> T, S: FnMut() -> R> Routine for WithSetup<S, F> {
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        // Same batching as when measuring, so that the estimate doesn't include the setup
+        Duration::from_nanos(self.sample(iterations) as u64)
+    }
+
+    fn sample(&mut self, it_count: u64) -> u128 {
+        let closure = &mut self.closure;
+        sample_in_batches(it_count, &mut self.setup, |inputs| {
+            let start = Instant::now();
+            for i in inputs {
+                black_box(closure(i));
+            }
+            start.elapsed()
+        })
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        it_count: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        let closure = &mut self.closure;
+        sample_in_batches(it_count, &mut self.setup, |inputs| {
+            inputs
+                .into_iter()
+                .map(|i| time_iteration(extremes, || closure(i)))
+                .sum()
+        })
+    }
+}
+
+/// A function taking a reference to its input and returning something that may borrow from it,
+/// like a zero-copy parser returning a view into its input.
+/// Implemented for everything `FnMut(&R) -> T` that accepts any lifetime. The compiler can't
+/// infer that for closures returning borrowed data, pass a function for those.
+pub trait BorrowingFn<'a, R: 'a> {
+    /// The result, which may borrow from the input
+    type Output: 'a;
+
+    /// Calls the closure
+    fn call(&mut self, input: &'a R) -> Self::Output;
+}
+
+impl<'a, R: 'a, T: 'a, F: FnMut(&'a R) -> T> BorrowingFn<'a, R> for F {
+    type Output = T;
+
+    fn call(&mut self, input: &'a R) -> T {
+        self(input)
+    }
+}
+
+struct WithSetupBorrowing<S, F> {
+    setup: S,
+    closure: F,
+}
+
+impl<R, F: for<'a> BorrowingFn<'a, R>, S: FnMut() -> R> Routine for WithSetupBorrowing<S, F> {
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        Duration::from_nanos(self.sample(iterations) as u64)
+    }
+
+    fn sample(&mut self, it_count: u64) -> u128 {
+        let closure = &mut self.closure;
+        sample_in_batches(it_count, &mut self.setup, |inputs| {
+            let start = Instant::now();
+            for i in &inputs {
+                black_box(closure.call(i));
+            }
+            let elapsed = start.elapsed();
+            // Inputs are torn down after the borrowed results, outside of the measurement
+            drop(inputs);
+            elapsed
+        })
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        it_count: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        let closure = &mut self.closure;
+        sample_in_batches(it_count, &mut self.setup, |inputs| {
+            let elapsed = inputs
+                .iter()
+                .map(|i| time_iteration(extremes, || closure.call(i)))
+                .sum();
+            drop(inputs);
+            elapsed
+        })
+    }
+}
+
+/// Runs the setup for each iteration ahead of measuring, in batches to bound memory use,
+/// and sums the time `measure` reports for each batch
+fn sample_in_batches<R>(
+    it_count: u64,
+    setup: &mut impl FnMut() -> R,
+    mut measure: impl FnMut(Vec<R>) -> Duration,
+) -> u128 {
+    const BATCH_SIZE: usize = 10_000;
+    if it_count < BATCH_SIZE as u64 {
+        let inputs = (0..it_count).map(|_| setup()).collect::<Vec<_>>();
+        measure(inputs).as_nanos()
+    } else {
+        let mut elapsed = Duration::ZERO;
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        for _ in 0..it_count {
+            batch.push(setup());
+            if batch.len() >= BATCH_SIZE {
+                elapsed += measure(std::mem::replace(
+                    &mut batch,
+                    Vec::with_capacity(BATCH_SIZE),
+                ));
+            }
+        }
+        if !batch.is_empty() {
+            elapsed += measure(batch);
+        }
+        elapsed.as_nanos()
+    }
+}
+
+struct WithSampleSetup<S, F, D> {
+    setup: S,
+    closure: F,
+    teardown: D,
+}
+
+impl<T, R, S: FnMut() -> R, F: FnMut(&mut R) -> T, D: FnMut(R)> Routine
+    for WithSampleSetup<S, F, D>
+{
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        let mut state = (self.setup)();
+        let start = Instant::now();
+        for _ in 0..iterations {
+            (self.closure)(&mut state);
+        }
+        let elapsed = start.elapsed();
+        (self.teardown)(state);
+        elapsed
+    }
+
+    fn sample(&mut self, iterations: u64) -> u128 {
+        let mut state = (self.setup)();
+        let start = Instant::now();
+        for _ in 0..iterations {
+            black_box((self.closure)(&mut state));
+        }
+        let elapsed = start.elapsed().as_nanos();
+        (self.teardown)(state);
+        elapsed
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        let mut state = (self.setup)();
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..iterations {
+            elapsed += time_iteration(extremes, || (self.closure)(&mut state));
+        }
+        (self.teardown)(state);
+        elapsed.as_nanos()
+    }
+}
+
+struct OverInputs<'a, I, F> {
+    order: Vec<&'a I>,
+    rng: Option<Rng>,
+    closure: F,
+}
+
+impl<I, T, F: FnMut(&I) -> T> Routine for OverInputs<'_, I, F> {
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        let start = Instant::now();
+        for input in self.order.iter().cycle().take(iterations as usize) {
+            (self.closure)(input);
+        }
+        start.elapsed()
+    }
+
+    fn sample(&mut self, iterations: u64) -> u128 {
+        // Shuffle outside of the timed region
+        if let Some(rng) = &mut self.rng {
+            rng.shuffle(&mut self.order);
+        }
+        let start = Instant::now();
+        for input in self.order.iter().cycle().take(iterations as usize) {
+            black_box((self.closure)(input));
+        }
+        start.elapsed().as_nanos()
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        if let Some(rng) = &mut self.rng {
+            rng.shuffle(&mut self.order);
+        }
+        let mut elapsed = Duration::ZERO;
+        for input in self.order.iter().cycle().take(iterations as usize) {
+            elapsed += time_iteration(extremes, || (self.closure)(input));
+        }
+        elapsed.as_nanos()
+    }
+}
+
+struct FromReader<R, I, P, F> {
+    /// `None` once exhausted
+    reader: Option<BufReader<R>>,
+    /// Buffer for the record being read
+    record: Vec<u8>,
+    parse: P,
+    closure: F,
+    inputs: Vec<I>,
+    /// Inputs used so far, past the end of `inputs` they're cycled
+    consumed: usize,
+}
+
+impl<R: Read, I, P: FnMut(&[u8]) -> Option<I>, F> FromReader<R, I, P, F> {
+    /// Reads until the next `iterations` inputs are available or the reader is exhausted, and
+    /// returns where they start in `inputs`
+    fn prepare(&mut self, iterations: u64) -> usize {
+        let needed = self.consumed.saturating_add(iterations as usize);
+        while self.inputs.len() < needed && self.read_record() {}
+        assert!(
+            !self.inputs.is_empty(),
+            "Can't bench over a reader without records that parse"
+        );
+        let start = self.consumed % self.inputs.len();
+        self.consumed = self.consumed.saturating_add(iterations as usize);
+        start
+    }
+
+    /// Reads the next record, returns false if the reader is exhausted
+    fn read_record(&mut self) -> bool {
+        let Some(reader) = &mut self.reader else {
+            return false;
+        };
+        self.record.clear();
+        match reader.read_until(b'\n', &mut self.record) {
+            Ok(0) => {
+                self.reader = None;
+                false
+            }
+            Ok(_) => {
+                let record = self.record.strip_suffix(b"\n").unwrap_or(&self.record);
+                let record = record.strip_suffix(b"\r").unwrap_or(record);
+                if let Some(input) = (self.parse)(record) {
+                    self.inputs.push(input);
+                }
+                true
+            }
+            Err(e) => {
+                outln!(
+                    "{} {e}, cycling the {} inputs read so far",
+                    wrap_yellow("Failed to read record, cause"),
+                    self.inputs.len()
+                );
+                self.reader = None;
+                false
+            }
+        }
+    }
+}
+
+impl<R: Read, I, T, P: FnMut(&[u8]) -> Option<I>, F: FnMut(&I) -> T> Routine
+    for FromReader<R, I, P, F>
+{
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        let first = self.prepare(iterations);
+        let start = Instant::now();
+        let inputs = self.inputs[first..]
+            .iter()
+            .chain(self.inputs.iter().cycle());
+        for input in inputs.take(iterations as usize) {
+            (self.closure)(input);
+        }
+        start.elapsed()
+    }
+
+    fn sample(&mut self, iterations: u64) -> u128 {
+        // Read outside of the timed region
+        let first = self.prepare(iterations);
+        let start = Instant::now();
+        let inputs = self.inputs[first..]
+            .iter()
+            .chain(self.inputs.iter().cycle());
+        for input in inputs.take(iterations as usize) {
+            black_box((self.closure)(input));
+        }
+        start.elapsed().as_nanos()
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        let first = self.prepare(iterations);
+        let inputs = self.inputs[first..]
+            .iter()
+            .chain(self.inputs.iter().cycle());
+        let mut elapsed = Duration::ZERO;
+        for input in inputs.take(iterations as usize) {
+            elapsed += time_iteration(extremes, || (self.closure)(input));
+        }
+        elapsed.as_nanos()
+    }
+}
+
+fn run_warm_up<R: Routine>(routine: &mut R, warmup_time: Duration) -> WarmupResults {
+    // Routines with setup only report the time of the benched code, stop on the wall clock
+    let start = Instant::now();
+    let mut elapsed = Duration::ZERO;
+    let mut iterations = 0u128;
+    let mut run_iterations = 1u64;
+    let mut previous_batch_elapsed = None;
+    let mut flat_doublings = 0;
+    let mut suspected_elision = false;
+    loop {
+        let batch_elapsed = routine.warm_up(run_iterations);
+        elapsed += batch_elapsed;
+        iterations += u128::from(run_iterations);
+        if let Some(previous) = previous_batch_elapsed {
+            flat_doublings = if is_flat_doubling(run_iterations, previous, batch_elapsed) {
+                flat_doublings + 1
+            } else {
+                0
+            };
+            // Checked as it happens, an empty loop doubles until the iterations overflow
+            suspected_elision |= flat_doublings >= ELISION_DOUBLINGS;
+        }
+        previous_batch_elapsed = Some(batch_elapsed);
+        if start.elapsed() >= warmup_time {
+            return WarmupResults {
+                iterations,
+                elapsed,
+                last_batch_iterations: run_iterations,
+                last_batch_elapsed: batch_elapsed,
+                suspected_elision,
+            };
+        }
+        run_iterations = run_iterations.wrapping_mul(2);
+    }
+}
+
+/// Batches smaller than this are dominated by the overhead of reading the clock
+const ELISION_MIN_BATCH: u64 = 1 << 10;
+/// Consecutive doublings of the batch size that barely took longer, after which the benched code
+/// is suspected to have been optimized away
+const ELISION_DOUBLINGS: u32 = 3;
+
+/// Whether twice the iterations of the previous batch took barely longer than it, which means that
+/// the time per iteration halved. Real work can't speed up like that batch after batch, but a loop
+/// that the optimizer removed takes the same time however many iterations it's asked for
+fn is_flat_doubling(iterations: u64, previous_elapsed: Duration, elapsed: Duration) -> bool {
+    iterations >= ELISION_MIN_BATCH && elapsed.as_nanos() * 4 < previous_elapsed.as_nanos() * 5
+}
+
+/// How far the mean of the last batch of the warm up is from the mean of the whole warm up,
+/// in percent. The batches double in size, so the last one is about half of the warm up, a large
+/// difference means that the first half ran colder than the second
+fn warm_up_drift(wu: &WarmupResults) -> f64 {
+    let mean = wu.elapsed.as_nanos() as f64 / wu.iterations as f64;
+    let last_batch_mean = wu.last_batch_elapsed.as_nanos() as f64 / wu.last_batch_iterations as f64;
+    (last_batch_mean / mean - 1.0).abs() * 100.0
+}
+
+/// Warms up again while the warm up hasn't stabilized and the check allows it, warns if it
+/// never does
+fn check_warm_up<R: Routine>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    check: WarmUpCheck,
+    routine: &mut R,
+    mut wu: WarmupResults,
+) -> WarmupResults {
+    let extensions = match check.action {
+        WarmUpAction::Warn => 0,
+        WarmUpAction::Extend(extensions) => extensions,
+    };
+    let mut drift = warm_up_drift(&wu);
+    for _ in 0..extensions {
+        if drift <= check.tolerance {
+            return wu;
+        }
+        wu = run_warm_up(routine, cfg.warm_up_time);
+        drift = warm_up_drift(&wu);
+    }
+    if drift > check.tolerance {
+        outln!(
+            "{} {} the mean of the last warm up batch differs {drift:.1}% from the warm up mean, above the tolerance of {:.1}%, the benchmark may not have reached a steady state",
+            wrap_bold_green(label),
+            wrap_yellow("Warning:"),
+            check.tolerance
+        );
+    }
+    wu
+}
+
+/// Statistics from a finished benchmark, times are in nanoseconds
+#[derive(Debug, Clone)]
+pub struct BenchStats {
+    /// Iterations ran in total over all samples
+    pub total_iters: u128,
+    /// Time spent running all samples
+    pub elapsed: u128,
+    /// Mean time per iteration
+    pub mean: f64,
+    /// Median of the mean time per iteration of each sample
+    pub median: f64,
+    /// Mean time per iteration of the fastest sample
+    pub min: f64,
+    /// Mean time per iteration of the slowest sample
+    pub max: f64,
+    /// Variance of the mean time per iteration of each sample
+    pub variance: f64,
+    /// Standard deviation of the mean time per iteration of each sample
+    pub stddev: f64,
+    /// Mean time per iteration of each sample, in the order they were measured. Without the
+    /// severe outliers if `outlier_filter` drops them, like the mean
+    pub per_sample_average: Vec<f64>,
+    /// Iterations ran during warm up
+    pub warmup_iterations: u128,
+    /// Time spent in the benched code while warming up, warming up as a whole takes at least
+    /// `warm_up_time`
+    pub warmup_elapsed: Duration,
+    /// Mean time per iteration measured during warm up, used to plan the iterations of each
+    /// sample.
+    /// If this differs a lot from `mean` the benched code likely didn't reach a steady state
+    /// during warm up, consider increasing `warm_up_time`. A large mismatch also means that
+    /// the measurement ran for longer or shorter than `measurement_time`.
+    pub estimated_mean_ns: f64,
+    /// 95% confidence interval of the mean time per iteration, bootstrapped from the mean of each
+    /// sample with `num_resamples` resamples, so it holds for skewed distributions too.
+    /// `None` if `num_resamples` is `Resamples::Fixed(0)`
+    pub mean_confidence_interval: Option<(f64, f64)>,
+    /// Iterations per second at the mean time per iteration, `1e9 / mean`. Like `mean` every
+    /// sample counts the same, whatever its number of iterations. The reciprocal of the mean time
+    /// is the harmonic mean of the per sample rates, averaging the rates arithmetically instead
+    /// would overweight the fastest samples and overstate the throughput
+    pub throughput_per_sec: f64,
+    /// The secondary metric per iteration, `None` unless benched with [`bench_with_metric`]
+    pub metric_per_iteration: Option<f64>,
+    /// Time of the first call, before warming up, if measured, see `ColdStart::Measure`
+    pub cold_start: Option<Duration>,
+}
+
+impl BenchStats {
+    /// Whether the mean is within `tolerance_pct` percent of `other`'s mean, for tests asserting
+    /// that a change didn't affect performance. Benchmarks are noisy, leave room for that
+    /// ```no_run
+    /// use tiny_bench::{bench_with_configuration_labeled_returning, BenchmarkConfig};
+    /// let cfg = BenchmarkConfig::default();
+    /// let old = bench_with_configuration_labeled_returning("old", &cfg, || (0..100u64).sum::<u64>());
+    /// let new = bench_with_configuration_labeled_returning("new", &cfg, || (0..100u64).fold(0, |a, b| a + b));
+    /// assert!(new.approx_eq(&old, 5.0));
+    /// ```
+    #[must_use]
+    pub fn approx_eq(&self, other: &BenchStats, tolerance_pct: f64) -> bool {
+        (self.mean - other.mean).abs() <= other.mean.abs() * tolerance_pct / 100.0
+    }
+
+    /// `mean` as a `Duration`, see [`nanos_to_duration`] for how it's rounded
+    #[must_use]
+    pub fn mean_duration(&self) -> Duration {
+        nanos_to_duration(self.mean)
+    }
+
+    /// `median` as a `Duration`, see [`nanos_to_duration`] for how it's rounded
+    #[must_use]
+    pub fn median_duration(&self) -> Duration {
+        nanos_to_duration(self.median)
+    }
+
+    /// `min` as a `Duration`, see [`nanos_to_duration`] for how it's rounded
+    #[must_use]
+    pub fn min_duration(&self) -> Duration {
+        nanos_to_duration(self.min)
+    }
+
+    /// `max` as a `Duration`, see [`nanos_to_duration`] for how it's rounded
+    #[must_use]
+    pub fn max_duration(&self) -> Duration {
+        nanos_to_duration(self.max)
+    }
+}
+
+/// Rounds nanoseconds to the nearest whole nanosecond, halfway cases away from zero.
+/// Negative and NaN values become zero and values beyond `u64::MAX` nanoseconds saturate
+#[must_use]
+pub fn nanos_to_duration(nanos: f64) -> Duration {
+    // Float to int casts saturate and map NaN to 0
+    Duration::from_nanos(nanos.round() as u64)
+}
+
+/// How a benchmark changed compared to its last persisted run, changes are in percent
+#[derive(Debug, Copy, Clone)]
+pub struct RunComparison {
+    /// Change of the fastest sample, in percent like all changes. NaN if the earlier run took no
+    /// measurable time, so that there's no finite change
+    pub min_change: f64,
+    /// Change of the mean, or of the configured aggregation
+    pub mean_change: f64,
+    /// Change of the slowest sample
+    pub max_change: f64,
+    /// Probability of a change at least this large if nothing changed
+    pub p: f64,
+    /// Welch's t-value of the new run against the old one, the difference of the means in
+    /// standard errors, positive if the new run is slower. The p-value is derived from it
+    pub t: f64,
+    /// Quantiles of the t-values of the bootstrap resamples, the spread that `t` is judged
+    /// against. `None` if the bootstrap was skipped, see `Resamples`
+    pub t_quantiles: Option<TQuantiles>,
+    /// The change is significant and over the noise threshold for regressions
+    pub regressed: bool,
+    /// The change is significant and over the noise threshold for improvements
+    pub improved: bool,
+    /// Change of the secondary metric per iteration, if both runs measured one, see
+    /// [`bench_with_metric`]
+    pub metric_change: Option<f64>,
+    /// Probability of a metric change at least this large if nothing changed, from Welch's t-test
+    /// over the metric per iteration of each sample. Set along with `metric_change`
+    pub metric_p: Option<f64>,
+}
+
+/// Quantiles of the bootstrapped t-distribution of a [`RunComparison`], under the assumption
+/// that nothing changed. A `t` outside of `lower..=upper` is significant at the 5% level
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TQuantiles {
+    /// The 2.5% quantile
+    pub lower: f64,
+    /// The median
+    pub median: f64,
+    /// The 97.5% quantile
+    pub upper: f64,
+}
+
+/// A benchmark recorded by the process wide collector, see [`take_results`]
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    /// The label the benchmark was run with
+    pub label: &'static str,
+    /// Statistics of the run
+    pub stats: BenchStats,
+    /// The comparison with the last persisted run, if the output format compares and there was one
+    pub comparison: Option<RunComparison>,
+}
+
+/// Whether a benchmark is getting better or worse over its persisted runs
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Trend {
+    /// The mean is decreasing
+    Improving,
+    /// No clear trend
+    Stable,
+    /// The mean is increasing
+    Regressing,
+    /// Fewer than 3 runs are persisted
+    InsufficientData,
+}
+
+/// The trend of a benchmark's mean over its persisted runs, see [`regression_report`]
+#[derive(Debug, Copy, Clone)]
+pub struct RegressionReport {
+    /// How many persisted runs the report is based on
+    pub runs: usize,
+    /// Change of the mean per run in nanoseconds
+    pub slope: f64,
+    /// Change of the mean per run as a percentage of the mean over all runs
+    pub relative_slope: f64,
+    /// How well a line fits the means, from 0 (not at all) to 1 (perfectly)
+    pub r_squared: f64,
+    /// Regressing or improving if the slope is over 1% per run and the line fits well,
+    /// R² of at least 0.5
+    pub trend: Trend,
+}
+
+impl RegressionReport {
+    const MIN_RUNS: usize = 3;
+    const TREND_THRESHOLD: f64 = 1.0;
+    const MIN_R_SQUARED: f64 = 0.5;
+
+    fn from_means(means: &[f64]) -> Self {
+        if means.len() < Self::MIN_RUNS {
+            return RegressionReport {
+                runs: means.len(),
+                slope: 0.0,
+                relative_slope: 0.0,
+                r_squared: 0.0,
+                trend: Trend::InsufficientData,
+            };
+        }
+        let (slope, r_squared) = crate::output::analysis::regression::linear_regression(means);
+        let mean = means.iter().sum::<f64>() / means.len() as f64;
+        let relative_slope = slope / mean * 100.0;
+        let trend =
+            if r_squared < Self::MIN_R_SQUARED || relative_slope.abs() < Self::TREND_THRESHOLD {
+                Trend::Stable
+            } else if relative_slope > 0.0 {
+                Trend::Regressing
+            } else {
+                Trend::Improving
+            };
+        RegressionReport {
+            runs: means.len(),
+            slope,
+            relative_slope,
+            r_squared,
+            trend,
+        }
+    }
+}
+
+/// A single measured sample, see `BenchmarkConfig::on_sample`
+#[derive(Debug, Copy, Clone)]
+pub struct SampleResult {
+    /// Which sample this is, counting from 0
+    pub index: usize,
+    /// How many iterations the sample ran
+    pub iterations: u64,
+    /// Time spent running those iterations
+    pub elapsed_nanos: u128,
+}
+
+#[derive(Debug)]
+struct WarmupResults {
+    iterations: u128,
+    elapsed: Duration,
+    /// The last and largest batch of iterations, to tell whether the warm up stabilized
+    last_batch_iterations: u64,
+    last_batch_elapsed: Duration,
+    /// Whether the batches stopped taking longer as they grew, see `is_flat_doubling`
+    suspected_elision: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg(feature = "bench")]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+pub(crate) struct SamplingData {
+    pub(crate) samples: Vec<u64>,
+    pub(crate) times: Vec<u128>,
+    /// Total of the secondary metric over each sample's iterations, empty if the benchmark
+    /// doesn't measure one, see [`bench_with_metric`]
+    pub(crate) metrics: Vec<u64>,
+}
+
+#[cfg(feature = "bench")]
+impl SamplingData {
+    /// One sample of a single iteration per duration
+    pub(crate) fn from_durations(durations: &[Duration]) -> Self {
+        SamplingData {
+            samples: vec![1; durations.len()],
+            times: durations.iter().map(Duration::as_nanos).collect(),
+            metrics: Vec::new(),
+        }
+    }
+
+    /// The same samples as if every iteration took `ratio` times as long
+    pub(crate) fn scaled(mut self, ratio: f64) -> Self {
+        for time in &mut self.times {
+            *time = (*time as f64 * ratio).round() as u128;
+        }
+        self
+    }
+
+    /// Drops the first `n` samples, they're the smallest and earliest, most likely to still be
+    /// affected by cold caches and such
+    pub(crate) fn discard_first(mut self, n: usize) -> Self {
+        self.samples.drain(..n);
+        self.times.drain(..n);
+        self.metrics.drain(..n.min(self.metrics.len()));
+        self
+    }
+
+    /// Drops the samples that took no measurable time
+    pub(crate) fn without_zero_time(self) -> Self {
+        let has_metrics = !self.metrics.is_empty();
+        let mut kept = SamplingData {
+            samples: Vec::new(),
+            times: Vec::new(),
+            metrics: Vec::new(),
+        };
+        for (index, (iterations, time)) in self.samples.into_iter().zip(self.times).enumerate() {
+            if time > 0 {
+                kept.samples.push(iterations);
+                kept.times.push(time);
+                if has_metrics {
+                    kept.metrics.push(self.metrics[index]);
+                }
+            }
+        }
+        kept
+    }
+
+    /// The secondary metric per iteration over all samples, `None` if there's none
+    pub(crate) fn metric_per_iteration(&self) -> Option<f64> {
+        if self.metrics.is_empty() {
+            return None;
+        }
+        let total = self
+            .metrics
+            .iter()
+            .map(|metric| *metric as f64)
+            .sum::<f64>();
+        Some(total / self.total_iterations() as f64)
+    }
+
+    /// The secondary metric per iteration of each sample, empty if there's no metric
+    pub(crate) fn metric_per_sample(&self) -> Vec<f64> {
+        self.metrics
+            .iter()
+            .zip(&self.samples)
+            .map(|(metric, iterations)| *metric as f64 / *iterations as f64)
+            .collect()
+    }
+
+    /// Mean time per iteration of each sample, in measured order
+    pub(crate) fn per_sample_average(&self) -> Vec<f64> {
+        self.samples
+            .iter()
+            .zip(&self.times)
+            .map(|(samples, time)| *time as f64 / *samples as f64)
+            .collect()
+    }
+
+    pub(crate) fn total_iterations(&self) -> u128 {
+        let mut total_iters = 0u128;
+        for count in self.samples.iter().copied() {
+            total_iters = total_iters.saturating_add(u128::from(count));
+        }
+        total_iters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::analysis::sample_data::simple_analyze_sampling_data;
+    use crate::output::codec::BinaryCodec;
+    use std::ops::ControlFlow;
+    use std::time::Duration;
+
+    #[test]
+    fn benches() {
+        let closure = || {
+            let mut sum = 0;
+            for _ in 0..100 {
+                sum += black_box(1);
+            }
+            assert_eq!(black_box(100), sum);
+        };
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            ..fast_cfg()
+        };
+        bench_with_configuration(&cfg, closure);
+    }
+
+    #[test]
+    fn returns_warm_up_results() {
+        let cfg = fast_cfg();
+        let stats = bench_with_configuration_labeled_returning("warm up", &cfg, || black_box(1));
+        assert!(stats.warmup_iterations > 0);
+        assert!(stats.warmup_elapsed > Duration::ZERO);
+        assert!(stats.estimated_mean_ns > 0.0);
+        assert_eq!(cfg.num_samples, stats.per_sample_average.len());
+        assert!(
+            (stats.stddev * stats.stddev - stats.variance).abs() < 1e-6 * stats.variance.max(1.0)
+        );
+    }
+
+    #[test]
+    fn calls_on_sample() {
+        let samples = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collected = samples.clone();
+        let cfg = BenchmarkConfig {
+            on_sample: Some(Box::new(move |s| {
+                collected.lock().unwrap().push(s);
+                ControlFlow::Continue(())
+            })),
+            ..fast_cfg()
+        };
+        bench_with_configuration_labeled("on sample", &cfg, || black_box(1));
+        let samples = samples.lock().unwrap();
+        assert_eq!(cfg.num_samples, samples.len());
+        for (i, s) in samples.iter().enumerate() {
+            assert_eq!(i, s.index);
+        }
+    }
+
+    #[test]
+    fn stops_when_on_sample_breaks() {
+        let cfg = BenchmarkConfig {
+            measurement_time: Duration::from_millis(10),
+            warm_up_time: Duration::from_millis(5),
+            dump_results_to_disk: false,
+            on_sample: Some(Box::new(|s| {
+                if s.index >= 4 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })),
+            ..BenchmarkConfig::default()
+        };
+        let averages = bench_collect("stopped by on_sample", &cfg, || black_box(1));
+        assert_eq!(5, averages.len());
+    }
+
+    #[test]
+    fn calls_between_samples() {
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = calls.clone();
+        let cfg = BenchmarkConfig {
+            between_samples: Some(Box::new(move || {
+                counted.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            })),
+            ..fast_cfg()
+        };
+        bench_with_configuration_labeled("between samples", &cfg, || black_box(1));
+        assert_eq!(
+            cfg.num_samples - 1,
+            calls.load(std::sync::atomic::Ordering::Relaxed)
+        );
+    }
+
+    #[test]
+    fn benches_against_target() {
+        let cfg = fast_cfg();
+        assert_bench_against_target("under target", &cfg, Duration::from_secs(1), || {
+            black_box(1)
+        });
+        assert!(!bench_against_target_configuration(
+            "over target",
+            &cfg,
+            Duration::ZERO,
+            || black_box(1)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "over target is over its target of 0.00ns")]
+    fn asserts_against_target() {
+        let cfg = fast_cfg();
+        assert_bench_against_target("over target", &cfg, Duration::ZERO, || black_box(1));
+    }
+
+    #[test]
+    fn pauses_between_samples() {
+        use crate::SamplePause;
+        let cfg = BenchmarkConfig {
+            pause_between_samples: SamplePause::Sleep(Duration::from_millis(1)),
+            ..BenchmarkConfig::default()
+        };
+        let (data, paused, _, _) = run(vec![1; 5], &mut Plain(|| black_box(1)), &cfg, None);
+        assert_eq!(5, data.times.len());
+        assert!(paused >= Duration::from_millis(4));
+    }
+
+    #[test]
+    fn bootstraps_the_filtered_averages() {
+        let cfg = BenchmarkConfig {
+            outlier_filter: crate::OutlierFilter::DropSevere,
+            seed: Some(7),
+            ..fast_cfg()
+        };
+        let sampling_data = SamplingData {
+            samples: vec![10; 10],
+            times: vec![100, 101, 99, 100, 102, 98, 100, 101, 99, 10_000],
+            metrics: Vec::new(),
+        };
+        let analysis = analyze_sampling_data(&sampling_data, &cfg);
+        let stats = summarize(
+            &cfg,
+            &sampling_data,
+            &analysis,
+            100,
+            (0, Duration::ZERO),
+            0.0,
+            None,
+        );
+        assert_eq!(9, stats.per_sample_average.len());
+        assert!((stats.per_sample_average[0] - 10.0).abs() < f64::EPSILON);
+        let (low, high) = stats.mean_confidence_interval.unwrap();
+        assert!(low <= stats.mean && stats.mean <= high);
+        // Resamples that include the outlier would stretch the interval far above the mean
+        assert!(high < 11.0);
+    }
+
+    #[test]
+    fn sets_up_once_per_sample_when_timing_iterations() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex};
+        let setups = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&setups);
+        let per_sample = Arc::new(Mutex::new(Vec::new()));
+        let collected = Arc::clone(&per_sample);
+        let cfg = BenchmarkConfig {
+            extremes: crate::Extremes::Iteration,
+            on_sample: Some(Box::new(move |_| {
+                collected
+                    .lock()
+                    .unwrap()
+                    .push(counted.load(Ordering::Relaxed));
+                ControlFlow::Continue(())
+            })),
+            ..fast_cfg()
+        };
+        bench_per_sample_setup_configuration(
+            "per_sample_setup_iteration_extremes",
+            &cfg,
+            || setups.fetch_add(1, Ordering::Relaxed),
+            |_| black_box(1),
+            drop,
+        );
+        let per_sample = per_sample.lock().unwrap();
+        assert_eq!(cfg.num_samples, per_sample.len());
+        assert!(per_sample.windows(2).all(|pair| pair[1] - pair[0] == 1));
+    }
+
+    #[test]
+    fn times_iteration_extremes() {
+        let cfg = BenchmarkConfig {
+            extremes: crate::Extremes::Iteration,
+            ..BenchmarkConfig::default()
+        };
+        let (data, _, extremes, _) = run(vec![3, 6], &mut Plain(|| black_box(1)), &cfg, None);
+        let (min, max) = extremes.unwrap();
+        assert!(min <= max);
+        assert!(data.times.iter().all(|&time| time >= min * 3));
+        let cfg = BenchmarkConfig::default();
+        assert!(run(vec![3, 6], &mut Plain(|| black_box(1)), &cfg, None)
+            .2
+            .is_none());
+    }
+
+    #[test]
+    fn tells_significant_differences() {
+        let label = "significantly_different";
+        let fast = SamplingData {
+            samples: vec![1; 20],
+            times: (0..20).map(|i| 100 + i % 3).collect(),
+            metrics: Vec::new(),
+        };
+        let slow = SamplingData {
+            samples: vec![1; 20],
+            times: (0..20).map(|i| 200 + i % 3).collect(),
+            metrics: Vec::new(),
+        };
+        let cfg = BenchmarkConfig {
+            num_resamples: crate::Resamples::Fixed(0),
+            ..BenchmarkConfig::default()
+        };
+        let _ = crate::output::disk::clear_persisted(label);
+        assert_eq!(
+            None,
+            is_significantly_different_with_configuration(label, &cfg)
+        );
+        crate::output::disk::try_write_last_simpling(label, None, &fast);
+        assert_eq!(
+            None,
+            is_significantly_different_with_configuration(label, &cfg)
+        );
+        crate::output::disk::try_write_last_simpling(label, None, &fast);
+        assert_eq!(
+            Some(false),
+            is_significantly_different_with_configuration(label, &cfg)
+        );
+        crate::output::disk::try_write_last_simpling(label, None, &slow);
+        assert_eq!(
+            Some(true),
+            is_significantly_different_with_configuration(label, &cfg)
+        );
+    }
+
+    #[test]
+    fn discards_zero_time_samples() {
+        let data = || SamplingData {
+            samples: vec![1, 2, 3, 4],
+            times: vec![0, 20, 0, 40],
+            metrics: vec![1, 2, 3, 4],
+        };
+        let keep = BenchmarkConfig::default();
+        assert_eq!(data(), handle_zero_time_samples("zero_time", &keep, data()));
+        let discard = BenchmarkConfig {
+            zero_time_samples: ZeroTimeSamples::Discard,
+            ..BenchmarkConfig::default()
+        };
+        let kept = handle_zero_time_samples("zero_time", &discard, data());
+        assert_eq!(vec![2, 4], kept.samples);
+        assert_eq!(vec![20, 40], kept.times);
+        assert_eq!(vec![2, 4], kept.metrics);
+        let mostly_zero = SamplingData {
+            samples: vec![1, 2, 3],
+            times: vec![0, 0, 30],
+            metrics: Vec::new(),
+        };
+        assert_eq!(
+            3,
+            handle_zero_time_samples("zero_time", &discard, mostly_zero)
+                .times
+                .len()
+        );
+    }
+
+    #[test]
+    fn stores_runs_by_id() {
+        let label = "stored_by_run_id";
+        let cfg = |run_id| BenchmarkConfig {
+            dump_results_to_disk: true,
+            run_id: Some(run_id),
+            label_policy: crate::LabelPolicy::Sanitize,
+            ..fast_cfg()
+        };
+        bench_with_configuration_labeled(label, &cfg("control"), || black_box(1));
+        bench_with_configuration_labeled(label, &cfg("treatment/1"), || black_box(2));
+        let control = load_run(label, "control").unwrap().unwrap();
+        assert!(!control.is_empty());
+        assert_eq!(0, control[0].index);
+        assert!(load_run(label, "treatment_1").unwrap().is_some());
+        assert!(load_run(label, "never_ran").unwrap().is_none());
+        let cfg = cfg("control");
+        assert!(compare_runs(label, &cfg, "treatment/1", "control")
+            .unwrap()
+            .is_some());
+        assert!(compare_runs(label, &cfg, "never_ran", "control")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn persists_on_commit() {
+        let label = "persisted_on_commit";
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            persist: crate::Persist::OnCommit,
+            ..fast_cfg()
+        };
+        let _ = crate::output::disk::clear_persisted(label);
+        bench_with_configuration_labeled(label, &cfg, || black_box(1));
+        bench_with_configuration_labeled(label, &cfg, || black_box(2));
+        assert!(
+            crate::output::disk::try_read_last_simpling(label, None, &BinaryCodec)
+                .unwrap()
+                .is_none()
+        );
+        let manifest = commit_session();
+        assert!(manifest.failed.is_empty());
+        assert_eq!(
+            1,
+            manifest
+                .written
+                .iter()
+                .filter(|written| *written == label)
+                .count()
+        );
+        assert!(
+            crate::output::disk::try_read_last_simpling(label, None, &BinaryCodec)
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            crate::output::disk::try_read_old_simpling(label, None, &BinaryCodec)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn replays_persisted_run() {
+        let label = "replayed";
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            ..fast_cfg()
+        };
+        let _ = crate::output::disk::clear_persisted(label);
+        assert!(replay(label, &cfg).unwrap().is_none());
+        let stats = bench_with_configuration_labeled_returning(label, &cfg, || black_box(1));
+        let replayed = replay(label, &cfg).unwrap().unwrap();
+        assert_eq!(stats.total_iters, replayed.total_iters);
+        assert!((stats.mean - replayed.mean).abs() < f64::EPSILON);
+        assert_eq!(0, replayed.warmup_iterations);
+    }
+
+    #[test]
+    fn prints_to_configured_stream() {
+        let buf = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let cfg = BenchmarkConfig {
+            output_stream: crate::OutputStream::Writer(buf.clone()),
+            ..fast_cfg()
+        };
+        bench_with_configuration_labeled("streamed", &cfg, || black_box(1));
+        let printed = String::from_utf8(buf.lock().unwrap().clone()).unwrap();
+        assert!(printed.contains("streamed"));
+        assert!(printed.contains("mean"));
+    }
+
+    #[test]
+    fn measures_secondary_metric() {
+        let cfg = BenchmarkConfig {
+            discard_first_samples: 2,
+            ..fast_cfg()
+        };
+        let stats = bench_with_metric_configuration("metric", &cfg, || black_box(3));
+        assert_eq!(Some(3.0), stats.metric_per_iteration);
+        let plain = bench_with_configuration_labeled_returning("no_metric", &cfg, || black_box(3));
+        assert_eq!(None, plain.metric_per_iteration);
+    }
+
+    #[test]
+    fn estimates_duration() {
+        let cfg = BenchmarkConfig {
+            measurement_time: Duration::from_millis(100),
+            warm_up_time: Duration::from_millis(10),
+            ..BenchmarkConfig::default()
+        };
+        let sleepy = || std::thread::sleep(Duration::from_micros(100));
+        let estimate = estimate_duration(&cfg, sleepy);
+        assert!(estimate >= Duration::from_millis(110));
+        let limited = BenchmarkConfig {
+            hard_time_limit: Some(Duration::from_millis(20)),
+            ..cfg
+        };
+        assert_eq!(
+            Duration::from_millis(20),
+            estimate_duration(&limited, sleepy)
+        );
+    }
+
+    #[test]
+    fn benches_valid_output() {
+        let cfg = fast_cfg();
+        bench_checked_output_configuration("checked", &cfg, || black_box(2) + 2, |sum| *sum == 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid output")]
+    fn rejects_invalid_output() {
+        bench_checked_output("checked_invalid", || black_box(2) + 3, |sum| *sum == 4);
+    }
+
+    #[test]
+    fn measures_cold_start() {
+        use crate::ColdStart;
+        let cfg = BenchmarkConfig {
+            cold_start: ColdStart::Measure,
+            ..fast_cfg()
+        };
+        let mut first = true;
+        let stats = bench_with_configuration_labeled_returning("cold_start", &cfg, || {
+            if std::mem::take(&mut first) {
+                std::thread::sleep(Duration::from_millis(2));
+            }
+        });
+        assert!(stats.cold_start.unwrap() >= Duration::from_millis(2));
+        let warm = BenchmarkConfig {
+            cold_start: ColdStart::Ignore,
+            ..cfg
+        };
+        assert_eq!(
+            None,
+            bench_with_configuration_labeled_returning("warm_start", &warm, || black_box(1))
+                .cold_start
+        );
+    }
+
+    #[test]
+    fn settles_before_measuring() {
+        use crate::Settle;
+        for settle_by in [Settle::Sleep, Settle::Spin] {
+            let cfg = BenchmarkConfig {
+                settle_time: Some(Duration::from_millis(20)),
+                settle_by,
+                ..fast_cfg()
+            };
+            let start = Instant::now();
+            measure("settle", &cfg, &mut Plain(|| black_box(1)), false);
+            assert!(start.elapsed() >= Duration::from_millis(25));
+        }
+    }
+
+    #[test]
+    fn compares_with_setup() {
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            ..fast_cfg()
+        };
+        bench_compare_with_setup_configuration(
+            "compare",
+            &cfg,
+            || (0..100).collect::<Vec<u64>>(),
+            |v| v.iter().sum::<u64>(),
+            |v| v.iter().rev().sum::<u64>(),
+        );
+    }
+
+    #[test]
+    fn discarded_samples_dont_affect_mean() {
+        let cold = SamplingData {
+            samples: vec![1, 2, 3, 4],
+            times: vec![1_000_000, 20, 30, 40],
+            metrics: Vec::new(),
+        };
+        let warm = SamplingData {
+            samples: vec![2, 3, 4],
+            times: vec![20, 30, 40],
+            metrics: Vec::new(),
+        };
+        let discarded = cold.discard_first(1);
+        assert_eq!(warm, discarded);
+        assert_eq!(9, discarded.total_iterations());
+        let analysis = simple_analyze_sampling_data(&discarded);
+        assert!((analysis.average - 10.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn measures_clock_resolution() {
+        let resolution = clock_resolution();
+        assert!(resolution > Duration::ZERO);
+        assert!(resolution < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn collects_per_sample_averages() {
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            ..fast_cfg()
+        };
+        let averages = bench_collect("collect", &cfg, || black_box(1));
+        assert_eq!(cfg.num_samples, averages.len());
+    }
+
+    #[test]
+    fn collects_results() {
+        let cfg = fast_cfg();
+        bench_with_configuration_labeled("collected", &cfg, || black_box(1));
+        assert!(collected_labels().contains(&"collected"));
+    }
+
+    #[test]
+    fn threads_config_through() {
+        let mut cfg = BenchmarkConfig {
+            num_samples: 20,
+            ..fast_cfg()
+        };
+        for size in [1u64, 10] {
+            cfg = bench_returning_config("sweep", cfg, || black_box((0..size).sum::<u64>()));
+        }
+        assert_eq!(20, cfg.num_samples);
+    }
+
+    #[test]
+    fn raises_iterations_to_floor() {
+        let cfg = BenchmarkConfig {
+            num_samples: 4,
+            min_iterations_per_sample: 1000,
+            ..BenchmarkConfig::default()
+        };
+        // A second per iteration fits a single iteration per sample in the measurement time
+        let (iters, total_iters) = calculate_iters_and_total_iters(&cfg, 1e9, 4);
+        assert_eq!(vec![1000, 2000, 3000, 4000], iters);
+        assert_eq!(10_000, total_iters);
+        // Fast code already runs more than the floor
+        let (iters, _) = calculate_iters_and_total_iters(&cfg, 1.0, 4);
+        assert!(iters[0] > 1000);
+    }
+
+    #[test]
+    fn measures_warm_up_drift() {
+        let wu = |elapsed, last_batch_elapsed| WarmupResults {
+            iterations: 200,
+            elapsed: Duration::from_nanos(elapsed),
+            last_batch_iterations: 100,
+            last_batch_elapsed: Duration::from_nanos(last_batch_elapsed),
+            suspected_elision: false,
+        };
+        assert!(warm_up_drift(&wu(2000, 1000)) < 0.000_001);
+        // Twice as slow in the first half
+        assert!((warm_up_drift(&wu(3000, 1000)) - 33.333).abs() < 0.001);
+    }
+
+    /// Takes the same time however many iterations it runs, like an optimized away loop
+    struct Elided;
+
+    impl Routine for Elided {
+        fn warm_up(&mut self, _iterations: u64) -> Duration {
+            Duration::from_nanos(40)
+        }
+
+        fn sample(&mut self, _iterations: u64) -> u128 {
+            40
+        }
+
+        fn sample_timed_iterations(
+            &mut self,
+            iterations: u64,
+            _extremes: &mut Option<(u128, u128)>,
+        ) -> u128 {
+            self.sample(iterations)
+        }
+    }
+
+    #[test]
+    fn detects_flat_doublings() {
+        let nanos = Duration::from_nanos;
+        assert!(is_flat_doubling(1 << 20, nanos(40), nanos(41)));
+        assert!(!is_flat_doubling(1 << 20, nanos(1000), nanos(2000)));
+        // Small batches are dominated by reading the clock
+        assert!(!is_flat_doubling(4, nanos(40), nanos(40)));
+        let wu = run_warm_up(&mut Elided, Duration::from_millis(5));
+        assert!(wu.suspected_elision);
+        let mut working = Plain(|| (0..black_box(100u64)).map(black_box).sum::<u64>());
+        let wu = run_warm_up(&mut working, Duration::from_millis(5));
+        assert!(!wu.suspected_elision);
+    }
+
+    #[test]
+    fn extends_unstable_warm_up() {
+        /// Takes 10ns per iteration, a few batches fit in a warm up
+        struct Steady;
+
+        impl Routine for Steady {
+            fn warm_up(&mut self, iterations: u64) -> Duration {
+                std::thread::sleep(Duration::from_micros(200));
+                Duration::from_nanos(iterations * 10)
+            }
+
+            fn sample(&mut self, iterations: u64) -> u128 {
+                u128::from(iterations) * 10
+            }
+
+            fn sample_timed_iterations(
+                &mut self,
+                iterations: u64,
+                _extremes: &mut Option<(u128, u128)>,
+            ) -> u128 {
+                self.sample(iterations)
+            }
+        }
+
+        let cfg = BenchmarkConfig {
+            warm_up_time: Duration::from_millis(5),
+            ..BenchmarkConfig::default()
+        };
+        let check = WarmUpCheck {
+            tolerance: 10.0,
+            action: WarmUpAction::Extend(3),
+        };
+        // Twice as slow in the first half, like a cache that needed filling
+        let cold = WarmupResults {
+            iterations: 20,
+            elapsed: Duration::from_nanos(300),
+            last_batch_iterations: 10,
+            last_batch_elapsed: Duration::from_nanos(100),
+            suspected_elision: false,
+        };
+        let wu = check_warm_up("unstable_warm_up", &cfg, check, &mut Steady, cold);
+        // The cold iterations were left behind in the first warm up
+        assert!(wu.iterations > 0);
+        assert!((wu.elapsed.as_nanos() as f64 / wu.iterations as f64 - 10.0).abs() < 0.000_001);
+    }
+
+    #[test]
+    fn reads_and_cycles_records() {
+        let corpus: &[u8] = b"1\n2\r\nnot a number\n3";
+        let mut seen = Vec::new();
+        let mut routine = FromReader {
+            reader: Some(BufReader::new(corpus)),
+            record: Vec::new(),
+            parse: |record: &[u8]| std::str::from_utf8(record).ok()?.parse::<u64>().ok(),
+            closure: |input: &u64| seen.push(*input),
+            inputs: Vec::new(),
+            consumed: 0,
+        };
+        routine.sample(1);
+        // Only what the sample needs is read
+        assert_eq!(1, routine.inputs.len());
+        routine.sample(4);
+        routine.warm_up(2);
+        drop(routine);
+        assert_eq!(vec![1, 2, 3, 1, 2, 3, 1], seen);
+    }
+
+    #[test]
+    fn benches_from_reader() {
+        let cfg = fast_cfg();
+        let corpus = (0..1000)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        bench_from_reader_configuration(
+            "from_reader",
+            &cfg,
+            corpus.as_bytes(),
+            |record| std::str::from_utf8(record).ok().map(str::to_string),
+            |line| line.parse::<u64>(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "without records that parse")]
+    fn panics_on_reader_without_records() {
+        bench_from_reader("empty_reader", &b""[..], |_| Some(()), |()| ());
+    }
+
+    #[test]
+    fn requires_full_samples() {
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            max_iterations: Some(1),
+            stop_when: StopWhen::FixedSamples,
+            ..fast_cfg()
+        };
+        let averages = bench_collect("full_samples", &cfg, || black_box(1));
+        assert_eq!(cfg.num_samples, averages.len());
+    }
+
+    #[test]
+    fn truncates_at_hard_time_limit() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        // Fast while warming up and slow once sampling, so the iterations are underestimated
+        let slow = Arc::new(AtomicBool::new(false));
+        let set_slow = Arc::clone(&slow);
+        let cfg = BenchmarkConfig {
+            hard_time_limit: Some(Duration::from_millis(50)),
+            on_sample: Some(Box::new(move |_| {
+                set_slow.store(true, Ordering::Relaxed);
+                ControlFlow::Continue(())
+            })),
+            ..fast_cfg()
+        };
+        let start = Instant::now();
+        let averages = bench_collect("hard_time_limit", &cfg, || {
+            if slow.load(Ordering::Relaxed) {
+                let start = Instant::now();
+                while start.elapsed() < Duration::from_micros(10) {}
+            }
+        });
+        assert!(averages.len() >= 2);
+        assert!(averages.len() < cfg.num_samples);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn benches_generic_functions() {
+        fn parse<T: std::str::FromStr>() -> Option<T> {
+            black_box("42").parse().ok()
+        }
+        let cfg = BenchmarkConfig {
+            label_separator: Some("/"),
+            ..fast_cfg()
+        };
+        let stats = crate::bench_generic!("generic_parse", &cfg, parse, [u8, i64, f64,]);
+        assert_eq!(3, stats.len());
+        assert!(stats.iter().all(|stats| stats.total_iters > 0));
+        let labels = collected_labels()
+            .into_iter()
+            .filter(|label| label.starts_with("generic_parse/"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["generic_parse/u8", "generic_parse/i64", "generic_parse/f64"],
+            labels
+        );
+    }
+
+    #[test]
+    fn compares_stats_approximately() {
+        let stats = |mean| BenchStats {
+            total_iters: 1,
+            elapsed: 1,
+            mean,
+            median: mean,
+            min: mean,
+            max: mean,
+            variance: 0.0,
+            stddev: 0.0,
+            per_sample_average: vec![mean],
+            warmup_iterations: 1,
+            warmup_elapsed: Duration::ZERO,
+            estimated_mean_ns: mean,
+            mean_confidence_interval: None,
+            throughput_per_sec: 1.0,
+            metric_per_iteration: None,
+            cold_start: None,
+        };
+        assert!(stats(104.0).approx_eq(&stats(100.0), 5.0));
+        assert!(stats(96.0).approx_eq(&stats(100.0), 5.0));
+        assert!(!stats(106.0).approx_eq(&stats(100.0), 5.0));
+        assert!(stats(0.0).approx_eq(&stats(0.0), 0.0));
+        assert!(!stats(f64::NAN).approx_eq(&stats(100.0), 5.0));
+    }
+
+    #[test]
+    fn converts_nanos_to_durations() {
+        assert_eq!(Duration::from_nanos(2), nanos_to_duration(1.5));
+        assert_eq!(Duration::from_nanos(1), nanos_to_duration(1.49));
+        assert_eq!(Duration::ZERO, nanos_to_duration(-3.0));
+        assert_eq!(Duration::ZERO, nanos_to_duration(f64::NAN));
+        assert_eq!(
+            Duration::from_nanos(u64::MAX),
+            nanos_to_duration(f64::INFINITY)
+        );
+        let stats =
+            bench_with_configuration_labeled_returning("durations", &fast_cfg(), || black_box(1));
+        assert!(stats.min <= stats.median && stats.median <= stats.max);
+        assert!(stats.min_duration() <= stats.max_duration());
+        assert_eq!(nanos_to_duration(stats.mean), stats.mean_duration());
+        assert_eq!(nanos_to_duration(stats.median), stats.median_duration());
+        assert!(
+            (stats.throughput_per_sec - 1e9 / stats.mean).abs() < 1e-9 * stats.throughput_per_sec
+        );
+    }
+
+    #[test]
+    fn establishes_baseline() {
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            ..fast_cfg()
+        };
+        let selected = bench_establish_baseline("establish_baseline", &cfg, 3, || black_box(1));
+        assert!(selected < 3);
+        let persisted =
+            crate::output::disk::try_read_last_simpling("establish_baseline", None, &BinaryCodec)
+                .unwrap();
+        assert!(persisted.is_some_and(|data| !data.times.is_empty()));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one run")]
+    fn establishing_baseline_requires_runs() {
+        bench_establish_baseline("no_runs", &BenchmarkConfig::default(), 0, || black_box(1));
+    }
+
+    #[test]
+    fn benches_relative_to_reference() {
+        let cfg = fast_cfg();
+        let ratio = bench_relative_with_configuration(
+            "relative",
+            &cfg,
+            || black_box(1),
+            || (0..black_box(100)).map(black_box).sum::<u64>(),
+        );
+        assert!(ratio.is_finite());
+        assert!(ratio > 0.0);
+    }
+
+    #[test]
+    fn reuses_recent_baseline() {
+        let label = "recent baseline";
+        crate::output::disk::try_write_last_simpling(
+            label,
+            None,
+            &SamplingData {
+                samples: vec![1, 2],
+                times: vec![10, 40],
+                metrics: Vec::new(),
+            },
+        );
+        let cfg = BenchmarkConfig {
+            reuse_recent_baseline: Some(Duration::from_mins(1)),
+            ..BenchmarkConfig::default()
+        };
+        assert_eq!(Some(15.0), recent_baseline_mean(label, &cfg));
+        let without = BenchmarkConfig::default();
+        assert_eq!(None, recent_baseline_mean(label, &without));
+    }
+
+    #[test]
+    fn benches_subtracted() {
+        let cfg = fast_cfg();
+        let difference = bench_subtracted_with_configuration(
+            "subtracted",
+            &cfg,
+            || black_box(1),
+            || (0..black_box(1000)).map(black_box).sum::<u64>(),
+        );
+        assert!(difference > 0.0);
+    }
+
+    #[test]
+    fn benches_borrowing_from_setup() {
+        struct Input(Vec<u8>);
+        fn middle(input: &Input) -> &[u8] {
+            &input.0[1..3]
+        }
+        let cfg = fast_cfg();
+        bench_with_setup_borrowing_configuration_labeled(
+            "borrowing",
+            &cfg,
+            || Input(vec![1, 2, 3, 4]),
+            |input: &Input| input.0.len(),
+        );
+        bench_with_setup_borrowing_configuration_labeled(
+            "borrowing",
+            &cfg,
+            || Input(vec![1, 2, 3, 4]),
+            middle,
+        );
+    }
+
+    #[test]
+    fn classifies_trend() {
+        assert_eq!(
+            Trend::InsufficientData,
+            RegressionReport::from_means(&[1.0, 2.0]).trend
+        );
+        assert_eq!(
+            Trend::Regressing,
+            RegressionReport::from_means(&[100.0, 105.0, 110.0, 115.0]).trend
+        );
+        assert_eq!(
+            Trend::Improving,
+            RegressionReport::from_means(&[115.0, 110.0, 105.0, 100.0]).trend
+        );
+        assert_eq!(
+            Trend::Stable,
+            RegressionReport::from_means(&[100.0, 100.1, 100.2, 100.3]).trend
+        );
+        assert_eq!(
+            Trend::Stable,
+            RegressionReport::from_means(&[100.0, 120.0, 90.0, 110.0]).trend
+        );
+    }
+
+    #[test]
+    fn estimates_minimum_detectable_effect() {
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            ..fast_cfg()
+        };
+        let effect = estimate_minimum_detectable_effect("mde", &cfg, 0.8, || black_box(1));
+        assert!(effect.is_finite());
+        assert!(effect >= 0.0);
+    }
+
+    #[test]
+    fn benches_with_per_sample_setup() {
+        let cfg = fast_cfg();
+        let setups = std::cell::Cell::new(0);
+        let teardowns = std::cell::Cell::new(0);
+        bench_per_sample_setup_configuration(
+            "per sample setup",
+            &cfg,
+            || {
+                setups.set(setups.get() + 1);
+                Vec::new()
+            },
+            |v| v.push(1),
+            |_| teardowns.set(teardowns.get() + 1),
+        );
+        assert_eq!(setups.get(), teardowns.get());
+        // At least one warm up round and one per sample
+        assert!(setups.get() > cfg.num_samples);
+    }
+
+    #[test]
+    fn benches_shuffled_inputs() {
+        let inputs = (0..10).collect::<Vec<u32>>();
+        let cfg = BenchmarkConfig {
+            shuffle_inputs: true,
+            seed: Some(5),
+            ..fast_cfg()
+        };
+        bench_over_inputs_configuration_labeled("shuffled", &cfg, &inputs, |i| black_box(*i));
     }
 }
@@ -0,0 +1,97 @@
+//! Pins the benchmarking thread to a single core so that the scheduler can't migrate it
+//! mid-measurement. Unsafe code is denied in this crate, so instead of calling
+//! `sched_setaffinity` directly this shells out to `taskset` on Linux.
+use crate::error::{Error, Result};
+use crate::output::stream::outln;
+
+/// Restores the thread's previous affinity when dropped
+pub(crate) struct PinGuard {
+    #[cfg(target_os = "linux")]
+    tid: String,
+    #[cfg(target_os = "linux")]
+    previous: String,
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn pin_current_thread(core: usize) -> Result<PinGuard> {
+    let tid = current_tid()?;
+    let status = std::fs::read_to_string("/proc/thread-self/status")
+        .map_err(|e| Error::new(format!("Failed to read thread status, cause {e}")))?;
+    let previous = status
+        .lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .map(|list| list.trim().to_string())
+        .ok_or_else(|| Error::new("Failed to find the thread's current affinity"))?;
+    taskset(&core.to_string(), &tid)?;
+    Ok(PinGuard { tid, previous })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pin_current_thread(_core: usize) -> Result<PinGuard> {
+    Err(Error::new("Pinning to a core is only supported on Linux"))
+}
+
+#[cfg(target_os = "linux")]
+fn current_tid() -> Result<String> {
+    // Links to `<pid>/task/<tid>`
+    let link = std::fs::read_link("/proc/thread-self")
+        .map_err(|e| Error::new(format!("Failed to find the current thread id, cause {e}")))?;
+    link.file_name()
+        .and_then(|tid| tid.to_str())
+        .map(ToString::to_string)
+        .ok_or_else(|| Error::new(format!("Unexpected thread path {}", link.display())))
+}
+
+#[cfg(target_os = "linux")]
+fn taskset(cpu_list: &str, tid: &str) -> Result<()> {
+    let output = std::process::Command::new("taskset")
+        .args(["-cp", cpu_list, tid])
+        .output()
+        .map_err(|e| Error::new(format!("Failed to run taskset, cause {e}")))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::new(format!(
+            "taskset failed, cause {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+impl Drop for PinGuard {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Err(e) = taskset(&self.previous, &self.tid) {
+            outln!(
+                "{} {e}",
+                crate::output::wrap_yellow("Failed to restore thread affinity, cause")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_and_restores() {
+        let read_allowed = || {
+            std::fs::read_to_string("/proc/thread-self/status")
+                .unwrap()
+                .lines()
+                .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+                .unwrap()
+                .trim()
+                .to_string()
+        };
+        let before = read_allowed();
+        // taskset may be unavailable, that's reported as an error rather than a panic
+        if let Ok(guard) = pin_current_thread(0) {
+            assert_eq!("0", read_allowed());
+            drop(guard);
+            assert_eq!(before, read_allowed());
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Counts allocations made by the benchmarking thread, so that code which must not allocate can
+//! be guarded against regressions. Counting requires [`CountingAllocator`] to be installed as the
+//! global allocator of the benchmark binary.
+use crate::benching::{run_routine, Plain, Routine};
+use crate::BenchmarkConfig;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::hint::black_box;
+use std::time::Duration;
+
+thread_local! {
+    static ALLOCATIONS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// A global allocator delegating to the system allocator which counts the allocations of each
+/// thread, required by [`assert_no_alloc_bench`]. Install it in the benchmark binary:
+/// ```no_run
+/// use tiny_bench::CountingAllocator;
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// ```
+pub struct CountingAllocator;
+
+fn count_allocation() {
+    // Fails only while the thread is being torn down, those allocations can't be benched anyway
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+// Delegating to the system allocator is as safe as the system allocator,
+// the counting is done in a thread local that never allocates itself
+#[allow(unsafe_code)]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Allocations made by the current thread so far, always 0 if [`CountingAllocator`] isn't installed
+fn allocations() -> u64 {
+    ALLOCATIONS.try_with(Cell::get).unwrap_or(0)
+}
+
+fn is_installed() -> bool {
+    let before = allocations();
+    drop(black_box(Box::new(0u8)));
+    allocations() > before
+}
+
+/// A sample during which the benched code allocated
+struct AllocatingSample {
+    index: usize,
+    iterations: u64,
+    allocations: u64,
+}
+
+/// Counts the allocations of each sample of the wrapped routine, warm up isn't counted
+struct CountingAllocations<R> {
+    routine: R,
+    samples: usize,
+    allocating: Vec<AllocatingSample>,
+}
+
+impl<R: Routine> Routine for CountingAllocations<R> {
+    fn warm_up(&mut self, iterations: u64) -> Duration {
+        self.routine.warm_up(iterations)
+    }
+
+    fn sample(&mut self, iterations: u64) -> u128 {
+        self.count_sample(iterations, None)
+    }
+
+    fn sample_timed_iterations(
+        &mut self,
+        iterations: u64,
+        extremes: &mut Option<(u128, u128)>,
+    ) -> u128 {
+        self.count_sample(iterations, Some(extremes))
+    }
+}
+
+impl<R: Routine> CountingAllocations<R> {
+    /// Counts the allocations of one sample, timing each iteration if there are `extremes` to widen
+    fn count_sample(
+        &mut self,
+        iterations: u64,
+        extremes: Option<&mut Option<(u128, u128)>>,
+    ) -> u128 {
+        let before = allocations();
+        let elapsed = match extremes {
+            Some(extremes) => self.routine.sample_timed_iterations(iterations, extremes),
+            None => self.routine.sample(iterations),
+        };
+        let allocations = allocations() - before;
+        if allocations > 0 {
+            self.allocating.push(AllocatingSample {
+                index: self.samples,
+                iterations,
+                allocations,
+            });
+        }
+        self.samples += 1;
+        elapsed
+    }
+}
+
+/// Benches the closure like [`crate::bench_labeled`] and panics if it allocated during any of the
+/// measured samples. A guard for hot paths that must stay allocation free, warm up isn't checked.
+/// Requires [`CountingAllocator`] to be installed as the global allocator.
+/// # Panics
+/// If the closure allocated while being measured, or if [`CountingAllocator`] isn't installed
+/// ```no_run
+/// use tiny_bench::{assert_no_alloc_bench, CountingAllocator};
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// fn main() {
+///     let values = [1u64, 2, 3];
+///     assert_no_alloc_bench("sum", || values.iter().sum::<u64>());
+/// }
+/// ```
+pub fn assert_no_alloc_bench<T, F: FnMut() -> T>(label: &'static str, closure: F) {
+    assert_no_alloc_bench_configuration(label, &BenchmarkConfig::default(), closure);
+}
+
+/// Like [`assert_no_alloc_bench`] with the supplied configuration
+/// # Panics
+/// If the closure allocated while being measured, or if [`CountingAllocator`] isn't installed, or
+/// if the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{assert_no_alloc_bench_configuration, BenchmarkConfig, CountingAllocator};
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator;
+/// fn main() {
+///     assert_no_alloc_bench_configuration("noop", &BenchmarkConfig::default(), || ());
+/// }
+/// ```
+pub fn assert_no_alloc_bench_configuration<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    closure: F,
+) {
+    assert!(
+        is_installed(),
+        "assert_no_alloc_bench requires `CountingAllocator` to be installed as the `#[global_allocator]`"
+    );
+    let mut routine = CountingAllocations {
+        routine: Plain(closure),
+        samples: 0,
+        allocating: Vec::new(),
+    };
+    run_routine(label, cfg, &mut routine);
+    if let Some(first) = routine.allocating.first() {
+        let total: u64 = routine.allocating.iter().map(|s| s.allocations).sum();
+        panic!(
+            "{label} allocated {total} times in {} of {} samples, first {} times in sample {} of {} iterations",
+            routine.allocating.len(),
+            routine.samples,
+            first.allocations,
+            first.index,
+            first.iterations,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benching::fast_cfg;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn counts_allocations() {
+        assert!(is_installed());
+        let before = allocations();
+        let v = black_box(vec![1u8; 16]);
+        assert_eq!(before + 1, allocations());
+        drop(v);
+    }
+
+    #[test]
+    fn passes_without_allocations() {
+        let values = [1u64, 2, 3];
+        assert_no_alloc_bench_configuration("no_alloc", &fast_cfg(), || values.iter().sum::<u64>());
+    }
+
+    #[test]
+    #[should_panic(expected = "allocating allocated")]
+    fn panics_on_allocation() {
+        assert_no_alloc_bench_configuration("allocating", &fast_cfg(), || vec![1u8; 16]);
+    }
+}
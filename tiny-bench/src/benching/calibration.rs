@@ -0,0 +1,72 @@
+//! A fixed reference workload that scores how fast the machine is, so that results persisted on
+//! one machine can be roughly compared with results from another, see `Calibration::Normalize`
+use crate::black_box;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+/// Rounds of the reference workload per timed run
+const WORKLOAD_ROUNDS: u64 = 10_000;
+/// Timed runs of the reference workload, the fastest is the score
+const RUNS: usize = 50;
+
+/// Nanoseconds the fixed reference workload takes on this machine, lower is faster.
+/// Measured once per process, the fastest of a few runs to keep noise out.
+/// Only comparable between builds of the same version with the same optimization level
+/// ```no_run
+/// use tiny_bench::calibration_score;
+/// println!("this machine scores {:.0}", calibration_score());
+/// ```
+#[must_use]
+pub fn calibration_score() -> f64 {
+    static SCORE: OnceLock<f64> = OnceLock::new();
+    *SCORE.get_or_init(|| {
+        (0..RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                black_box(reference_workload(black_box(WORKLOAD_ROUNDS)));
+                start.elapsed().as_nanos() as f64
+            })
+            .fold(f64::INFINITY, f64::min)
+    })
+}
+
+/// Integer arithmetic with a dependency chain and a few branches, roughly how fast the core is
+fn reference_workload(rounds: u64) -> u64 {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut acc = 0u64;
+    for round in 0..rounds {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        acc = if state & 1 == 0 {
+            acc.wrapping_add(state.rotate_left((round % 64) as u32))
+        } else {
+            acc.wrapping_mul(state | 1)
+        };
+    }
+    acc
+}
+
+/// Identifies the machine that results were persisted on, `unknown` if it can't be told
+pub(crate) fn machine_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_machine() {
+        let score = calibration_score();
+        assert!(score.is_finite() && score > 0.0);
+        assert!((score - calibration_score()).abs() < f64::EPSILON);
+        assert!(!machine_name().is_empty());
+    }
+}
@@ -0,0 +1,200 @@
+//! Per-iteration latencies, recorded individually rather than averaged over a sample, so that
+//! the shape of the distribution can be compared and not just its mean
+use crate::error::Result;
+use crate::output::analysis::criterion::OutputFormat;
+use crate::output::stream::outln;
+use crate::output::{persisted_label, resolve_label, Access};
+use crate::BenchmarkConfig;
+use std::time::Instant;
+
+/// Latencies recorded at most, so that fast code benched for a long time doesn't exhaust memory
+const MAX_LATENCIES: usize = 1_000_000;
+
+/// The sorted latencies of every recorded iteration in nanoseconds, an empirical cumulative
+/// distribution, see [`bench_latency_cdf`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatencyCdf {
+    latencies: Vec<u64>,
+}
+
+impl LatencyCdf {
+    /// Sorts the latencies, in nanoseconds
+    #[must_use]
+    pub fn new(mut latencies: Vec<u64>) -> Self {
+        latencies.sort_unstable();
+        LatencyCdf { latencies }
+    }
+
+    /// The latencies in nanoseconds, sorted ascending
+    #[must_use]
+    pub fn latencies(&self) -> &[u64] {
+        &self.latencies
+    }
+
+    /// The largest vertical distance between this and the other distribution, the
+    /// [Kolmogorov-Smirnov](https://en.wikipedia.org/wiki/Kolmogorov%E2%80%93Smirnov_test)
+    /// statistic, from 0 (identical) to 1 (no overlap at all). Unlike comparing means it
+    /// catches changes in shape, like a new slow path that's rarely taken.
+    /// 0 if either is empty
+    #[must_use]
+    pub fn ks_distance(&self, other: &LatencyCdf) -> f64 {
+        let (a, b) = (&self.latencies, &other.latencies);
+        if a.is_empty() || b.is_empty() {
+            return 0.0;
+        }
+        let (mut i, mut j) = (0, 0);
+        let mut distance = 0.0f64;
+        while i < a.len() && j < b.len() {
+            let latency = a[i].min(b[j]);
+            while i < a.len() && a[i] == latency {
+                i += 1;
+            }
+            while j < b.len() && b[j] == latency {
+                j += 1;
+            }
+            distance = distance.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+        }
+        distance
+    }
+
+    /// Probability of a `ks_distance` at least this large if both were drawn from the same
+    /// distribution, from the asymptotic Kolmogorov distribution, so approximate for small sizes
+    #[must_use]
+    pub fn ks_p_value(&self, other: &LatencyCdf) -> f64 {
+        let (n, m) = (self.latencies.len() as f64, other.latencies.len() as f64);
+        if n == 0.0 || m == 0.0 {
+            return 1.0;
+        }
+        let effective = (n * m / (n + m)).sqrt();
+        let lambda = (effective + 0.12 + 0.11 / effective) * self.ks_distance(other);
+        // The series converges too slowly to sum below this, where it's 1 to five decimals
+        if lambda < 0.3 {
+            return 1.0;
+        }
+        let p = (1..=100)
+            .map(|k| {
+                let k = f64::from(k);
+                let sign = if k % 2.0 == 0.0 { -1.0 } else { 1.0 };
+                sign * 2.0 * (-2.0 * k * k * lambda * lambda).exp()
+            })
+            .sum::<f64>();
+        p.clamp(0.0, 1.0)
+    }
+}
+
+/// Times every call of the closure individually for `measurement_time`, after warming up for
+/// `warm_up_time`, and returns the latency distribution.
+/// If `dump_results_to_disk` is set, prints the Kolmogorov-Smirnov distance to the last
+/// persisted distribution and persists this one in its place.
+/// Timing each call costs two clock reads, tens of nanoseconds, which distorts code that only
+/// takes that long, the regular benchmarks amortize that over many iterations and are the
+/// better fit there. At most a million latencies are recorded, about 8MB.
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use tiny_bench::{bench_latency_cdf, BenchmarkConfig};
+/// let cdf = bench_latency_cdf("my_benchmark", &BenchmarkConfig::default(), || {
+///     // Some code that should be benched
+/// });
+/// println!("slowest {}ns", cdf.latencies().last().unwrap());
+/// ```
+pub fn bench_latency_cdf<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    mut closure: F,
+) -> LatencyCdf {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < cfg.warm_up_time {
+        crate::black_box(closure());
+    }
+    let mut latencies = Vec::new();
+    let start = Instant::now();
+    while start.elapsed() < cfg.measurement_time && latencies.len() < MAX_LATENCIES {
+        let call = Instant::now();
+        crate::black_box(closure());
+        latencies.push(call.elapsed().as_nanos() as u64);
+    }
+    let cdf = LatencyCdf::new(latencies);
+    let previous = if cfg.dump_results_to_disk {
+        match load_latency_cdf(label, cfg) {
+            Ok(previous) => previous,
+            Err(e) => {
+                outln!("Failed to read last latency distribution, cause: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_latency_cdf(label, &cdf, previous.as_ref());
+    }
+    if cfg.dump_results_to_disk {
+        let (label, separator) = persisted_label(label, cfg, Access::Write);
+        crate::output::disk::try_write_latency_cdf(label, separator, &cdf);
+    }
+    cdf
+}
+
+/// The latency distribution persisted by the last [`bench_latency_cdf`] run of the label, from
+/// the branch that's compared with if `git_branch` is configured
+/// # Errors
+/// If the persisted distribution can't be read
+/// # Panics
+/// If the label or `run_id` is invalid and `label_policy` is `LabelPolicy::Reject`
+pub fn load_latency_cdf(label: &'static str, cfg: &BenchmarkConfig) -> Result<Option<LatencyCdf>> {
+    // Resolved like when it was persisted, so that an invalid label finds the same file
+    let label = resolve_label(label, cfg);
+    let (label, separator) = persisted_label(label, cfg, Access::Read);
+    crate::output::disk::try_read_latency_cdf(label, separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benching::fast_cfg;
+
+    #[test]
+    fn measures_ks_distance() {
+        let a = LatencyCdf::new(vec![4, 1, 3, 2]);
+        assert_eq!(&[1, 2, 3, 4], a.latencies());
+        assert!(a.ks_distance(&a).abs() < f64::EPSILON);
+        let shifted = LatencyCdf::new(vec![3, 4, 5, 6]);
+        assert!((0.5 - a.ks_distance(&shifted)).abs() < f64::EPSILON);
+        let disjoint = LatencyCdf::new(vec![10, 11]);
+        assert!((1.0 - a.ks_distance(&disjoint)).abs() < f64::EPSILON);
+        assert!(a.ks_distance(&LatencyCdf::new(vec![])).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn tells_distributions_apart() {
+        let a = LatencyCdf::new((0..1000).collect());
+        let b = LatencyCdf::new((0..1000).map(|latency| latency + 1).collect());
+        assert!(a.ks_p_value(&b) > 0.9);
+        let c = LatencyCdf::new((0..1000).map(|latency| latency + 200).collect());
+        assert!(a.ks_p_value(&c) < 0.001);
+    }
+
+    #[test]
+    fn records_latencies() {
+        let cfg = fast_cfg();
+        let cdf = bench_latency_cdf("latency_cdf", &cfg, || std::hint::black_box(1 + 1));
+        assert!(!cdf.latencies().is_empty());
+        assert!(cdf.latencies().windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn loads_latency_cdf_by_resolved_label() {
+        let cfg = BenchmarkConfig {
+            dump_results_to_disk: true,
+            label_policy: crate::LabelPolicy::Sanitize,
+            ..fast_cfg()
+        };
+        let cdf = bench_latency_cdf("latency:cdf", &cfg, || std::hint::black_box(1 + 1));
+        let loaded = load_latency_cdf("latency:cdf", &cfg).unwrap().unwrap();
+        assert_eq!(cdf.latencies(), loaded.latencies());
+    }
+}
@@ -0,0 +1,238 @@
+//! Benching over growing input sizes and fitting the times to complexity classes, to confirm
+//! empirically how the code scales
+use super::{measure, Plain};
+use crate::output::analysis::criterion::OutputFormat;
+use crate::output::analysis::regression::least_squares;
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::resolve_label;
+use crate::BenchmarkConfig;
+use std::fmt::{Display, Formatter};
+
+/// A candidate for how the time grows with the input size, see [`bench_complexity`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Complexity {
+    /// O(n)
+    Linear,
+    /// O(n log n)
+    Linearithmic,
+    /// O(n²)
+    Quadratic,
+}
+
+impl Complexity {
+    const ALL: [Complexity; 3] = [
+        Complexity::Linear,
+        Complexity::Linearithmic,
+        Complexity::Quadratic,
+    ];
+
+    fn grow(self, size: usize) -> f64 {
+        let n = size as f64;
+        match self {
+            Complexity::Linear => n,
+            Complexity::Linearithmic => n * n.max(1.0).log2(),
+            Complexity::Quadratic => n * n,
+        }
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Complexity::Linear => "O(n)",
+            Complexity::Linearithmic => "O(n log n)",
+            Complexity::Quadratic => "O(n²)",
+        })
+    }
+}
+
+/// How well the mean times fit one complexity class, `mean = intercept + coefficient * f(n)`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ComplexityFit {
+    /// The complexity class fitted to
+    pub complexity: Complexity,
+    /// Nanoseconds per unit of `f(n)`
+    pub coefficient: f64,
+    /// Nanoseconds that don't grow with the size
+    pub intercept: f64,
+    /// Coefficient of determination, the share of the variation of the means that the fit
+    /// explains, 1 is a perfect fit
+    pub r_squared: f64,
+}
+
+/// The result of [`bench_complexity`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexityReport {
+    /// Each size with the mean time per iteration at it, in nanoseconds
+    pub means: Vec<(usize, f64)>,
+    /// Every candidate fit, best first
+    pub fits: Vec<ComplexityFit>,
+}
+
+impl ComplexityReport {
+    /// The fit with the highest R²
+    #[must_use]
+    pub fn best(&self) -> ComplexityFit {
+        self.fits[0]
+    }
+}
+
+/// Benches the closure on an input of each size, made by `make_input` outside of the timed
+/// region, and fits the mean times to O(n), O(n log n), and O(n²) by least squares, reporting
+/// the best fit and its R².
+/// Sizes should span at least an order of magnitude, close sizes can't tell the classes apart
+/// and at small sizes constant overheads dominate. Results are not persisted.
+/// ```no_run
+/// use tiny_bench::{bench_complexity, Complexity};
+/// let report = bench_complexity(
+///     "sort",
+///     &[1_000, 10_000, 100_000, 1_000_000],
+///     |size| (0..size as u64).rev().collect::<Vec<_>>(),
+///     |v| {
+///         let mut v = v.clone();
+///         v.sort_unstable();
+///         v
+///     },
+/// );
+/// assert_ne!(Complexity::Quadratic, report.best().complexity);
+/// ```
+/// # Panics
+/// If there are fewer than 3 distinct sizes
+pub fn bench_complexity<I, T, M, F>(
+    label: &'static str,
+    sizes: &[usize],
+    make_input: M,
+    closure: F,
+) -> ComplexityReport
+where
+    M: Fn(usize) -> I,
+    F: FnMut(&I) -> T,
+{
+    bench_complexity_configuration(
+        label,
+        &BenchmarkConfig::default(),
+        sizes,
+        make_input,
+        closure,
+    )
+}
+
+/// Run `bench_complexity` with configuration
+/// # Panics
+/// If there are fewer than 3 distinct sizes, or if the label or `run_id` is invalid and
+/// `label_policy` is `LabelPolicy::Reject`
+pub fn bench_complexity_configuration<I, T, M, F>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    sizes: &[usize],
+    make_input: M,
+    mut closure: F,
+) -> ComplexityReport
+where
+    M: Fn(usize) -> I,
+    F: FnMut(&I) -> T,
+{
+    let mut distinct = sizes.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert!(
+        distinct.len() >= 3,
+        "fitting a complexity needs at least 3 distinct sizes, got {sizes:?}"
+    );
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let means = sizes
+        .iter()
+        .map(|&size| {
+            let input = make_input(size);
+            let measurement = measure(
+                label,
+                cfg,
+                &mut Plain(|| closure(&input)),
+                cfg.output_format.prints_progress(),
+            );
+            (
+                size,
+                analyze_sampling_data(&measurement.sampling_data, cfg).average,
+            )
+        })
+        .collect::<Vec<_>>();
+    let report = fit(means);
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_complexity(label, &report);
+    }
+    report
+}
+
+fn fit(means: Vec<(usize, f64)>) -> ComplexityReport {
+    let ys = means.iter().map(|&(_, mean)| mean).collect::<Vec<_>>();
+    let mut fits = Complexity::ALL
+        .into_iter()
+        .map(|complexity| {
+            let xs = means
+                .iter()
+                .map(|&(size, _)| complexity.grow(size))
+                .collect::<Vec<_>>();
+            let (intercept, coefficient, r_squared) = least_squares(&xs, &ys);
+            ComplexityFit {
+                complexity,
+                coefficient,
+                intercept,
+                r_squared,
+            }
+        })
+        .collect::<Vec<_>>();
+    fits.sort_by(|a, b| b.r_squared.total_cmp(&a.r_squared));
+    ComplexityReport { means, fits }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benching::fast_cfg;
+
+    #[test]
+    fn fits_complexity_classes() {
+        let sizes = [10, 100, 1_000, 10_000];
+        let linear = fit(sizes.iter().map(|&n| (n, 5.0 + 2.0 * n as f64)).collect());
+        assert_eq!(Complexity::Linear, linear.best().complexity);
+        assert!((linear.best().coefficient - 2.0).abs() < 0.000_001);
+        assert!((linear.best().intercept - 5.0).abs() < 0.000_001);
+        let quadratic = fit(sizes.iter().map(|&n| (n, (n * n) as f64)).collect());
+        assert_eq!(Complexity::Quadratic, quadratic.best().complexity);
+        let linearithmic = fit(sizes
+            .iter()
+            .map(|&n| (n, n as f64 * (n as f64).log2()))
+            .collect());
+        assert_eq!(Complexity::Linearithmic, linearithmic.best().complexity);
+        assert!((linearithmic.best().r_squared - 1.0).abs() < 0.000_001);
+    }
+
+    #[test]
+    fn benches_every_size() {
+        let cfg = fast_cfg();
+        let report = bench_complexity_configuration(
+            "complexity",
+            &cfg,
+            &[10, 100, 1000],
+            |size| vec![1u64; size],
+            |v| v.iter().sum::<u64>(),
+        );
+        assert_eq!(
+            vec![10, 100, 1000],
+            report
+                .means
+                .iter()
+                .map(|&(size, _)| size)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(3, report.fits.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 3 distinct sizes")]
+    fn rejects_too_few_sizes() {
+        bench_complexity("complexity", &[10, 10, 100], |size| size, |&size| size);
+    }
+}
@@ -0,0 +1,206 @@
+//! What the benchmarks ran on, so that numbers shared or compared over time can be interpreted
+use crate::error::{Error, Result};
+use crate::output::stream::outln;
+use crate::output::{persisted_label, wrap_yellow, Access};
+use crate::{BenchmarkConfig, DebugBuild};
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+
+/// The machine and build that benchmarks ran on, gathered best-effort, see [`environment`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment {
+    /// CPU model, from `/proc/cpuinfo` on Linux and `sysctl` on macOS, `None` elsewhere or if it
+    /// couldn't be read
+    pub cpu: Option<String>,
+    /// Logical cores available to the process, `None` if unknown
+    pub cores: Option<usize>,
+    /// `debug` if built with debug assertions, otherwise `release`. The optimization level itself
+    /// isn't visible at runtime, debug assertions are the best proxy for an unoptimized build
+    pub profile: String,
+}
+
+/// The environment of this process, gathered once, printed before the first benchmark and
+/// persisted with every result, see [`load_environment`]
+/// ```no_run
+/// use tiny_bench::environment;
+/// println!("{}", environment());
+/// ```
+pub fn environment() -> &'static Environment {
+    static ENVIRONMENT: OnceLock<Environment> = OnceLock::new();
+    ENVIRONMENT.get_or_init(|| Environment {
+        cpu: cpu_model(),
+        cores: std::thread::available_parallelism()
+            .ok()
+            .map(std::num::NonZeroUsize::get),
+        profile: if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+        .to_string(),
+    })
+}
+
+/// The environment persisted with the last run of the label, from the branch that's compared
+/// with if `git_branch` is configured
+/// # Errors
+/// If the label is invalid or the persisted environment can't be read
+pub fn load_environment(label: &'static str, cfg: &BenchmarkConfig) -> Result<Option<Environment>> {
+    let (label, separator) = persisted_label(label, cfg, Access::Read);
+    crate::output::disk::try_read_environment(label, separator)
+}
+
+/// Warns or panics, depending on the configuration, if this is a debug build
+pub(crate) fn check_build(label: &str, debug_build: DebugBuild, verbose: bool) {
+    check_profile(label, debug_build, cfg!(debug_assertions), verbose);
+}
+
+fn check_profile(label: &str, debug_build: DebugBuild, debug_assertions: bool, verbose: bool) {
+    if !debug_assertions {
+        return;
+    }
+    match debug_build {
+        DebugBuild::Ignore => {}
+        // Extra lines would break machine readable formats
+        DebugBuild::Warn => {
+            if verbose {
+                static WARNED: std::sync::Once = std::sync::Once::new();
+                WARNED.call_once(|| {
+                    outln!(
+                        "{} benching an unoptimized debug build, results won't reflect release performance, bench with `--release`",
+                        wrap_yellow("Warning:")
+                    );
+                });
+            }
+        }
+        DebugBuild::Refuse => panic!(
+            "Refusing to bench {label} in a debug build, results won't reflect release performance, bench with `--release`"
+        ),
+    }
+}
+
+impl Environment {
+    /// One `key=value` per line, the persisted format
+    pub(crate) fn to_lines(&self) -> String {
+        let cpu = self
+            .cpu
+            .as_ref()
+            .map(|cpu| format!("cpu={cpu}\n"))
+            .unwrap_or_default();
+        let cores = self
+            .cores
+            .map(|cores| format!("cores={cores}\n"))
+            .unwrap_or_default();
+        format!("{cpu}{cores}profile={}\n", self.profile)
+    }
+
+    pub(crate) fn try_from_lines(lines: &str) -> Result<Self> {
+        let mut environment = Environment {
+            cpu: None,
+            cores: None,
+            profile: String::new(),
+        };
+        for line in lines.lines().filter(|line| !line.is_empty()) {
+            let malformed = || Error::new(format!("Malformed environment line {line}"));
+            let (key, value) = line.split_once('=').ok_or_else(malformed)?;
+            match key {
+                "cpu" => environment.cpu = Some(value.to_string()),
+                "cores" => {
+                    environment.cores = Some(value.parse().map_err(|e| {
+                        Error::new(format!("Malformed environment line {line}, cause {e}"))
+                    })?);
+                }
+                "profile" => environment.profile = value.to_string(),
+                // Written by a later version, not needed to make sense of the rest
+                _ => {}
+            }
+        }
+        if environment.profile.is_empty() {
+            return Err(Error::new("Malformed environment, no profile"));
+        }
+        Ok(environment)
+    }
+}
+
+impl Display for Environment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, ", self.cpu.as_deref().unwrap_or("unknown cpu"))?;
+        match self.cores {
+            Some(cores) => write!(f, "{cores} logical cores, ")?,
+            None => write!(f, "unknown logical cores, ")?,
+        }
+        write!(f, "{} build", self.profile)
+    }
+}
+
+fn cpu_model() -> Option<String> {
+    let model = if cfg!(target_os = "macos") {
+        let output = std::process::Command::new("sysctl")
+            .args(["-n", "machdep.cpu.brand_string"])
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())?;
+        String::from_utf8(output.stdout).ok()?
+    } else {
+        let cpuinfo = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+        cpuinfo.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            (key.trim() == "model name").then(|| value.to_string())
+        })?
+    };
+    let model = model.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!model.is_empty()).then_some(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_environment() {
+        let environment = Environment {
+            cpu: Some("Some CPU @ 3.00GHz".to_string()),
+            cores: Some(16),
+            profile: "release".to_string(),
+        };
+        assert_eq!(
+            environment,
+            Environment::try_from_lines(&environment.to_lines()).unwrap()
+        );
+        assert_eq!(
+            "Some CPU @ 3.00GHz, 16 logical cores, release build",
+            environment.to_string()
+        );
+        let unknown = Environment {
+            cpu: None,
+            cores: None,
+            profile: "debug".to_string(),
+        };
+        assert_eq!(
+            unknown,
+            Environment::try_from_lines(&unknown.to_lines()).unwrap()
+        );
+        assert!(Environment::try_from_lines("cores=many\nprofile=debug\n").is_err());
+        assert!(Environment::try_from_lines("cpu=x\n").is_err());
+    }
+
+    #[test]
+    fn checks_build_profile() {
+        check_profile("release", DebugBuild::Refuse, false, true);
+        check_profile("ignored", DebugBuild::Ignore, true, true);
+        check_profile("warned", DebugBuild::Warn, true, true);
+    }
+
+    #[test]
+    #[should_panic(expected = "Refusing to bench refused in a debug build")]
+    fn refuses_debug_build() {
+        check_profile("refused", DebugBuild::Refuse, true, false);
+    }
+
+    #[test]
+    fn gathers_environment() {
+        assert!(["debug", "release"].contains(&environment().profile.as_str()));
+        assert!(environment().cores.is_some_and(|cores| cores > 0));
+    }
+}
@@ -0,0 +1,49 @@
+//! Named locks that serialize benchmarks sharing an external resource, see
+//! `BenchmarkConfig::exclusive_resource`
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// One lock per resource name, leaked so that guards can outlive the registry's lock.
+/// Resource names are few and static, so are the leaked locks
+static RESOURCES: Mutex<Option<HashMap<&'static str, &'static Mutex<()>>>> = Mutex::new(None);
+
+/// Blocks until no other benchmark in this process holds the named resource, then holds it
+/// until the guard is dropped. A benchmark that panicked while holding it doesn't poison it
+pub(crate) fn lock(name: &'static str) -> MutexGuard<'static, ()> {
+    let resource = *RESOURCES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert_with(HashMap::new)
+        .entry(name)
+        .or_insert_with(|| Box::leak(Box::new(Mutex::new(()))));
+    resource.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn serializes_holders_of_the_same_name() {
+        let holders = AtomicUsize::new(0);
+        let overlapped = AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let _guard = lock("exclusive_test_resource");
+                    if holders.fetch_add(1, Ordering::SeqCst) > 0 {
+                        overlapped.fetch_add(1, Ordering::SeqCst);
+                    }
+                    std::thread::sleep(Duration::from_millis(5));
+                    holders.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        assert_eq!(0, overlapped.load(Ordering::SeqCst));
+        // Different names don't block each other
+        let _a = lock("exclusive_test_a");
+        let _b = lock("exclusive_test_b");
+    }
+}
@@ -0,0 +1,223 @@
+//! Benching several implementations of the same thing and comparing every pair, with the
+//! p-values corrected for how many pairs were tested
+use super::{measure, Plain};
+use crate::output::analysis::criterion::OutputFormat;
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::analysis::welch::{correct_p_values, two_tailed_p_value, welch_t_value};
+use crate::output::{relative_change, resolve_label, SIGNIFICANCE_LEVEL};
+use crate::BenchmarkConfig;
+
+/// One pair of a group benched with [`bench_group`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairwiseComparison {
+    /// Name of the first implementation
+    pub a: &'static str,
+    /// Name of the second implementation
+    pub b: &'static str,
+    /// Change of the mean from `a` to `b` in percent, negative if `b` is faster
+    pub mean_change: f64,
+    /// p-value of this comparison on its own
+    pub p: f64,
+    /// p-value corrected for every comparison in the group, see `FamilyCorrection`
+    pub corrected_p: f64,
+    /// Whether the corrected p-value is significant
+    pub significant: bool,
+}
+
+/// Benches every named implementation and compares every pair of them with a Welch's t-test
+/// over their sample means, correcting the p-values with `family_correction` of the default
+/// configuration. Testing every pair of many implementations makes some uncorrected p-value
+/// significant by chance alone, the correction keeps the chance of any false winner at 5%.
+/// Results are not persisted.
+/// ```no_run
+/// use tiny_bench::bench_group;
+/// let v = (0..1000u64).collect::<Vec<_>>();
+/// let comparisons = bench_group(
+///     "sum",
+///     &mut [
+///         ("iter", &mut || v.iter().sum::<u64>()),
+///         ("fold", &mut || v.iter().fold(0, |a, b| a + b)),
+///         ("loop", &mut || {
+///             let mut sum = 0;
+///             for i in &v {
+///                 sum += i;
+///             }
+///             sum
+///         }),
+///     ],
+/// );
+/// for comparison in comparisons.iter().filter(|comparison| comparison.significant) {
+///     println!("{} differs from {}", comparison.b, comparison.a);
+/// }
+/// ```
+pub fn bench_group<T>(
+    label: &'static str,
+    implementations: &mut [(&'static str, &mut dyn FnMut() -> T)],
+) -> Vec<PairwiseComparison> {
+    bench_group_configuration(label, &BenchmarkConfig::default(), implementations)
+}
+
+/// Run `bench_group` with configuration
+/// # Panics
+/// If the label, an implementation name or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+pub fn bench_group_configuration<T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    implementations: &mut [(&'static str, &mut dyn FnMut() -> T)],
+) -> Vec<PairwiseComparison> {
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let analyses = implementations
+        .iter_mut()
+        .map(|(name, closure)| {
+            let name = resolve_label(name, cfg);
+            let measurement = measure(
+                name,
+                cfg,
+                &mut Plain(closure),
+                cfg.output_format.prints_progress(),
+            );
+            (name, analyze_sampling_data(&measurement.sampling_data, cfg))
+        })
+        .collect::<Vec<_>>();
+    let mut comparisons = Vec::new();
+    for (i, (a, analysis_a)) in analyses.iter().enumerate() {
+        for (b, analysis_b) in &analyses[i + 1..] {
+            let t = welch_t_value(
+                analysis_b.average,
+                analysis_b.variance,
+                analysis_b.per_sample_average.len() as f64,
+                analysis_a.average,
+                analysis_a.variance,
+                analysis_a.per_sample_average.len() as f64,
+            );
+            comparisons.push(PairwiseComparison {
+                a,
+                b,
+                mean_change: relative_change(analysis_b.average, analysis_a.average),
+                p: two_tailed_p_value(t),
+                corrected_p: 1.0,
+                significant: false,
+            });
+        }
+    }
+    let p_values = comparisons.iter().map(|c| c.p).collect::<Vec<_>>();
+    for (comparison, corrected_p) in comparisons
+        .iter_mut()
+        .zip(correct_p_values(&p_values, cfg.family_correction))
+    {
+        comparison.corrected_p = corrected_p;
+        comparison.significant = corrected_p <= SIGNIFICANCE_LEVEL;
+    }
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_group_comparison(label, cfg.family_correction, &comparisons);
+    }
+    comparisons
+}
+
+/// Benches the same code under each variant of its own configuration, like a feature flag or a
+/// tuning parameter, and compares every pair like [`bench_group`]. Each variant is benched under
+/// its name.
+/// ```no_run
+/// use tiny_bench::bench_variants;
+/// let v = (0..10_000u64).collect::<Vec<_>>();
+/// let comparisons = bench_variants("chunked_sum", &[("64", 64), ("1024", 1024)], |&chunk| {
+///     v.chunks(chunk).map(|c| c.iter().sum::<u64>()).sum::<u64>()
+/// });
+/// for comparison in comparisons.iter().filter(|comparison| comparison.significant) {
+///     println!("{} differs from {}", comparison.b, comparison.a);
+/// }
+/// ```
+pub fn bench_variants<C, T, F: Fn(&C) -> T>(
+    label: &'static str,
+    variants: &[(&'static str, C)],
+    run: F,
+) -> Vec<PairwiseComparison> {
+    bench_variants_configuration(label, &BenchmarkConfig::default(), variants, run)
+}
+
+/// Run `bench_variants` with configuration
+/// # Panics
+/// If the label, an implementation name or `run_id` is invalid and `label_policy` is
+/// `LabelPolicy::Reject`
+pub fn bench_variants_configuration<C, T, F: Fn(&C) -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    variants: &[(&'static str, C)],
+    run: F,
+) -> Vec<PairwiseComparison> {
+    let run = &run;
+    let mut closures = variants
+        .iter()
+        .map(|(name, variant)| (*name, move || run(variant)))
+        .collect::<Vec<_>>();
+    let mut implementations = closures
+        .iter_mut()
+        .map(|(name, closure)| (*name, closure as &mut dyn FnMut() -> T))
+        .collect::<Vec<_>>();
+    bench_group_configuration(label, cfg, &mut implementations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benching::fast_cfg;
+
+    #[test]
+    fn compares_every_pair() {
+        let cfg = fast_cfg();
+        let comparisons = bench_group_configuration(
+            "group",
+            &cfg,
+            &mut [
+                ("one", &mut || std::hint::black_box(1) + 1),
+                ("two", &mut || std::hint::black_box(2) + 2),
+                ("three", &mut || std::hint::black_box(3) + 3),
+            ],
+        );
+        let pairs = comparisons
+            .iter()
+            .map(|comparison| (comparison.a, comparison.b))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![("one", "two"), ("one", "three"), ("two", "three")],
+            pairs
+        );
+        assert!(comparisons
+            .iter()
+            .all(|comparison| comparison.corrected_p >= comparison.p));
+    }
+
+    #[test]
+    fn compares_every_variant() {
+        let cfg = fast_cfg();
+        let comparisons = bench_variants_configuration(
+            "variants",
+            &cfg,
+            &[("small", 10u64), ("large", 1000u64)],
+            |&n| (0..std::hint::black_box(n)).sum::<u64>(),
+        );
+        assert_eq!(1, comparisons.len());
+        assert_eq!(("small", "large"), (comparisons[0].a, comparisons[0].b));
+    }
+
+    #[test]
+    fn resolves_implementation_names() {
+        let cfg = BenchmarkConfig {
+            label_policy: crate::LabelPolicy::Sanitize,
+            ..fast_cfg()
+        };
+        let comparisons = bench_variants_configuration(
+            "resolved_variants",
+            &cfg,
+            &[("naive:sum", 10u64), ("fast?sum", 10u64)],
+            |&n| (0..std::hint::black_box(n)).sum::<u64>(),
+        );
+        assert_eq!(
+            ("naive_sum", "fast_sum"),
+            (comparisons[0].a, comparisons[0].b)
+        );
+    }
+}
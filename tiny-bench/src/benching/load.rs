@@ -0,0 +1,75 @@
+//! Checks how busy the machine is before benching, results from a machine that's busy with
+//! something else, like a build, are mostly noise.
+use crate::output::stream::outln;
+use crate::output::{wrap_bold_green, wrap_yellow};
+use crate::{LoadAction, LoadCheck};
+
+/// Warns or panics, depending on the check's action, if the load is above the check's limit
+pub(crate) fn check(label: &str, load_check: LoadCheck) {
+    let Some(load) = load_per_core() else {
+        return;
+    };
+    if load <= load_check.max_load_per_core {
+        return;
+    }
+    match load_check.action {
+        LoadAction::Warn => outln!(
+            "{} {} load per core is {load:.2}, above the limit of {:.2}, results will be noisy",
+            wrap_bold_green(label),
+            wrap_yellow("Warning:"),
+            load_check.max_load_per_core
+        ),
+        LoadAction::Refuse => panic!(
+            "Refusing to bench {label}, load per core is {load:.2}, above the limit of {:.2}",
+            load_check.max_load_per_core
+        ),
+    }
+}
+
+/// The one minute load average divided by the number of cores, if it can be read
+fn load_per_core() -> Option<f64> {
+    let cores = std::thread::available_parallelism().ok()?.get();
+    Some(load_average()? / cores as f64)
+}
+
+#[cfg(target_os = "linux")]
+fn load_average() -> Option<f64> {
+    parse_load_average(&std::fs::read_to_string("/proc/loadavg").ok()?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn load_average() -> Option<f64> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn parse_load_average(loadavg: &str) -> Option<f64> {
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_load_average() {
+        assert_eq!(
+            Some(1.25),
+            parse_load_average("1.25 0.80 0.50 2/345 6789\n")
+        );
+        assert!(load_per_core().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Refusing to bench")]
+    fn refuses_under_load() {
+        check(
+            "loaded",
+            LoadCheck {
+                max_load_per_core: -1.0,
+                action: LoadAction::Refuse,
+            },
+        );
+    }
+}
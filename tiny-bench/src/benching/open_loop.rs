@@ -0,0 +1,165 @@
+//! Open-loop benchmarking, operations are issued at a fixed rate regardless of how long the
+//! previous ones took, so that latency includes the time an operation waited for its turn
+use crate::output::analysis::criterion::OutputFormat;
+use crate::output::resolve_label;
+use crate::BenchmarkConfig;
+use std::time::{Duration, Instant};
+
+/// Latency distribution of an open-loop benchmark, see [`bench_open_loop`]
+#[derive(Debug, Copy, Clone)]
+pub struct OpenLoopStats {
+    /// Operations issued
+    pub operations: usize,
+    /// Operations that started later than scheduled because the previous ones ran late
+    pub late: usize,
+    /// Median latency
+    pub p50: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// 99.9th percentile latency
+    pub p999: Duration,
+    /// Highest latency
+    pub max: Duration,
+}
+
+/// Issues the closure at a fixed rate of `rate_per_sec` for `duration`, sleeping until each
+/// operation is due, and reports the latency distribution.
+/// Latency is measured from when the operation was scheduled rather than from when it started,
+/// so if an operation runs long the ones queued behind it count the time they waited, like
+/// requests arriving at a service would. Running as fast as possible instead hides that.
+/// # Panics
+/// If `rate_per_sec` isn't a positive finite number
+/// ```no_run
+/// use std::time::Duration;
+/// use tiny_bench::bench_open_loop;
+/// let stats = bench_open_loop("my_benchmark", 1000.0, Duration::from_secs(5), || {
+///     // Some code that should be benched
+/// });
+/// println!("p99 {:?}", stats.p99);
+/// ```
+pub fn bench_open_loop<T, F: FnMut() -> T>(
+    label: &'static str,
+    rate_per_sec: f64,
+    duration: Duration,
+    closure: F,
+) -> OpenLoopStats {
+    bench_open_loop_configuration(
+        label,
+        &BenchmarkConfig::default(),
+        rate_per_sec,
+        duration,
+        closure,
+    )
+}
+
+/// Like [`bench_open_loop`] with the supplied configuration, of which the label and output
+/// settings apply. Nothing is persisted
+/// # Panics
+/// If `rate_per_sec` isn't a positive finite number, or if the label or `run_id` is invalid and
+/// `label_policy` is `LabelPolicy::Reject`
+/// ```no_run
+/// use std::time::Duration;
+/// use tiny_bench::{bench_open_loop_configuration, BenchmarkConfig, OutputStream};
+/// let cfg = BenchmarkConfig {
+///     output_stream: OutputStream::Stderr,
+///     ..BenchmarkConfig::default()
+/// };
+/// let stats = bench_open_loop_configuration("my_benchmark", &cfg, 1000.0, Duration::from_secs(5), || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn bench_open_loop_configuration<T, F: FnMut() -> T>(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    rate_per_sec: f64,
+    duration: Duration,
+    mut closure: F,
+) -> OpenLoopStats {
+    assert!(
+        rate_per_sec.is_finite() && rate_per_sec > 0.0,
+        "open-loop rate must be a positive finite number, got {rate_per_sec}"
+    );
+    let label = resolve_label(label, cfg);
+    let _output = crate::output::stream::redirect(&cfg.output_stream);
+    let interval = Duration::from_secs_f64(1.0 / rate_per_sec);
+    let mut latencies = Vec::new();
+    let mut late = 0;
+    let start = Instant::now();
+    let mut scheduled = start;
+    while scheduled.duration_since(start) < duration {
+        let now = Instant::now();
+        if now < scheduled {
+            std::thread::sleep(scheduled - now);
+        } else if now > scheduled {
+            late += 1;
+        }
+        crate::black_box(closure());
+        latencies.push(scheduled.elapsed());
+        scheduled += interval;
+    }
+    let stats = OpenLoopStats::from_latencies(latencies, late);
+    // Extra lines would break machine readable formats
+    if cfg.output_format == OutputFormat::Pretty {
+        crate::output::print_open_loop(label, rate_per_sec, &stats);
+    }
+    stats
+}
+
+impl OpenLoopStats {
+    fn from_latencies(mut latencies: Vec<Duration>, late: usize) -> Self {
+        latencies.sort_unstable();
+        OpenLoopStats {
+            operations: latencies.len(),
+            late,
+            p50: nearest_rank(&latencies, 0.5),
+            p99: nearest_rank(&latencies, 0.99),
+            p999: nearest_rank(&latencies, 0.999),
+            max: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// The smallest latency that at least `quantile` of the sorted latencies are at or below,
+/// zero if there are none
+fn nearest_rank(sorted: &[Duration], quantile: f64) -> Duration {
+    let rank = (quantile * sorted.len() as f64).ceil() as usize;
+    sorted
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_nearest_rank_percentiles() {
+        let latencies = (1..=1000).map(Duration::from_micros).collect::<Vec<_>>();
+        let stats = OpenLoopStats::from_latencies(latencies.into_iter().rev().collect(), 3);
+        assert_eq!(1000, stats.operations);
+        assert_eq!(3, stats.late);
+        assert_eq!(Duration::from_micros(500), stats.p50);
+        assert_eq!(Duration::from_micros(990), stats.p99);
+        assert_eq!(Duration::from_micros(999), stats.p999);
+        assert_eq!(Duration::from_millis(1), stats.max);
+        assert_eq!(Duration::ZERO, OpenLoopStats::from_latencies(vec![], 0).p99);
+    }
+
+    #[test]
+    fn paces_at_rate() {
+        let mut calls = 0;
+        let stats = bench_open_loop("open_loop", 1000.0, Duration::from_millis(20), || {
+            calls += 1;
+        });
+        assert_eq!(20, stats.operations);
+        assert_eq!(20, calls);
+        assert!(stats.p50 <= stats.p99 && stats.p99 <= stats.p999 && stats.p999 <= stats.max);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive finite")]
+    fn rejects_zero_rate() {
+        bench_open_loop("open_loop", 0.0, Duration::from_millis(1), || ());
+    }
+}
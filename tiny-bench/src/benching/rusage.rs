@@ -0,0 +1,72 @@
+//! Reads scheduler and memory counters of the benchmarking thread from `/proc`, which helps
+//! explaining noisy results. On other platforms nothing is read and nothing is reported.
+
+/// Counters of the current thread at some point in time
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Usage {
+    pub(crate) involuntary_context_switches: u64,
+    pub(crate) major_page_faults: u64,
+}
+
+impl Usage {
+    /// Counts accumulated between `earlier` and `self`
+    pub(crate) fn since(self, earlier: Usage) -> Usage {
+        Usage {
+            involuntary_context_switches: self
+                .involuntary_context_switches
+                .saturating_sub(earlier.involuntary_context_switches),
+            major_page_faults: self
+                .major_page_faults
+                .saturating_sub(earlier.major_page_faults),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn read() -> Option<Usage> {
+    let status = std::fs::read_to_string("/proc/thread-self/status").ok()?;
+    let stat = std::fs::read_to_string("/proc/thread-self/stat").ok()?;
+    Some(Usage {
+        involuntary_context_switches: parse_involuntary_context_switches(&status)?,
+        major_page_faults: parse_major_page_faults(&stat)?,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn read() -> Option<Usage> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn parse_involuntary_context_switches(status: &str) -> Option<u64> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("nonvoluntary_ctxt_switches:"))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+#[cfg(target_os = "linux")]
+fn parse_major_page_faults(stat: &str) -> Option<u64> {
+    // The command name is in parentheses and may contain spaces, fields are counted after it.
+    // `majflt` is field 12 and the state following the command name is field 3
+    stat.rsplit_once(')')?
+        .1
+        .split_whitespace()
+        .nth(12 - 3)
+        .and_then(|count| count.parse().ok())
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_proc_counters() {
+        let status = "Name:\tbench\nvoluntary_ctxt_switches:\t10\nnonvoluntary_ctxt_switches:\t3\n";
+        assert_eq!(Some(3), parse_involuntary_context_switches(status));
+        let stat = "1234 (my bench) R 1 1234 1234 0 -1 4194560 150 0 7 0 5 2";
+        assert_eq!(Some(7), parse_major_page_faults(stat));
+        assert!(read().is_some());
+    }
+}
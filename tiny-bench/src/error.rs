@@ -2,8 +2,9 @@ use std::fmt::{Display, Formatter};
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
+/// Something went wrong reading or writing persisted results
 #[derive(Debug)]
-pub(crate) struct Error {
+pub struct Error {
     msg: String,
 }
 
@@ -15,12 +15,53 @@ pub(crate) mod benching;
 
 #[cfg(feature = "bench")]
 pub use benching::{
-    bench, bench_labeled, bench_with_configuration, bench_with_configuration_labeled,
-    bench_with_setup, bench_with_setup_configuration, bench_with_setup_configuration_labeled,
-    bench_with_setup_labeled,
+    assert_bench_against_target, bench, bench_against_target, bench_against_target_configuration,
+    bench_checked_output, bench_checked_output_configuration, bench_collect,
+    bench_compare_with_setup, bench_compare_with_setup_configuration, bench_complexity,
+    bench_complexity_configuration, bench_ephemeral, bench_ephemeral_with_setup,
+    bench_establish_baseline, bench_from_reader, bench_from_reader_configuration, bench_group,
+    bench_group_configuration, bench_labeled, bench_latency_cdf, bench_open_loop,
+    bench_open_loop_configuration, bench_over_inputs_configuration_labeled,
+    bench_over_inputs_labeled, bench_per_sample_setup, bench_per_sample_setup_configuration,
+    bench_relative, bench_relative_with_configuration, bench_returning_config, bench_subtracted,
+    bench_subtracted_with_configuration, bench_variants, bench_variants_configuration,
+    bench_with_configuration, bench_with_configuration_labeled,
+    bench_with_configuration_labeled_returning, bench_with_metric, bench_with_metric_configuration,
+    bench_with_setup, bench_with_setup_borrowing_configuration_labeled,
+    bench_with_setup_borrowing_labeled, bench_with_setup_configuration,
+    bench_with_setup_configuration_labeled, bench_with_setup_labeled, calibration_score,
+    commit_session, compare_runs, environment, estimate_duration,
+    estimate_minimum_detectable_effect, export_session, import_session, is_significantly_different,
+    is_significantly_different_with_configuration, load_environment, load_latency_cdf, load_run,
+    load_run_with_configuration, nanos_to_duration, print_summary, regression_report,
+    regression_report_with_configuration, replay, report_durations, report_relative_to_first,
+    take_results, BenchResult, BenchStats, BorrowingFn, CommitManifest, Complexity, ComplexityFit,
+    ComplexityReport, Environment, LatencyCdf, OpenLoopStats, PairwiseComparison, RegressionReport,
+    RunComparison, SampleResult, Session, TQuantiles, Trend,
 };
+#[cfg(feature = "alloc-count")]
+pub use benching::{assert_no_alloc_bench, assert_no_alloc_bench_configuration, CountingAllocator};
 #[cfg(feature = "bench")]
-pub use output::analysis::criterion::BenchmarkConfig;
+pub use output::analysis::criterion::{
+    calculate_iterations, Aggregation, BenchmarkConfig, Calibration, ColdStart, DebugBuild,
+    ElisionCheck, Extremes, FamilyCorrection, GitBranch, LabelPolicy, LoadAction, LoadCheck,
+    NoiseThreshold, NumberFormat, OutlierFilter, OutputFormat, OutputStream, Persist, Resamples,
+    SamplePause, SampleStream, Settle, StopWhen, WarmUpAction, WarmUpCheck, ZeroTimeSamples,
+};
+#[cfg(feature = "bench")]
+pub use output::analysis::random::set_global_seed;
+#[cfg(feature = "json-codec")]
+pub use output::codec::JsonCodec;
+#[cfg(feature = "bench")]
+pub use output::codec::{BinaryCodec, PersistedSamples, ResultCodec};
+#[cfg(feature = "bench")]
+pub use output::diff_text::print_diff_text;
+#[cfg(feature = "tap")]
+pub use output::tap::print_tap_plan;
+/// The standard library's optimization barrier, which is a compiler intrinsic rather than a
+/// volatile read, so there's no need for a specialized version of it for small `Copy` values,
+/// copying them out of a barrier on a reference instead is measured to be slower in
+/// `examples/black_box_copy.rs`.
 #[cfg(feature = "bench")]
 pub use std::hint::black_box;
 
@@ -30,10 +71,19 @@ mod error;
 #[cfg(any(feature = "bench", feature = "timer"))]
 pub(crate) mod output;
 
+#[cfg(any(feature = "bench", feature = "timer"))]
+pub use error::Error;
+#[cfg(any(feature = "bench", feature = "timer"))]
+pub use output::disk::{clear_all_persisted, clear_persisted, list_persisted};
+#[cfg(any(feature = "bench", feature = "timer"))]
+pub use output::{format_duration, format_rate};
+
 #[cfg(feature = "timer")]
 pub(crate) mod timing;
 
 #[cfg(feature = "timer")]
 pub use timing::{
-    run_timed, run_timed_from_iterator, run_timed_times, Timeable, TimedIterator, TimingData,
+    run_timed, run_timed_from_iterator, run_timed_times, run_timed_times_collect,
+    run_timed_times_parallel, run_timed_value, ParallelTimingData, Timeable, TimedIterator,
+    TimingData,
 };
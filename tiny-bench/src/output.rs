@@ -1,32 +1,66 @@
 pub(crate) mod analysis;
+#[cfg(feature = "bench")]
+pub(crate) mod codec;
+#[cfg(feature = "criterion-json")]
+pub(crate) mod criterion_json;
+#[cfg(feature = "bench")]
+pub(crate) mod diff_text;
 pub(crate) mod disk;
+#[cfg(feature = "bench")]
+pub(crate) mod json;
+#[cfg(feature = "bench")]
+pub(crate) mod plot;
 pub(crate) mod ser;
+pub(crate) mod stream;
+#[cfg(feature = "tap")]
+pub(crate) mod tap;
 
 #[cfg(feature = "bench")]
-use crate::benching::SamplingData;
+use crate::benching::{BenchResult, RunComparison, SamplingData, TQuantiles};
 #[cfg(feature = "bench")]
 use crate::output::analysis::criterion::{
-    calculate_p_value, calculate_t_value, resample, BenchmarkConfig, SamplingDataSimpleAnalysis,
+    calculate_p_value, calculate_paired_t_value, calculate_t_value, resample, t_quantiles,
+    Aggregation, BenchmarkConfig, Calibration, GitBranch, LabelPolicy, NoiseThreshold,
+    NumberFormat, Persist, SamplingDataSimpleAnalysis,
 };
 #[cfg(feature = "bench")]
-use crate::output::analysis::sample_data::simple_analyze_sampling_data;
+use crate::output::analysis::random::Rng;
+#[cfg(feature = "bench")]
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::analysis::welch::{two_tailed_p_value, welch_t_value};
+#[cfg(feature = "bench")]
+use crate::output::codec::ResultCodec;
+#[cfg(feature = "bench")]
+use crate::output::stream::out;
+use crate::output::stream::outln;
 #[cfg(feature = "timer")]
 use crate::timing::TimingData;
+#[cfg(feature = "bench")]
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "bench")]
+use std::fmt::Write;
+#[cfg(feature = "bench")]
+use std::sync::{Mutex, PoisonError};
 
 /// Percentage increase which is deemed to be big enough to matter.
 /// Only used for highlighting output
 #[cfg(feature = "timer")]
 const TIMING_NOISE_THRESHOLD: f64 = 5.0;
 
+/// p-value under which a timing result is deemed significant enough to matter.
+/// Only used for highlighting output
+#[cfg(feature = "timer")]
+const TIMING_SIGNIFICANCE_LEVEL: f64 = 0.05;
+
 /// Percentage increase which is deemed to be big enough to matter.
 /// Only used for highlighting output
 #[cfg(feature = "bench")]
-const NOISE_THRESHOLD: f64 = 1.0;
+pub(crate) const NOISE_THRESHOLD: f64 = 1.0;
 
 /// p-value under which a result is deemed significant enough to matter.
 /// Only used for highlighting output
 #[cfg(feature = "bench")]
-const SIGNIFICANCE_LEVEL: f64 = 0.05;
+pub(crate) const SIGNIFICANCE_LEVEL: f64 = 0.05;
 
 #[cfg(feature = "timer")]
 pub(crate) struct LabeledOutput<Output> {
@@ -60,9 +94,10 @@ pub(crate) trait Output {
         &self,
         label: &'static str,
         sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
         cfg: &BenchmarkConfig,
         total_iters: u128,
-    );
+    ) -> Option<RunComparison>;
 }
 
 /// Just prints the results straight to stdout
@@ -81,12 +116,68 @@ impl Output for SimpleStdout {
         &self,
         label: &'static str,
         sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
+        cfg: &BenchmarkConfig,
+        total_iters: u128,
+    ) -> Option<RunComparison> {
+        print_sample_header(
+            label,
+            total_iters,
+            analysis.elapsed,
+            sampling_data.samples.len() as u64,
+            cfg.number_format,
+        );
+        print_analysis(analysis);
+        print_aggregate(analysis, cfg.aggregation);
+        if let Some(metric) = sampling_data.metric_per_iteration() {
+            print_metric(metric, None, cfg);
+        }
+        if cfg.emit_raw_line {
+            print_raw_line(
+                label,
+                analysis,
+                total_iters,
+                sampling_data.samples.len() as u64,
+            );
+        }
+        None
+    }
+}
+
+/// Prints the results like [`SimpleStdout`], but only if the mean is over the threshold
+#[cfg(feature = "timer")]
+pub struct SlowStdout {
+    pub(crate) threshold: std::time::Duration,
+}
+
+#[cfg(feature = "timer")]
+impl Output for SlowStdout {
+    #[cfg(feature = "timer")]
+    fn dump_timing_data(&self, label: &'static str, data: TimingData) {
+        if data.mean() <= self.threshold.as_nanos() as f64 {
+            return;
+        }
+        outln!(
+            "{} {label} took longer than {} per iteration",
+            wrap_yellow("Slow:"),
+            fmt_time(self.threshold.as_nanos() as f64)
+        );
+        SimpleStdout.dump_timing_data(label, data);
+    }
+
+    #[cfg(feature = "bench")]
+    fn dump_sampling_data(
+        &self,
+        label: &'static str,
+        sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
         cfg: &BenchmarkConfig,
         total_iters: u128,
-    ) {
-        let analysis = simple_analyze_sampling_data(sampling_data);
-        print_sample_header(label, total_iters, analysis.elapsed, cfg.num_samples as u64);
-        print_analysis(&analysis);
+    ) -> Option<RunComparison> {
+        if analysis.average <= self.threshold.as_nanos() as f64 {
+            return None;
+        }
+        SimpleStdout.dump_sampling_data(label, sampling_data, analysis, cfg, total_iters)
     }
 }
 
@@ -102,26 +193,57 @@ impl Output for ComparedStdout {
         timer_print_elapsed(data.min_nanos as f64, mean, data.max_nanos as f64);
         match maybe_old {
             Ok(Some(old)) => {
-                let min_change = (data.min_nanos as f64 / old.min_nanos as f64 - 1f64) * 100f64;
-                let max_change = (data.max_nanos as f64 / old.max_nanos as f64 - 1f64) * 100f64;
-                let mean_change =
-                    (mean / (old.elapsed as f64 / old.iterations as f64) - 1f64) * 100f64;
-                let mean_comparison = if mean_change >= TIMING_NOISE_THRESHOLD {
-                    MeanComparison::new(mean_change, Comparison::Better)
-                } else if mean_change <= -TIMING_NOISE_THRESHOLD {
-                    MeanComparison::new(mean_change, Comparison::Worse)
+                let min_change = relative_change(data.min_nanos as f64, old.min_nanos as f64);
+                let max_change = relative_change(data.max_nanos as f64, old.max_nanos as f64);
+                let mean_change = relative_change(mean, old.mean());
+                // A single iteration in either run leaves no variance to test against
+                if data.iterations < 2 || old.iterations < 2 {
+                    let mean_comparison = if mean_change >= TIMING_NOISE_THRESHOLD {
+                        MeanComparison::new(mean_change, Comparison::Worse)
+                    } else if mean_change <= -TIMING_NOISE_THRESHOLD {
+                        MeanComparison::new(mean_change, Comparison::Better)
+                    } else {
+                        MeanComparison::new(mean_change, Comparison::Same)
+                    };
+                    print_cmp(
+                        min_change,
+                        &mean_comparison,
+                        max_change,
+                        "p=? single sample",
+                        DEFAULT_CHANGE_DECIMALS,
+                    );
                 } else {
-                    MeanComparison::new(mean_change, Comparison::Same)
-                };
-                print_cmp(
-                    min_change,
-                    &mean_comparison,
-                    max_change,
-                    "p=? single sample",
-                );
+                    let t = welch_t_value(
+                        mean,
+                        data.variance(),
+                        data.iterations as f64,
+                        old.mean(),
+                        old.variance(),
+                        old.iterations as f64,
+                    );
+                    let p = two_tailed_p_value(t);
+                    let mean_comparison = if mean_change.abs() >= TIMING_NOISE_THRESHOLD
+                        && p <= TIMING_SIGNIFICANCE_LEVEL
+                    {
+                        if mean_change > 0.0 {
+                            MeanComparison::new(mean_change, Comparison::Worse)
+                        } else {
+                            MeanComparison::new(mean_change, Comparison::Better)
+                        }
+                    } else {
+                        MeanComparison::new(mean_change, Comparison::Same)
+                    };
+                    print_cmp(
+                        min_change,
+                        &mean_comparison,
+                        max_change,
+                        &format!("p = {p:.2}"),
+                        DEFAULT_CHANGE_DECIMALS,
+                    );
+                }
             }
             Err(e) => {
-                println!(
+                outln!(
                     "{}, cause {e}",
                     wrap_high_insensity_red("Failed to read last results")
                 );
@@ -136,44 +258,53 @@ impl Output for ComparedStdout {
         &self,
         label: &'static str,
         sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
         cfg: &BenchmarkConfig,
         total_iters: u128,
-    ) {
-        let analysis = simple_analyze_sampling_data(sampling_data);
-        print_sample_header(label, total_iters, analysis.elapsed, cfg.num_samples as u64);
-        print_analysis(&analysis);
-        match disk::try_read_last_simpling(label) {
-            Ok(Some(last)) => {
-                let old_analysis = simple_analyze_sampling_data(&last);
-                let min_change = (analysis.min / old_analysis.min - 1f64) * 100f64;
-                let max_change = (analysis.max / old_analysis.max - 1f64) * 100f64;
-                let mean_change = (analysis.average / old_analysis.average - 1f64) * 100f64;
-                let t = calculate_t_value(
-                    &analysis.per_sample_average,
-                    &old_analysis.per_sample_average,
-                );
-                let t_distribution = resample(
-                    &analysis.per_sample_average,
-                    &old_analysis.per_sample_average,
-                    cfg.num_resamples,
+    ) -> Option<RunComparison> {
+        print_sample_header(
+            label,
+            total_iters,
+            analysis.elapsed,
+            sampling_data.samples.len() as u64,
+            cfg.number_format,
+        );
+        print_analysis(analysis);
+        print_aggregate(analysis, cfg.aggregation);
+        let mut comparison = None;
+        match read_last_sampling(label, cfg) {
+            Ok(Some((last, normalization))) => {
+                let old_analysis = analyze_sampling_data(&last, cfg);
+                let cmp = compare_sampling(analysis, &old_analysis, cfg);
+                if let Some(normalization) = &normalization {
+                    print_normalized(&normalization.machine, normalization.ratio);
+                }
+                print_cmp(
+                    cmp.min_change,
+                    &cmp.mean,
+                    cmp.max_change,
+                    &format!(
+                        "p = {:.2}{}",
+                        cmp.p,
+                        if normalization.is_some() {
+                            ", normalized"
+                        } else {
+                            ""
+                        }
+                    ),
+                    cfg.change_decimals,
                 );
-                let p = calculate_p_value(t, &t_distribution);
-                let mean_change = if mean_change.abs() >= NOISE_THRESHOLD && p <= SIGNIFICANCE_LEVEL
-                {
-                    if mean_change > 0.0 {
-                        MeanComparison::new(mean_change, Comparison::Worse)
-                    } else if mean_change < 0.0 {
-                        MeanComparison::new(mean_change, Comparison::Better)
-                    } else {
-                        MeanComparison::new(mean_change, Comparison::Same)
+                let mut run_comparison = cmp.to_run_comparison();
+                if let Some(metric) = sampling_data.metric_per_iteration() {
+                    if let Some(last_metric) = last.metric_per_iteration() {
+                        run_comparison.metric_change = Some(relative_change(metric, last_metric));
+                        run_comparison.metric_p = Some(metric_p_value(sampling_data, &last));
                     }
-                } else {
-                    MeanComparison::new(mean_change, Comparison::Same)
-                };
-                print_cmp(min_change, &mean_change, max_change, &format!("p = {p:.2}"));
+                }
+                comparison = Some(run_comparison);
             }
             Err(e) => {
-                println!(
+                outln!(
                     "{}, cause {e}",
                     wrap_high_insensity_red("Failed to read last sample")
                 );
@@ -181,13 +312,298 @@ impl Output for ComparedStdout {
             _ => {}
         }
 
-        disk::try_write_last_simpling(label, sampling_data);
+        if let Some(metric) = sampling_data.metric_per_iteration() {
+            print_metric(
+                metric,
+                comparison.and_then(|comparison| comparison.metric_change.zip(comparison.metric_p)),
+                cfg,
+            );
+        }
+        write_last_sampling(label, cfg, sampling_data);
+        if cfg.emit_raw_line {
+            print_raw_line(
+                label,
+                analysis,
+                total_iters,
+                sampling_data.samples.len() as u64,
+            );
+        }
+        comparison
+    }
+}
+
+/// Reads the last persisted run of the label. With `Calibration::Normalize` a run persisted on
+/// another machine is scaled to the speed of this one, and how it was scaled is returned
+#[cfg(feature = "bench")]
+pub(crate) fn read_last_sampling(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+) -> crate::error::Result<Option<(SamplingData, Option<Normalization>)>> {
+    let (label, separator) = persisted_label(label, cfg, Access::Read);
+    let Some(last) = disk::try_read_last_simpling(label, separator, cfg.codec)? else {
+        return Ok(None);
+    };
+    if cfg.calibration == Calibration::None {
+        return Ok(Some((last, None)));
+    }
+    match disk::try_read_calibration(label, separator)? {
+        Some((score, machine)) if machine != crate::benching::machine_name() => {
+            let ratio = crate::benching::calibration_score() / score;
+            Ok(Some((
+                last.scaled(ratio),
+                Some(Normalization { ratio, machine }),
+            )))
+        }
+        _ => Ok(Some((last, None))),
+    }
+}
+
+/// How a run persisted on another machine was scaled to the speed of this one
+#[cfg(feature = "bench")]
+pub(crate) struct Normalization {
+    /// Calibration score of this machine divided by that of the other machine
+    pub(crate) ratio: f64,
+    pub(crate) machine: String,
+}
+
+/// Persists the run with the environment, and with `Calibration::Normalize` the calibration
+/// score of this machine, also under the run id if configured. With `Persist::OnCommit` it's
+/// held until `commit_session` instead
+#[cfg(feature = "bench")]
+pub(crate) fn write_last_sampling(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    sampling_data: &SamplingData,
+) {
+    let calibrate = cfg.calibration == Calibration::Normalize;
+    let targets = std::iter::once(persisted_label(label, cfg, Access::Write))
+        .chain(cfg.run_id.map(|run_id| run_label(label, cfg, run_id)));
+    for (label, separator) in targets {
+        if cfg.persist == Persist::OnCommit {
+            let mut pending = PENDING.lock().unwrap_or_else(PoisonError::into_inner);
+            // Only the last run of a label is kept, so that each label rotates once per session
+            pending.retain(|run| (run.label, run.separator) != (label, separator));
+            pending.push(PendingRun {
+                label,
+                separator,
+                sampling_data: sampling_data.clone(),
+                calibrate,
+                codec: cfg.codec,
+            });
+        } else if let Err(e) =
+            persist_sampling(label, separator, sampling_data, calibrate, cfg.codec)
+        {
+            outln!(
+                "{} {e}",
+                wrap_high_insensity_red("Failed to write sampling data, cause:")
+            );
+        }
+    }
+}
+
+/// A run held back until `commit_session`, see `Persist::OnCommit`
+#[cfg(feature = "bench")]
+struct PendingRun {
+    label: &'static str,
+    separator: Option<&'static str>,
+    sampling_data: SamplingData,
+    calibrate: bool,
+    codec: &'static dyn ResultCodec,
+}
+
+#[cfg(feature = "bench")]
+static PENDING: Mutex<Vec<PendingRun>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "bench")]
+fn persist_sampling(
+    label: &'static str,
+    separator: Option<&'static str>,
+    sampling_data: &SamplingData,
+    calibrate: bool,
+    codec: &dyn ResultCodec,
+) -> crate::error::Result<()> {
+    let written = disk::write_last_simpling(label, separator, sampling_data, codec);
+    // They describe this run whether or not its sample could be written
+    disk::try_write_environment(label, separator, crate::benching::environment());
+    if calibrate {
+        disk::try_write_calibration(
+            label,
+            separator,
+            crate::benching::calibration_score(),
+            &crate::benching::machine_name(),
+        );
+    }
+    // The sample is what comparisons read, a run without its history entry is still written
+    if written.is_ok() {
+        if let Err(e) = disk::append_history(label, separator, sampling_data) {
+            outln!("{} {e}", wrap_yellow("Failed to append to history, cause:"));
+        }
+    }
+    written
+}
+
+/// Writes every run held back by `Persist::OnCommit`, in the order they ran
+#[cfg(feature = "bench")]
+pub(crate) fn commit_pending() -> crate::benching::CommitManifest {
+    let pending = std::mem::take(&mut *PENDING.lock().unwrap_or_else(PoisonError::into_inner));
+    let mut manifest = crate::benching::CommitManifest {
+        written: Vec::new(),
+        failed: Vec::new(),
+    };
+    for run in pending {
+        let label = match run.separator {
+            Some(separator) => run.label.split(separator).collect::<Vec<_>>().join("/"),
+            None => run.label.to_string(),
+        };
+        match persist_sampling(
+            run.label,
+            run.separator,
+            &run.sampling_data,
+            run.calibrate,
+            run.codec,
+        ) {
+            Ok(()) => manifest.written.push(label),
+            Err(e) => manifest.failed.push((label, e)),
+        }
+    }
+    manifest
+}
+
+/// Prints the environment before the first benchmark of the process, later ones ran on the same
+#[cfg(feature = "bench")]
+pub(crate) fn print_environment_once() {
+    static PRINTED: std::sync::Once = std::sync::Once::new();
+    PRINTED.call_once(|| {
+        outln!(
+            "{} {}",
+            wrap_bold_green("environment"),
+            crate::benching::environment()
+        );
+    });
+}
+
+#[cfg(feature = "bench")]
+fn print_normalized(machine: &str, ratio: f64) {
+    outln!(
+        "{} comparing with a run from {machine} normalized by the calibration score ratio {ratio:.2}, comparisons across machines are approximate",
+        wrap_yellow("Warning:")
+    );
+}
+
+/// The change from an old run to a new one
+#[cfg(feature = "bench")]
+pub(crate) struct SamplingComparison {
+    pub(crate) min_change: f64,
+    pub(crate) max_change: f64,
+    pub(crate) mean: MeanComparison,
+    pub(crate) p: f64,
+    pub(crate) t: f64,
+    pub(crate) t_quantiles: Option<TQuantiles>,
+}
+
+#[cfg(feature = "bench")]
+impl SamplingComparison {
+    #[cfg(feature = "tap")]
+    pub(crate) fn is_regression(&self) -> bool {
+        matches!(self.mean.comparison, Comparison::Worse)
+    }
+
+    pub(crate) fn to_run_comparison(&self) -> RunComparison {
+        RunComparison {
+            min_change: self.min_change,
+            mean_change: self.mean.mean,
+            max_change: self.max_change,
+            p: self.p,
+            t: self.t,
+            t_quantiles: self.t_quantiles,
+            regressed: matches!(self.mean.comparison, Comparison::Worse),
+            improved: matches!(self.mean.comparison, Comparison::Better),
+            metric_change: None,
+            metric_p: None,
+        }
+    }
+}
+
+/// One line per benchmark with its mean and the change from its last run, if any.
+/// Sorted by label and then by mean, so that the same benchmarks produce the same report
+/// whichever order they ran in
+#[cfg(feature = "bench")]
+pub(crate) fn fmt_summary(results: &[BenchResult]) -> String {
+    let mut results = results.iter().collect::<Vec<_>>();
+    results.sort_by(|a, b| {
+        a.label
+            .cmp(b.label)
+            .then(a.stats.mean.total_cmp(&b.stats.mean))
+    });
+    let label_width = results
+        .iter()
+        .map(|r| r.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for result in results {
+        let _ = write!(
+            out,
+            "{:label_width$}  {:>9}",
+            result.label,
+            fmt_time(result.stats.mean)
+        );
+        if let Some(cmp) = result.comparison {
+            let _ = write!(
+                out,
+                "  {} (p = {:.2})",
+                fmt_signed_change(cmp.mean_change),
+                cmp.p
+            );
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn compare_sampling(
+    analysis: &SamplingDataSimpleAnalysis,
+    old_analysis: &SamplingDataSimpleAnalysis,
+    cfg: &BenchmarkConfig,
+) -> SamplingComparison {
+    let min_change = relative_change(analysis.min, old_analysis.min);
+    let max_change = relative_change(analysis.max, old_analysis.max);
+    let mean_change = relative_change(
+        analysis.aggregate(cfg.aggregation),
+        old_analysis.aggregate(cfg.aggregation),
+    );
+    let samples = analysis.aggregated_samples(cfg.aggregation);
+    let num_resamples = cfg.num_resamples.count(samples.len());
+    let (p, t, t_quantiles) = if num_resamples == 0 {
+        // No bootstrap, the summary statistics are enough for a classical test
+        let (mean, variance, len) = analysis.aggregated_summary(cfg.aggregation);
+        let (old_mean, old_variance, old_len) = old_analysis.aggregated_summary(cfg.aggregation);
+        let t = welch_t_value(mean, variance, len, old_mean, old_variance, old_len);
+        (two_tailed_p_value(t), t, None)
+    } else {
+        let old_samples = old_analysis.aggregated_samples(cfg.aggregation);
+        let t = calculate_t_value(samples, old_samples);
+        // The first stream of the seed shuffles inputs, resample independently from the next one
+        let mut rng = Rng::from_seed_or_time(cfg.seed);
+        let _shuffle = rng.split();
+        let mut t_distribution = resample(&mut rng, samples, old_samples, num_resamples);
+        let p = calculate_p_value(t, &t_distribution);
+        (p, t, Some(t_quantiles(&mut t_distribution)))
+    };
+    SamplingComparison {
+        min_change,
+        max_change,
+        mean: MeanComparison::classify(mean_change, p, cfg.noise_threshold),
+        p,
+        t,
+        t_quantiles,
     }
 }
 
 #[cfg(feature = "timer")]
 pub(crate) fn print_timer_header(label: &'static str, data: &TimingData) {
-    println!(
+    outln!(
         "{} [{} iterations in {}]:",
         wrap_bold_green(label),
         fmt_num(data.iterations as f64),
@@ -195,30 +611,475 @@ pub(crate) fn print_timer_header(label: &'static str, data: &TimingData) {
     );
 }
 
+#[cfg(feature = "timer")]
+pub(crate) fn print_throughput(per_sec: f64, threads: usize) {
+    outln!(
+        "\tthroughput\t{} iterations/s over {} threads",
+        wrap_high_intensity_white(&fmt_num(per_sec)),
+        fmt_num(threads as f64)
+    );
+}
+
 #[cfg(feature = "bench")]
 pub(crate) fn print_sample_header(
-    label: &'static str,
+    label: &str,
     total_iterations: u128,
     total_elapsed: u128,
     num_samples: u64,
+    number_format: NumberFormat,
 ) {
-    println!(
+    outln!(
         "{} [{} iterations in {} with {} samples]:",
         wrap_bold_green(label),
-        fmt_num(total_iterations as f64),
+        fmt_num_with(total_iterations as f64, number_format),
         fmt_time(total_elapsed as f64),
-        fmt_num(num_samples as f64)
+        fmt_num_with(num_samples as f64, number_format)
+    );
+}
+
+/// Prints both sides of an A/B comparison and the change going from a to b
+#[cfg(feature = "bench")]
+pub(crate) fn print_paired_comparison(
+    label: &'static str,
+    data_a: &SamplingData,
+    data_b: &SamplingData,
+    total_iters: u128,
+    cfg: &BenchmarkConfig,
+) {
+    let analysis_a = analyze_sampling_data(data_a, cfg);
+    let analysis_b = analyze_sampling_data(data_b, cfg);
+    let num_samples = data_a.samples.len() as u64;
+    print_sample_header(
+        &format!("{label} (a)"),
+        total_iters,
+        analysis_a.elapsed,
+        num_samples,
+        cfg.number_format,
+    );
+    print_analysis(&analysis_a);
+    print_sample_header(
+        &format!("{label} (b)"),
+        total_iters,
+        analysis_b.elapsed,
+        num_samples,
+        cfg.number_format,
+    );
+    print_analysis(&analysis_b);
+    // The analysis sorts its per sample averages, pairing needs them in measured order
+    let t = calculate_paired_t_value(&data_b.per_sample_average(), &data_a.per_sample_average());
+    let p = two_tailed_p_value(t);
+    let min_change = relative_change(analysis_b.min, analysis_a.min);
+    let max_change = relative_change(analysis_b.max, analysis_a.max);
+    let mean_change = relative_change(analysis_b.average, analysis_a.average);
+    print_cmp(
+        min_change,
+        &MeanComparison::classify(mean_change, p, cfg.noise_threshold),
+        max_change,
+        &format!("b vs a, paired p = {p:.2}"),
+        cfg.change_decimals,
+    );
+}
+
+/// Prints after how many samples the running mean and median converged, see
+/// `BenchmarkConfig::convergence_tolerance`
+#[cfg(feature = "bench")]
+pub(crate) fn print_convergence(per_sample_average: &[f64], tolerance_pct: f64) {
+    use crate::output::analysis::criterion::{calculate_mean, converged_after, median_of};
+    let describe = |converged: Option<usize>| match converged {
+        Some(samples) => format!("after {samples} samples"),
+        None => wrap_yellow("not converged"),
+    };
+    let mean = converged_after(per_sample_average, tolerance_pct, calculate_mean);
+    let median = converged_after(per_sample_average, tolerance_pct, median_of);
+    outln!(
+        "\tconverged\tmean {}, median {} (within {tolerance_pct}%)",
+        describe(mean),
+        describe(median)
+    );
+    if mean.is_none() || median.is_none() {
+        outln!(
+            "{} more than {} samples are needed for a stable result, consider increasing num_samples",
+            wrap_yellow("Warning:"),
+            per_sample_average.len()
+        );
+    }
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_mean_interval(low: f64, high: f64, confidence: f64) {
+    outln!(
+        "\tmean\t[{} {}] ({:.0}% confidence, bootstrapped)",
+        fmt_time(low),
+        fmt_time(high),
+        confidence * 100.0
+    );
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_plot(per_sample_average: &[f64], width: usize) {
+    outln!("\tsamples");
+    for line in plot::fmt_braille_plot(per_sample_average, width, plot::PLOT_ROWS).lines() {
+        outln!("\t{line}");
+    }
+}
+
+/// Prints the time of the first call and how many times slower than the mean it was
+#[cfg(feature = "bench")]
+pub(crate) fn print_cold_start(cold_start: std::time::Duration, mean: f64) {
+    let cold_start = cold_start.as_nanos() as f64;
+    outln!(
+        "\tcold\t{} first call, {:.1}× the steady state mean",
+        wrap_high_intensity_white(&fmt_time(cold_start)),
+        cold_start / mean
+    );
+}
+
+/// Prints the fastest and slowest single iteration, see `Extremes::Iteration`
+#[cfg(feature = "bench")]
+pub(crate) fn print_iteration_extremes(min: u128, max: u128) {
+    outln!(
+        "\titeration\t[{} {}]:\t[{} {}]",
+        wrap_gray("min"),
+        wrap_gray("max"),
+        wrap_gray(&fmt_time(min as f64)),
+        wrap_gray(&fmt_time(max as f64)),
+    );
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_paused(paused: std::time::Duration) {
+    outln!(
+        "\tpaused\t{} between samples, not measured",
+        fmt_time(paused.as_nanos() as f64)
+    );
+}
+
+/// Prints counters that explain noisy results, see the `linux-rusage` feature
+#[cfg(feature = "linux-rusage")]
+pub(crate) fn print_usage(
+    involuntary_context_switches: u64,
+    major_page_faults: u64,
+    total_iters: u128,
+) {
+    outln!(
+        "\tcontext switches\t{} involuntary ({:.6} per iteration)",
+        fmt_num(involuntary_context_switches as f64),
+        involuntary_context_switches as f64 / total_iters as f64,
+    );
+    outln!(
+        "\tpage faults\t{} major ({:.6} per iteration)",
+        fmt_num(major_page_faults as f64),
+        major_page_faults as f64 / total_iters as f64,
+    );
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_regression_report(
+    label: &str,
+    report: &crate::RegressionReport,
+    change_decimals: usize,
+) {
+    use crate::Trend;
+    if report.trend == Trend::InsufficientData {
+        outln!(
+            "{} {} persisted runs, at least 3 are needed for a trend",
+            wrap_bold_green(label),
+            report.runs
+        );
+        return;
+    }
+    let trend = match report.trend {
+        Trend::Improving => wrap_high_intensity_green("improving"),
+        Trend::Regressing => wrap_high_insensity_red("regressing"),
+        Trend::Stable | Trend::InsufficientData => "stable".to_string(),
+    };
+    outln!(
+        "{} [{} persisted runs]:",
+        wrap_bold_green(label),
+        report.runs
+    );
+    outln!(
+        "\ttrend\t{trend} {} per run ({}, R² = {:.2})",
+        fmt_time(report.slope.abs()),
+        fmt_change(report.relative_slope, change_decimals),
+        report.r_squared
+    );
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_minimum_detectable_effect(
+    label: &str,
+    samples: usize,
+    effect: f64,
+    power: f64,
+) {
+    outln!(
+        "{} with {} samples changes of {} can be detected (p < {SIGNIFICANCE_LEVEL}, power {power})",
+        wrap_bold_green(label),
+        samples,
+        wrap_high_intensity_white(&format!("~{effect:.2}%")),
+    );
+}
+
+/// Welch's t-test over the secondary metric per iteration of each sample of both runs
+#[cfg(feature = "bench")]
+fn metric_p_value(new: &SamplingData, old: &SamplingData) -> f64 {
+    two_tailed_p_value(calculate_t_value(
+        &new.metric_per_sample(),
+        &old.metric_per_sample(),
+    ))
+}
+
+/// Prints the secondary metric per iteration and its change from the last run with the p-value
+/// of the change, if known
+#[cfg(feature = "bench")]
+fn print_metric(metric: f64, change: Option<(f64, f64)>, cfg: &BenchmarkConfig) {
+    out!(
+        "\tmetric\t{} per iteration",
+        wrap_high_intensity_white(&fmt_num_with(metric, cfg.number_format))
+    );
+    match change {
+        Some((change, p)) => outln!(" ({}, p = {p:.2})", fmt_change(change, cfg.change_decimals)),
+        None => outln!(),
+    }
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_relative(reference_mean: f64, ratio: f64) {
+    outln!(
+        "\trelative\t{} of reference mean {}",
+        wrap_high_intensity_white(&format!("{ratio:.2}×")),
+        fmt_time(reference_mean)
+    );
+}
+
+/// Prints how many latencies were recorded and, if there's a previous distribution, how far
+/// this one is from it
+#[cfg(feature = "bench")]
+pub(crate) fn print_latency_cdf(
+    label: &str,
+    cdf: &crate::benching::LatencyCdf,
+    previous: Option<&crate::benching::LatencyCdf>,
+) {
+    let latencies = cdf.latencies();
+    let median = latencies
+        .get(latencies.len() / 2)
+        .copied()
+        .unwrap_or_default();
+    outln!(
+        "{} {} latencies recorded, median {}",
+        wrap_bold_green(label),
+        latencies.len(),
+        wrap_high_intensity_white(&fmt_time(median as f64))
+    );
+    if let Some(previous) = previous {
+        let p = cdf.ks_p_value(previous);
+        let distance = format!("{:.3}", cdf.ks_distance(previous));
+        let distance = if p < SIGNIFICANCE_LEVEL {
+            wrap_yellow(&distance)
+        } else {
+            wrap_high_intensity_white(&distance)
+        };
+        outln!("\tKS distance {distance} from last run (p = {p:.2})");
+    }
+}
+
+/// Prints the latency percentiles of an open-loop benchmark
+#[cfg(feature = "bench")]
+pub(crate) fn print_open_loop(
+    label: &str,
+    rate_per_sec: f64,
+    stats: &crate::benching::OpenLoopStats,
+) {
+    outln!(
+        "{} {} operations at {}/s, {} started late",
+        wrap_bold_green(label),
+        stats.operations,
+        fmt_num(rate_per_sec),
+        stats.late
+    );
+    for (name, latency) in [
+        ("p50", stats.p50),
+        ("p99", stats.p99),
+        ("p99.9", stats.p999),
+        ("max", stats.max),
+    ] {
+        outln!(
+            "\t{name}\t{}",
+            wrap_high_intensity_white(&fmt_time(latency.as_nanos() as f64))
+        );
+    }
+}
+
+/// Prints the mean at each size and how well each complexity class fits, best first
+#[cfg(feature = "bench")]
+pub(crate) fn print_complexity(label: &str, report: &crate::benching::ComplexityReport) {
+    outln!(
+        "{} best fit {} (R² = {:.3})",
+        wrap_bold_green(label),
+        wrap_high_intensity_white(&report.best().complexity.to_string()),
+        report.best().r_squared
+    );
+    for (size, mean) in &report.means {
+        outln!("\tn = {size}\t{}", fmt_time(*mean));
+    }
+    for fit in &report.fits {
+        outln!("\t{}\tR² = {:.3}", fit.complexity, fit.r_squared);
+    }
+}
+
+/// Prints every pairwise comparison of a group, the p-values before and after the correction
+#[cfg(feature = "bench")]
+pub(crate) fn print_group_comparison(
+    label: &str,
+    correction: crate::FamilyCorrection,
+    comparisons: &[crate::benching::PairwiseComparison],
+) {
+    outln!(
+        "{} {} pairwise comparisons, {correction:?} corrected",
+        wrap_bold_green(label),
+        comparisons.len()
+    );
+    // Sorted like the summary, so that the report doesn't depend on the order of the group
+    let mut comparisons = comparisons.iter().collect::<Vec<_>>();
+    comparisons.sort_by(|x, y| {
+        x.b.cmp(y.b)
+            .then(x.a.cmp(y.a))
+            .then(x.mean_change.total_cmp(&y.mean_change))
+    });
+    for comparison in comparisons {
+        let change = fmt_signed_change(comparison.mean_change);
+        let change = if !comparison.significant {
+            wrap_high_intensity_white(&change)
+        } else if comparison.mean_change > 0.0 {
+            wrap_high_insensity_red(&change)
+        } else {
+            wrap_high_intensity_green(&change)
+        };
+        outln!(
+            "\t{} vs {}\t{change} p = {:.2}, corrected p = {:.2}",
+            comparison.b,
+            comparison.a,
+            comparison.p,
+            comparison.corrected_p
+        );
+    }
+}
+
+/// Prints the mean and relative standard deviation of each run, marking the selected one
+#[cfg(feature = "bench")]
+pub(crate) fn print_baseline_runs(label: &str, runs: &[(f64, f64)], selected: usize) {
+    outln!(
+        "{} baseline from {} runs",
+        wrap_bold_green(label),
+        runs.len()
+    );
+    for (index, (mean, relative_stddev)) in runs.iter().enumerate() {
+        let line = format!("run {index}\t{} ± {relative_stddev:.2}%", fmt_time(*mean));
+        if index == selected {
+            outln!(
+                "\t{} selected, lowest relative standard deviation",
+                wrap_high_intensity_white(&line)
+            );
+        } else {
+            outln!("\t{line}");
+        }
+    }
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_session_reference() {
+    outln!(
+        "\tspeedup\t{} session reference",
+        wrap_high_intensity_white("1.00×")
+    );
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_session_relative(reference_label: &str, ratio: f64) {
+    outln!(
+        "\tspeedup\t{} relative to {reference_label}",
+        wrap_high_intensity_white(&fmt_session_relative(ratio))
+    );
+}
+
+/// How long a benchmark took relative to the session reference, and how much faster or slower
+/// that is
+#[cfg(feature = "bench")]
+fn fmt_session_relative(ratio: f64) -> String {
+    let percent = (ratio - 1.0).abs() * 100.0;
+    let direction = if ratio < 1.0 { "faster" } else { "slower" };
+    format!("{ratio:.2}× ({percent:.0}% {direction})")
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_subtracted(
+    label: &str,
+    control_mean: f64,
+    treatment_mean: f64,
+    difference: f64,
+    half_width: f64,
+) {
+    outln!(
+        "{} treatment {} - control {}",
+        wrap_bold_green(label),
+        fmt_time(treatment_mean),
+        fmt_time(control_mean)
+    );
+    let sign = if difference < 0.0 { "-" } else { "" };
+    outln!(
+        "\tsubtracted\t{} ± {} (95% confidence)",
+        wrap_high_intensity_white(&format!("{sign}{}", fmt_time(difference.abs()))),
+        fmt_time(half_width)
+    );
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_target(mean: f64, target: f64) {
+    let difference = fmt_time((mean - target).abs());
+    let percent = if target > 0.0 {
+        format!(" ({:.1}%)", ((mean / target - 1.0) * 100.0).abs())
+    } else {
+        String::new()
+    };
+    if mean <= target {
+        outln!(
+            "\ttarget\t{} {difference}{percent} under {}",
+            wrap_high_intensity_green("pass"),
+            fmt_time(target)
+        );
+    } else {
+        outln!(
+            "\ttarget\t{} {difference}{percent} over {}",
+            wrap_high_insensity_red("fail"),
+            fmt_time(target)
+        );
+    }
+}
+
+/// Prints the aggregate if it's something other than the mean, which is always printed
+#[cfg(feature = "bench")]
+pub(crate) fn print_aggregate(analysis: &SamplingDataSimpleAnalysis, aggregation: Aggregation) {
+    let name = match aggregation {
+        Aggregation::Mean => return,
+        Aggregation::TrimmedMean(fraction) => format!("{:.0}% trimmed mean", fraction * 100.0),
+        Aggregation::Median => "median".to_string(),
+        Aggregation::Minimum => "minimum".to_string(),
+    };
+    outln!(
+        "\taggregate\t{name} {}",
+        wrap_high_intensity_white(&fmt_time(analysis.aggregate(aggregation)))
     );
 }
 
 #[cfg(feature = "bench")]
 pub(crate) fn print_analysis(analysis: &SamplingDataSimpleAnalysis) {
     // Variance has the unit T-squared,
-    println!(
+    outln!(
         "\telapsed\t[{} {} {}]:\t[{} {} {}] (sample data: med = {}, var = {}², stddev = {})",
-        wrap_gray("min"),
+        wrap_gray("min sample avg"),
         wrap_high_intensity_white("mean"),
-        wrap_gray("max"),
+        wrap_gray("max sample avg"),
         wrap_gray(&fmt_time(analysis.min)),
         wrap_high_intensity_white(&fmt_time(analysis.average)),
         wrap_gray(&fmt_time(analysis.max)),
@@ -226,12 +1087,85 @@ pub(crate) fn print_analysis(analysis: &SamplingDataSimpleAnalysis) {
         fmt_time(analysis.variance),
         fmt_time(analysis.stddev),
     );
+    outln!(
+        "\tspread\t[p25 p75 {} {}]:\t[{} {} {} {}]",
+        wrap_high_intensity_white("p95"),
+        wrap_high_intensity_white("p99"),
+        fmt_time(analysis.p25),
+        fmt_time(analysis.p75),
+        wrap_high_intensity_white(&fmt_time(analysis.p95)),
+        wrap_high_intensity_white(&fmt_time(analysis.p99)),
+    );
+    let outliers = &analysis.outliers;
+    if outliers.total() > 0 {
+        let measurements = analysis.per_sample_average.len()
+            + if outliers.filtered {
+                outliers.severe()
+            } else {
+                0
+            };
+        let kinds = [
+            (outliers.low_severe, "low severe"),
+            (outliers.low_mild, "low mild"),
+            (outliers.high_mild, "high mild"),
+            (outliers.high_severe, "high severe"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{count} {kind}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+        outln!(
+            "\toutliers\tfound {} outliers among {} measurements ({:.2}%): {kinds}{}",
+            outliers.total(),
+            measurements,
+            outliers.total() as f64 / measurements as f64 * 100.0,
+            if outliers.filtered && outliers.severe() > 0 {
+                ", severe ones left out of the mean"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn print_raw_line(
+    label: &'static str,
+    analysis: &SamplingDataSimpleAnalysis,
+    total_iterations: u128,
+    num_samples: u64,
+) {
+    outln!(
+        "{}",
+        fmt_raw_line(
+            label,
+            analysis.elapsed,
+            total_iterations,
+            analysis.average,
+            num_samples
+        )
+    );
+}
+
+/// Keep in sync with the format documented on `BenchmarkConfig::emit_raw_line`
+#[cfg(feature = "bench")]
+fn fmt_raw_line(
+    label: &'static str,
+    total_elapsed: u128,
+    total_iterations: u128,
+    mean: f64,
+    num_samples: u64,
+) -> String {
+    format!(
+        "RAW label={label:?} total_ns={total_elapsed} iters={total_iterations} mean_ns={mean:.3} samples={num_samples}"
+    )
 }
 
 #[cfg(feature = "timer")]
 pub(crate) fn timer_print_elapsed(min: f64, mean: f64, max: f64) {
     // Variance has the unit T-squared,
-    println!(
+    outln!(
         "\telapsed\t[{} {} {}]:\t[{} {} {}]",
         wrap_gray("min"),
         wrap_high_intensity_white("mean"),
@@ -243,8 +1177,8 @@ pub(crate) fn timer_print_elapsed(min: f64, mean: f64, max: f64) {
 }
 
 pub(crate) struct MeanComparison {
-    mean: f64,
-    comparison: Comparison,
+    pub(crate) mean: f64,
+    pub(crate) comparison: Comparison,
 }
 
 impl MeanComparison {
@@ -252,11 +1186,26 @@ impl MeanComparison {
         Self { mean, comparison }
     }
 
-    pub(crate) fn format(&self) -> String {
+    /// Only a change that's both big enough and significant enough counts as better or worse
+    #[cfg(feature = "bench")]
+    pub(crate) fn classify(mean_change: f64, p: f64, threshold: NoiseThreshold) -> Self {
+        if p > SIGNIFICANCE_LEVEL {
+            MeanComparison::new(mean_change, Comparison::Same)
+        } else if mean_change > 0.0 && mean_change >= threshold.regression_pct {
+            MeanComparison::new(mean_change, Comparison::Worse)
+        } else if mean_change < 0.0 && -mean_change >= threshold.improvement_pct {
+            MeanComparison::new(mean_change, Comparison::Better)
+        } else {
+            MeanComparison::new(mean_change, Comparison::Same)
+        }
+    }
+
+    pub(crate) fn format(&self, decimals: usize) -> String {
+        let change = fmt_change(self.mean, decimals);
         match self.comparison {
-            Comparison::Worse => wrap_high_insensity_red(&fmt_change(self.mean)),
-            Comparison::Same => wrap_high_intensity_white(&fmt_change(self.mean)),
-            Comparison::Better => wrap_high_intensity_green(&fmt_change(self.mean)),
+            Comparison::Worse => wrap_high_insensity_red(&change),
+            Comparison::Same => wrap_high_intensity_white(&change),
+            Comparison::Better => wrap_high_intensity_green(&change),
         }
     }
 }
@@ -267,22 +1216,58 @@ pub enum Comparison {
     Better,
 }
 
-pub(crate) fn print_cmp(min: f64, mean: &MeanComparison, max: f64, reliability_comment: &str) {
-    println!(
+pub(crate) fn print_cmp(
+    min: f64,
+    mean: &MeanComparison,
+    max: f64,
+    reliability_comment: &str,
+    decimals: usize,
+) {
+    outln!(
         "\tchange\t[{} {} {}]:\t[{} {} {}] ({reliability_comment})",
         wrap_gray("min"),
         wrap_high_intensity_white("mean"),
         wrap_gray("max"),
-        wrap_gray(&fmt_change(min)),
-        mean.format(),
-        wrap_gray(&fmt_change(max)),
+        wrap_gray(&fmt_change(min, decimals)),
+        mean.format(decimals),
+        wrap_gray(&fmt_change(max, decimals)),
     );
 }
 
+/// Printed in place of a number that couldn't be calculated, like a change from a run that took
+/// no measurable time
+const NOT_AVAILABLE: &str = "n/a";
+
+/// The change from `old` to `new` in percent, NaN if there's no finite change, when `old` took
+/// no measurable time, which prints as n/a
+pub(crate) fn relative_change(new: f64, old: f64) -> f64 {
+    let change = (new / old - 1.0) * 100.0;
+    if change.is_finite() {
+        change
+    } else {
+        f64::NAN
+    }
+}
+
+/// A change with its sign and two decimals, for the compact one line formats
+#[cfg(feature = "bench")]
+pub(crate) fn fmt_signed_change(change: f64) -> String {
+    if change.is_finite() {
+        format!("{change:+.2}%")
+    } else {
+        NOT_AVAILABLE.to_string()
+    }
+}
+
 const NANO_LIMIT: f64 = 1000f64;
 const MICRO_LIMIT: f64 = NANO_LIMIT * 1000f64;
 const MILLI_LIMIT: f64 = MICRO_LIMIT * 1000f64;
 
+const THOUSAND: f64 = 1000f64;
+const MILLION: f64 = THOUSAND * 1000f64;
+const BILLION: f64 = MILLION * 1000f64;
+const TRILLION: f64 = BILLION * 1000f64;
+
 pub(crate) fn wrap_bold_green(text: &str) -> String {
     format!("\x1b[1;32m{text}\x1b[0m")
 }
@@ -307,7 +1292,13 @@ pub(crate) fn wrap_high_intensity_white(text: &str) -> String {
     format!("\x1b[0;97m{text}\x1b[0m")
 }
 
+/// Totals are accumulated as integer nanoseconds and only converted to `f64` to be printed,
+/// which is exact up to 2^53 nanoseconds, about 104 days, and off by less than one part in 10^15
+/// beyond, far below the two printed decimals
 pub(crate) fn fmt_time(time: f64) -> String {
+    if !time.is_finite() {
+        return NOT_AVAILABLE.to_string();
+    }
     // Nanos
     if time < NANO_LIMIT {
         format!("{time:.2}ns")
@@ -320,19 +1311,75 @@ pub(crate) fn fmt_time(time: f64) -> String {
     }
 }
 
-fn fmt_change(change: f64) -> String {
-    format!("{change:.4}%")
+/// Formats the duration the way tiny-bench prints times, like `1.50µs`
+/// ```
+/// use std::time::Duration;
+/// assert_eq!("1.50µs", tiny_bench::format_duration(Duration::from_nanos(1500)));
+/// ```
+#[must_use]
+pub fn format_duration(duration: std::time::Duration) -> String {
+    fmt_time(duration.as_nanos() as f64)
+}
+
+/// Formats a rate per second the way tiny-bench prints throughput, like `2.5 million/s`
+/// ```
+/// assert_eq!("2.5 million/s", tiny_bench::format_rate(2_500_000.0));
+/// ```
+#[must_use]
+pub fn format_rate(per_second: f64) -> String {
+    format!("{}/s", fmt_num(per_second))
+}
+
+/// Decimals of percentages when there's no configuration, like when timing
+#[cfg(feature = "timer")]
+pub(crate) const DEFAULT_CHANGE_DECIMALS: usize = 2;
+
+pub(crate) fn fmt_change(change: f64, decimals: usize) -> String {
+    if !change.is_finite() {
+        return NOT_AVAILABLE.to_string();
+    }
+    let formatted = format!("{change:.decimals$}%");
+    // A change that rounds to zero would otherwise print as -0.00%
+    match formatted.strip_prefix('-') {
+        Some(unsigned) if unsigned.chars().all(|c| matches!(c, '0' | '.' | '%')) => {
+            unsigned.to_string()
+        }
+        _ => formatted,
+    }
 }
 
 pub(crate) fn fmt_num(num: f64) -> String {
-    if num < NANO_LIMIT {
+    if num < THOUSAND {
         format!("{num:.1}")
-    } else if num < MICRO_LIMIT {
-        format!("{:.1} thousand", num / NANO_LIMIT)
-    } else if num < MILLI_LIMIT {
-        format!("{:.1}M", num / MICRO_LIMIT)
+    } else if num < MILLION {
+        format!("{:.1} thousand", num / THOUSAND)
+    } else if num < BILLION {
+        format!("{:.1} million", num / MILLION)
+    } else if num < TRILLION {
+        format!("{:.1} billion", num / BILLION)
+    } else if num < TRILLION * THOUSAND {
+        format!("{:.1} trillion", num / TRILLION)
     } else {
-        format!("{:.1}B", num / MILLI_LIMIT)
+        format!("{num:.1e}")
+    }
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn fmt_num_with(num: f64, number_format: NumberFormat) -> String {
+    match number_format {
+        NumberFormat::Words => fmt_num(num),
+        NumberFormat::Grouped => {
+            let digits = format!("{num:.0}");
+            let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+            for (i, digit) in digits.chars().enumerate() {
+                if i > 0 && (digits.len() - i) % 3 == 0 {
+                    grouped.push(',');
+                }
+                grouped.push(digit);
+            }
+            grouped
+        }
+        NumberFormat::Plain => format!("{num:.0}"),
     }
 }
 
@@ -351,7 +1398,7 @@ pub(crate) enum LabelValidationResult {
 
 pub(crate) fn fallback_to_anonymous_on_invalid_label(label: &'static str) -> &'static str {
     if let LabelValidationResult::Invalid(reason) = validate_label(label) {
-        println!(
+        outln!(
             "{} falling back to 'anonymous'.",
             wrap_high_insensity_red(reason)
         );
@@ -361,6 +1408,200 @@ pub(crate) fn fallback_to_anonymous_on_invalid_label(label: &'static str) -> &'s
     }
 }
 
+/// Validates the label, validating each component separately if a separator is configured,
+/// and handles invalid labels according to the configured [`LabelPolicy`]
+#[cfg(feature = "bench")]
+pub(crate) fn resolve_label(label: &'static str, cfg: &BenchmarkConfig) -> &'static str {
+    let separator = cfg
+        .label_separator
+        .filter(|separator| !separator.is_empty());
+    resolve_with(label, separator, cfg.label_policy)
+}
+
+#[cfg(feature = "bench")]
+fn resolve_with(
+    label: &'static str,
+    separator: Option<&'static str>,
+    label_policy: LabelPolicy,
+) -> &'static str {
+    let validation = match separator {
+        Some(separator) => validate_nested_label(label, separator),
+        None => validate_label(label),
+    };
+    let LabelValidationResult::Invalid(reason) = validation else {
+        return label;
+    };
+    match label_policy {
+        LabelPolicy::Fallback => {
+            outln!(
+                "{} falling back to 'anonymous'.",
+                wrap_high_insensity_red(reason)
+            );
+            "anonymous"
+        }
+        LabelPolicy::Sanitize => {
+            let sanitized = match separator {
+                Some(separator) => label
+                    .split(separator)
+                    .map(sanitize_label)
+                    .collect::<Vec<_>>()
+                    .join(separator),
+                None => sanitize_label(label),
+            };
+            let resolved = sanitize_once(label, sanitized);
+            outln!(
+                "{} using '{resolved}' instead.",
+                wrap_high_insensity_red(reason)
+            );
+            resolved
+        }
+        LabelPolicy::Reject => panic!("Invalid label '{label}': {reason}"),
+    }
+}
+
+/// What each sanitized label resolved to, keyed by the original
+#[cfg(feature = "bench")]
+static SANITIZED: Mutex<BTreeMap<&'static str, &'static str>> = Mutex::new(BTreeMap::new());
+
+/// Resolves the label to its sanitized form once, later calls get the same static string.
+/// Two labels that sanitize the same, like `a:b` and `a?b`, would overwrite each other's results,
+/// so the later one gets a suffix hashed from the original
+#[cfg(feature = "bench")]
+fn sanitize_once(label: &'static str, sanitized: String) -> &'static str {
+    let mut by_original = SANITIZED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(resolved) = by_original.get(label) {
+        return resolved;
+    }
+    let resolved = if by_original.values().any(|taken| **taken == sanitized) {
+        let disambiguated = format!("{sanitized}_{:08x}", fnv1a(label));
+        outln!(
+            "{} '{label}' sanitizes to '{sanitized}' like another label, using '{disambiguated}'.",
+            wrap_high_insensity_red("Sanitized label collision:")
+        );
+        intern(disambiguated)
+    } else {
+        intern(sanitized)
+    };
+    by_original.insert(label, resolved);
+    resolved
+}
+
+/// 32 bit FNV-1a, unlike the std hashers it's the same across runs and Rust versions, so a
+/// disambiguated label keeps persisting to the same place
+#[cfg(feature = "bench")]
+fn fnv1a(label: &str) -> u32 {
+    label.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+/// Whether persisted results are read or written, reads may be from another branch's results,
+/// see `GitBranch::CompareWith`
+#[cfg(feature = "bench")]
+#[derive(Copy, Clone)]
+pub(crate) enum Access {
+    Read,
+    Write,
+}
+
+/// The label and separator that results are persisted under, namespaced by git branch if
+/// configured and the branch can be told, see `GitBranch`
+#[cfg(feature = "bench")]
+pub(crate) fn persisted_label(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    access: Access,
+) -> (&'static str, Option<&'static str>) {
+    let branch = match (cfg.git_branch, access) {
+        (GitBranch::Ignore, _) => None,
+        (GitBranch::CompareWith(branch), Access::Read) => Some(branch),
+        (GitBranch::Namespace | GitBranch::CompareWith(_), _) => disk::git_branch(),
+    };
+    let Some(branch) = branch else {
+        return (label, cfg.label_separator);
+    };
+    let separator = cfg
+        .label_separator
+        .filter(|separator| !separator.is_empty())
+        .unwrap_or("/");
+    let namespaced = format!(
+        "{}{separator}{}{separator}{label}",
+        disk::BRANCHES_DIR,
+        sanitize_label(branch)
+    );
+    (intern(namespaced), Some(separator))
+}
+
+/// Labels built at runtime, each leaked once
+#[cfg(feature = "bench")]
+static INTERNED: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// A static copy of the label, labels are static everywhere. Leaks each distinct label once
+/// however often it's built, so harnesses that bench in a loop don't grow without bound
+#[cfg(feature = "bench")]
+pub(crate) fn intern(label: String) -> &'static str {
+    let mut interned = INTERNED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(existing) = interned.get(label.as_str()) {
+        return existing;
+    }
+    let leaked: &'static str = Box::leak(label.into_boxed_str());
+    interned.insert(leaked);
+    leaked
+}
+
+/// The label and separator that the run of the id is persisted under, see
+/// `BenchmarkConfig::run_id`. The id is a single component, checked like a label
+#[cfg(feature = "bench")]
+pub(crate) fn run_label(
+    label: &'static str,
+    cfg: &BenchmarkConfig,
+    run_id: &'static str,
+) -> (&'static str, Option<&'static str>) {
+    let run_id = resolve_with(run_id, None, cfg.label_policy);
+    let separator = cfg
+        .label_separator
+        .filter(|separator| !separator.is_empty())
+        .unwrap_or("/");
+    let namespaced = format!("{}{separator}{run_id}{separator}{label}", disk::RUNS_DIR);
+    (intern(namespaced), Some(separator))
+}
+
+/// Replaces everything that makes a label invalid with `_`
+#[cfg(feature = "bench")]
+fn sanitize_label(label: &str) -> String {
+    let mut sanitized = label
+        .chars()
+        .map(|ch| {
+            if ILLEGAL.contains(&ch) || u32::from(ch) < 32 {
+                '_'
+            } else {
+                ch
+            }
+        })
+        .collect::<String>();
+    if sanitized.ends_with('.') || sanitized.ends_with(' ') {
+        sanitized.pop();
+        sanitized.push('_');
+    }
+    if sanitized.is_empty() {
+        sanitized.push('_');
+    }
+    sanitized
+}
+
+#[cfg(feature = "bench")]
+fn validate_nested_label(label: &'static str, separator: &'static str) -> LabelValidationResult {
+    for component in label.split(separator) {
+        if component.is_empty() || component == "." {
+            return LabelValidationResult::Invalid("Label contains an empty path component");
+        }
+        if let LabelValidationResult::Invalid(reason) = validate_label(component) {
+            return LabelValidationResult::Invalid(reason);
+        }
+    }
+    LabelValidationResult::Valid
+}
+
 fn validate_label(label: &'static str) -> LabelValidationResult {
     for ch in ILLEGAL {
         if label.contains(ch) {
@@ -388,6 +1629,174 @@ fn validate_label(label: &'static str) -> LabelValidationResult {
 mod tests {
     use crate::output::{fmt_change, fmt_num, fmt_time, validate_label, LabelValidationResult};
 
+    #[test]
+    #[cfg(feature = "bench")]
+    fn compares_from_summary_without_bootstrap() {
+        use crate::benching::SamplingData;
+        use crate::output::analysis::sample_data::simple_analyze_sampling_data;
+        use crate::output::{compare_sampling, BenchmarkConfig};
+        let old = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![10; 4],
+            times: vec![1000, 1010, 990, 1000],
+            metrics: Vec::new(),
+        });
+        let new = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![10; 4],
+            times: vec![2000, 2010, 1990, 2000],
+            metrics: Vec::new(),
+        });
+        let cfg = BenchmarkConfig {
+            num_resamples: crate::Resamples::Fixed(0),
+            ..BenchmarkConfig::default()
+        };
+        let comparison = compare_sampling(&new, &old, &cfg);
+        assert!((comparison.mean.mean - 100.0).abs() < 0.000_001);
+        assert!(comparison.p < 0.05);
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn tests_metric_change_for_significance() {
+        let run = |metrics: Vec<u64>| super::SamplingData {
+            samples: vec![10; 6],
+            times: vec![100; 6],
+            metrics,
+        };
+        let old = run(vec![30, 31, 29, 30, 32, 28]);
+        let same = run(vec![29, 31, 30, 30, 28, 32]);
+        let higher = run(vec![40, 41, 39, 40, 42, 38]);
+        assert!(super::metric_p_value(&same, &old) > 0.5);
+        assert!(super::metric_p_value(&higher, &old) < 0.01);
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn classifies_with_asymmetric_threshold() {
+        use crate::output::{Comparison, MeanComparison, NoiseThreshold};
+        let threshold = NoiseThreshold {
+            regression_pct: 1.0,
+            improvement_pct: 5.0,
+        };
+        let classify = |change| MeanComparison::classify(change, 0.01, threshold).comparison;
+        assert!(matches!(classify(1.5), Comparison::Worse));
+        assert!(matches!(classify(0.5), Comparison::Same));
+        assert!(matches!(classify(-3.0), Comparison::Same));
+        assert!(matches!(classify(-6.0), Comparison::Better));
+        assert!(matches!(
+            MeanComparison::classify(10.0, 0.5, threshold).comparison,
+            Comparison::Same
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn resolves_label_by_policy() {
+        use crate::output::{resolve_label, BenchmarkConfig, LabelPolicy};
+        let fallback = BenchmarkConfig::default();
+        assert_eq!("fine", resolve_label("fine", &fallback));
+        assert_eq!("anonymous", resolve_label("bad:label", &fallback));
+        let sanitize = BenchmarkConfig {
+            label_policy: LabelPolicy::Sanitize,
+            ..BenchmarkConfig::default()
+        };
+        assert_eq!("bad_label_", resolve_label("bad:label.", &sanitize));
+        let nested = BenchmarkConfig {
+            label_policy: LabelPolicy::Sanitize,
+            label_separator: Some("/"),
+            ..BenchmarkConfig::default()
+        };
+        assert_eq!("a/_/c_d", resolve_label("a//c?d", &nested));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn sanitizes_colliding_labels_apart() {
+        use crate::output::{resolve_label, BenchmarkConfig, LabelPolicy};
+        let sanitize = BenchmarkConfig {
+            label_policy: LabelPolicy::Sanitize,
+            ..BenchmarkConfig::default()
+        };
+        let first = resolve_label("collide:label", &sanitize);
+        let second = resolve_label("collide?label", &sanitize);
+        assert_eq!("collide_label", first);
+        assert!(second.starts_with("collide_label_"));
+        assert!(std::ptr::eq(
+            first,
+            resolve_label("collide:label", &sanitize)
+        ));
+        assert!(std::ptr::eq(
+            second,
+            resolve_label("collide?label", &sanitize)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn namespaces_persisted_label_by_branch() {
+        use crate::output::{persisted_label, Access, BenchmarkConfig, GitBranch};
+        let shared = BenchmarkConfig::default();
+        assert_eq!(
+            ("label", None),
+            persisted_label("label", &shared, Access::Read)
+        );
+        let compare = BenchmarkConfig {
+            git_branch: GitBranch::CompareWith("release/1.0"),
+            label_separator: Some("::"),
+            ..BenchmarkConfig::default()
+        };
+        assert_eq!(
+            ("branches::release_1.0::a::b", Some("::")),
+            persisted_label("a::b", &compare, Access::Read)
+        );
+        // Interned, building the same label again doesn't leak another copy
+        assert!(std::ptr::eq(
+            persisted_label("a::b", &compare, Access::Read).0,
+            persisted_label("a::b", &compare, Access::Read).0
+        ));
+        // Depends on whether git is available, either way it must be persistable
+        let (written, separator) = persisted_label("a::b", &compare, Access::Write);
+        assert_eq!(Some("::"), separator);
+        assert!(written == "a::b" || written.starts_with("branches::"));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    #[should_panic(expected = "Invalid label")]
+    fn rejects_invalid_label() {
+        use crate::output::{resolve_label, BenchmarkConfig, LabelPolicy};
+        let reject = BenchmarkConfig {
+            label_policy: LabelPolicy::Reject,
+            ..BenchmarkConfig::default()
+        };
+        resolve_label("bad|label", &reject);
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn validates_nested_label() {
+        use crate::output::validate_nested_label;
+        assert_eq!(
+            LabelValidationResult::Valid,
+            validate_nested_label("codec/encode/large", "/")
+        );
+        assert_eq!(
+            LabelValidationResult::Valid,
+            validate_nested_label("codec::encode", "::")
+        );
+        assert!(matches!(
+            validate_nested_label("codec//large", "/"),
+            LabelValidationResult::Invalid(_)
+        ));
+        assert!(matches!(
+            validate_nested_label("codec/../large", "/"),
+            LabelValidationResult::Invalid(_)
+        ));
+        assert!(matches!(
+            validate_nested_label("codec::enc/ode", "::"),
+            LabelValidationResult::Invalid(_)
+        ));
+    }
+
     #[test]
     fn validates_label() {
         assert_eq!(LabelValidationResult::Valid, validate_label("Hello!"));
@@ -458,16 +1867,201 @@ mod tests {
         assert_eq!("68.00s", &fmt_time(68_000_000_000.0));
     }
 
+    #[test]
+    fn formats_duration_and_rate() {
+        use crate::output::{format_duration, format_rate};
+        use std::time::Duration;
+        assert_eq!("3.33ms", &format_duration(Duration::from_micros(3330)));
+        assert_eq!("68.00s", &format_duration(Duration::from_secs(68)));
+        assert_eq!("35.0 thousand/s", &format_rate(35_000.0));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn formats_number_with_format() {
+        use crate::output::{fmt_num_with, NumberFormat};
+        assert_eq!(
+            "97.0 million",
+            &fmt_num_with(97_000_000.0, NumberFormat::Words)
+        );
+        assert_eq!(
+            "97,000,000",
+            &fmt_num_with(97_000_000.0, NumberFormat::Grouped)
+        );
+        assert_eq!("5", &fmt_num_with(5.1, NumberFormat::Grouped));
+        assert_eq!("35,001", &fmt_num_with(35_001.0, NumberFormat::Grouped));
+        assert_eq!("123,456", &fmt_num_with(123_456.0, NumberFormat::Grouped));
+        assert_eq!("97000000", &fmt_num_with(97_000_000.0, NumberFormat::Plain));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn normalizes_runs_from_other_machines() {
+        use crate::benching::{calibration_score, SamplingData};
+        use crate::output::analysis::criterion::{BenchmarkConfig, Calibration};
+        use crate::output::{disk, read_last_sampling};
+        let label = "normalized_label";
+        let data = SamplingData {
+            samples: vec![1, 1],
+            times: vec![100, 200],
+            metrics: Vec::new(),
+        };
+        disk::try_write_last_simpling(label, None, &data);
+        disk::try_write_calibration(label, None, calibration_score() * 2.0, "other-machine");
+        let cfg = BenchmarkConfig {
+            calibration: Calibration::Normalize,
+            ..BenchmarkConfig::default()
+        };
+        let (last, normalization) = read_last_sampling(label, &cfg).unwrap().unwrap();
+        assert_eq!(vec![50, 100], last.times);
+        let normalization = normalization.unwrap();
+        assert!((normalization.ratio - 0.5).abs() < 0.000_001);
+        assert_eq!("other-machine", normalization.machine);
+        let (raw, normalization) = read_last_sampling(label, &BenchmarkConfig::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(data, raw);
+        assert!(normalization.is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn formats_session_relative() {
+        use crate::output::fmt_session_relative;
+        assert_eq!("0.72× (28% faster)", fmt_session_relative(0.72));
+        assert_eq!("1.30× (30% slower)", fmt_session_relative(1.3));
+        assert_eq!("1.00× (0% slower)", fmt_session_relative(1.0));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn formats_summary() {
+        use crate::benching::{BenchResult, BenchStats, RunComparison};
+        use crate::output::fmt_summary;
+        use std::time::Duration;
+        let result = |label, mean, comparison| BenchResult {
+            label,
+            stats: BenchStats {
+                total_iters: 1,
+                elapsed: 1,
+                mean,
+                median: mean,
+                min: mean,
+                max: mean,
+                variance: 0.0,
+                stddev: 0.0,
+                per_sample_average: vec![mean],
+                warmup_iterations: 1,
+                warmup_elapsed: Duration::ZERO,
+                estimated_mean_ns: mean,
+                mean_confidence_interval: None,
+                throughput_per_sec: 1.0,
+                metric_per_iteration: None,
+                cold_start: None,
+            },
+            comparison,
+        };
+        let comparison = RunComparison {
+            min_change: 0.0,
+            mean_change: -5.0,
+            max_change: 0.0,
+            p: 0.01,
+            t: -3.0,
+            t_quantiles: None,
+            regressed: false,
+            improved: true,
+            metric_change: None,
+            metric_p: None,
+        };
+        assert_eq!(
+            "decode     1.20µs  -5.00% (p = 0.01)\nnew       20.00ns\n",
+            fmt_summary(&[
+                result("decode", 1200.0, Some(comparison)),
+                result("new", 20.0, None)
+            ])
+        );
+        assert_eq!(
+            "a    20.00ns\na    30.00ns\nb    10.00ns\n",
+            fmt_summary(&[
+                result("b", 10.0, None),
+                result("a", 30.0, None),
+                result("a", 20.0, None)
+            ])
+        );
+    }
+
     #[test]
     fn formats_number() {
         assert_eq!("5.1", &fmt_num(5.1));
         assert_eq!("35.0 thousand", &fmt_num(35_000.0));
-        assert_eq!("97.0M", &fmt_num(97_000_000.0));
-        assert_eq!("7.9B", &fmt_num(7_900_000_000.0));
+        assert_eq!("97.0 million", &fmt_num(97_000_000.0));
+        assert_eq!("7.9 billion", &fmt_num(7_900_000_000.0));
+        assert_eq!("5.0 trillion", &fmt_num(5_000_000_000_000.0));
+        assert_eq!("999.9 trillion", &fmt_num(999_900_000_000_000.0));
+        assert_eq!("1.2e15", &fmt_num(1_200_000_000_000_000.0));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn formats_raw_line() {
+        assert_eq!(
+            "RAW label=\"my bench\" total_ns=5000 iters=100 mean_ns=50.000 samples=10",
+            &super::fmt_raw_line("my bench", 5000, 100, 50.0, 10)
+        );
     }
 
     #[test]
     fn formats_change() {
-        assert_eq!("5.1973%", &fmt_change(5.1973));
+        assert_eq!("5.20%", &fmt_change(5.1973, 2));
+        assert_eq!("5.1973%", &fmt_change(5.1973, 4));
+        assert_eq!("-5%", &fmt_change(-5.1973, 0));
+        assert_eq!("0.00%", &fmt_change(-0.001, 2));
+        assert_eq!("n/a", &fmt_change(f64::NAN, 2));
+        assert_eq!("n/a", &fmt_change(f64::INFINITY, 2));
+        assert_eq!("n/a", &fmt_time(f64::NAN));
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn compares_zero_time_samples() {
+        use crate::benching::SamplingData;
+        use crate::output::analysis::sample_data::simple_analyze_sampling_data;
+        use crate::output::{compare_sampling, fmt_signed_change, BenchmarkConfig, Comparison};
+        assert_eq!("+5.20%", &fmt_signed_change(5.1973));
+        assert_eq!("n/a", &fmt_signed_change(f64::NAN));
+        let old = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![10; 4],
+            times: vec![0; 4],
+            metrics: Vec::new(),
+        });
+        assert!(old.average.abs() < f64::EPSILON);
+        assert!(old.variance.is_finite() && old.min.is_finite() && old.max.is_finite());
+        let new = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![10, 10, 0, 10],
+            times: vec![0, 10, 0, 20],
+            metrics: Vec::new(),
+        });
+        assert!(new
+            .per_sample_average
+            .iter()
+            .all(|average| average.is_finite()));
+        for num_resamples in [crate::Resamples::Fixed(0), crate::Resamples::Fixed(100)] {
+            let cfg = BenchmarkConfig {
+                num_resamples,
+                seed: Some(1234),
+                ..BenchmarkConfig::default()
+            };
+            for (a, b) in [(&new, &old), (&old, &old)] {
+                let comparison = compare_sampling(a, b, &cfg);
+                assert_eq!("n/a", fmt_change(comparison.mean.mean, 2));
+                assert_eq!("n/a", fmt_change(comparison.min_change, 2));
+                assert!(comparison.p.is_finite());
+                assert_eq!(
+                    num_resamples == crate::Resamples::Fixed(0),
+                    comparison.t_quantiles.is_none()
+                );
+                assert!(matches!(comparison.mean.comparison, Comparison::Same));
+            }
+        }
     }
 }
@@ -3,4 +3,8 @@ pub(crate) mod criterion;
 #[cfg(feature = "bench")]
 pub(crate) mod random;
 #[cfg(feature = "bench")]
+pub(crate) mod regression;
+#[cfg(feature = "bench")]
 pub(crate) mod sample_data;
+#[cfg(any(feature = "bench", feature = "timer"))]
+pub(crate) mod welch;
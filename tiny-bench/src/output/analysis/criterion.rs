@@ -1,7 +1,14 @@
 //! Everything in this module is more or less copied from [criterion.rs](https://github.com/bheisler/criterion.rs)
 //! with some rewrites to make it fit, the license is included in this file's directory
+use crate::benching::{SampleResult, TQuantiles};
 use crate::output::analysis::random::Rng;
+use crate::output::analysis::welch::normal_quantile;
+use crate::output::codec::{BinaryCodec, ResultCodec};
+use crate::output::stream::outln;
 use crate::output::wrap_yellow;
+use std::io::Write;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 /// Struct containing all of the configuration options for a benchmark.
@@ -10,51 +17,696 @@ pub struct BenchmarkConfig {
     /// longer to be able to collect `num_samples` if the code to be benched is slower
     /// than this time limit allowed.
     pub measurement_time: Duration,
-    /// How many resamples should be done
-    pub num_resamples: usize,
+    /// How many resamples should be done, see `Resamples`.
+    /// The p-value of a comparison is a multiple of `2 / resamples`, so with 10 000 resamples the
+    /// smallest p-value above 0 that can be reported is 0.0002, anything smaller reads as 0.
+    /// Set to `Resamples::Fixed(0)` to skip the bootstrap and compare with a classical t-test on
+    /// the summary statistics, which is much cheaper for high sample counts
+    pub num_resamples: Resamples,
     /// Recommended at least 50, above 100 <https://en.wikipedia.org/wiki/Bootstrapping_(statistics)#Recommendations>
     /// doesn't seem to yield a significantly different result
     pub num_samples: usize,
     /// How long the bench should warm up
     pub warm_up_time: Duration,
+    /// Whether to warn when the warm up suggests that the benched code was optimized away
+    pub elision_check: ElisionCheck,
+    /// What to do with samples that took no measurable time, see `ZeroTimeSamples`
+    pub zero_time_samples: ZeroTimeSamples,
+    /// Whether the reported min and max are those of the sample averages or of single
+    /// iterations, see `Extremes`
+    pub extremes: Extremes,
+    /// Whether to time the very first call on its own before warming up, see `ColdStart`
+    pub cold_start: ColdStart,
+    /// Checks that the warm up reached a steady state before its mean is used to plan the
+    /// iterations, a mean that blends cold and warm iterations misplans them
+    pub warm_up_check: Option<WarmUpCheck>,
+    /// Pauses between the warm up and the measurement, for benchmarks sensitive to CPU
+    /// frequency. Right after warming up the clock may still be ramping up or throttling down,
+    /// settling lets it stabilize before the first sample. Not measured, but counts towards
+    /// `hard_time_limit`
+    pub settle_time: Option<Duration>,
+    /// How to pause for `settle_time`
+    pub settle_by: Settle,
+    /// Shortens the warm up when the last persisted run is younger than this, its mean is used to
+    /// plan the iterations after a tenth of `warm_up_time` confirms it's still about right.
+    /// Falls back to a full warm up if there's no recent run or the means differ by more than 25%
+    pub reuse_recent_baseline: Option<Duration>,
     /// Puts results in target/tiny-bench/label/.. if target can be found.
     /// used for comparing previous runs
     pub dump_results_to_disk: bool,
+    /// When results are written to disk, see `Persist`
+    pub persist: Persist,
 
     /// Sets a hard ceiling on max iterations, overriding the heuristic calculations for iteration
     /// count. A rule of thumb; if this is used, the results are unlikely to be statistically
     /// significant.
     pub max_iterations: Option<u64>,
+
+    /// When to stop collecting samples
+    pub stop_when: StopWhen,
+
+    /// Raises the iterations of the smallest sample to at least this many, the samples grow
+    /// linearly so the first one is the shortest and noisiest, see `calculate_iterations`.
+    /// Every sample is raised along with it, so when the floor is above what fits, the run takes
+    /// longer than `measurement_time`. Ignored when `max_iterations` applies
+    pub min_iterations_per_sample: u64,
+
+    /// Stops sampling between samples once this much wall time, warm up included, has passed,
+    /// and analyzes the samples collected so far with a warning. Unlike `measurement_time` this
+    /// is a hard cap, a safety valve against a slow benchmark holding up CI.
+    /// Overrides `StopWhen::FixedSamples`.
+    pub hard_time_limit: Option<Duration>,
+
+    /// Prints a final uncolored line per benchmark for scripts to grep, the fields and their order
+    /// are stable:
+    /// `RAW label="<label>" total_ns=<u128> iters=<u128> mean_ns=<f64> samples=<u64>`.
+    /// The label is quoted and escaped like a Rust string literal, `mean_ns` is the mean
+    /// time per iteration with three decimals.
+    pub emit_raw_line: bool,
+
+    /// Shuffles the order of the inputs before each sample when benching over inputs, see
+    /// `bench_over_inputs_configuration_labeled`
+    pub shuffle_inputs: bool,
+
+    /// Seed for the random number generator, a fixed seed makes shuffles and resamples reproducible.
+    /// Seeded from the global seed if one is set with `set_global_seed`, otherwise from the
+    /// system clock, if `None`
+    pub seed: Option<u64>,
+
+    /// Called with each sample's raw data right after it has been measured, outside of the
+    /// timed region. Useful for streaming results to an external collector as they come in.
+    /// Use a channel or a `Mutex` to get data out of the callback, it's shared through the
+    /// configuration so it can't be `FnMut`. Returning `ControlFlow::Break` stops sampling and
+    /// analyzes the samples collected so far, like `hard_time_limit`, at least two are always
+    /// collected.
+    pub on_sample: Option<Box<dyn Fn(SampleResult) -> ControlFlow<()> + Send + Sync>>,
+
+    /// Streams each sample's raw data to a machine readable channel right after it has been
+    /// measured, outside of the timed region, while the summary is printed to stdout as usual
+    pub stream_samples: SampleStream,
+
+    /// Called between samples, outside of the timed region, to reset state that could carry over
+    /// from one sample to the next. Like trimming the allocator, purging a cache, or forcing a
+    /// collection. Must not be used to do work that should be measured, it never will be.
+    /// Use a `Mutex` for state that the callback needs to mutate.
+    pub between_samples: Option<Box<dyn Fn() + Send + Sync>>,
+
+    /// Gives the scheduler a chance to run other work between samples, so that preemption is
+    /// more likely to happen there than in the middle of a sample. The time spent is reported
+    /// but never measured.
+    pub pause_between_samples: SamplePause,
+
+    /// Drops the first samples from analysis and persistence, even after warm up the first
+    /// samples may be outliers because of residual cold state. The dropped samples still count
+    /// towards `measurement_time` and `num_samples`.
+    pub discard_first_samples: usize,
+
+    /// How results are printed
+    pub output_format: OutputFormat,
+
+    /// Where progress, results and warnings are printed, stdout by default. Sessions printed by
+    /// `print_summary` go to stdout regardless, they aren't tied to one configuration
+    pub output_stream: OutputStream,
+
+    /// How counts, like the number of iterations, are printed
+    pub number_format: NumberFormat,
+
+    /// Decimals of printed percentage changes, more than the measurement supports only add noise
+    pub change_decimals: usize,
+
+    /// Whether runs persisted on another machine are normalized by the machines' calibration
+    /// scores before comparing, see `Calibration::Normalize`
+    pub calibration: Calibration,
+
+    /// How big a significant change of the mean has to be to count as a regression or an
+    /// improvement when comparing with the last run
+    pub noise_threshold: NoiseThreshold,
+
+    /// How the p-values of a group's pairwise comparisons are corrected for testing many pairs,
+    /// see [`crate::bench_group`]
+    pub family_correction: FamilyCorrection,
+
+    /// Checks the load of the machine before benching, to catch benching while something else,
+    /// like a build, is running. Only supported on Linux, elsewhere there's no check.
+    pub load_check: Option<LoadCheck>,
+    /// What to do when benching a build with debug assertions, which is almost always
+    /// unoptimized and orders of magnitude off from release performance
+    pub debug_build: DebugBuild,
+
+    /// Reports after how many samples the running mean and median settled within this many
+    /// percent of their final values, and warns if they didn't, which means that more samples
+    /// are needed
+    pub convergence_tolerance: Option<f64>,
+
+    /// Plots the mean of each sample over the course of the run with braille characters, at most
+    /// this many characters wide, to show at a glance whether samples drift or are stable.
+    /// Only printed by the pretty output when stdout is a terminal
+    pub plot_width: Option<usize>,
+
+    /// How the central tendency of each run is computed, it's printed alongside the mean and
+    /// used when comparing with the last run
+    pub aggregation: Aggregation,
+
+    /// Whether severe outliers are left out of the analysis, see `OutlierFilter`. Outliers are
+    /// counted and reported either way
+    pub outlier_filter: OutlierFilter,
+
+    /// Pins the benchmarking thread to this core while benching, which keeps the scheduler from
+    /// migrating it between cores and reduces variance on multicore machines.
+    /// Only supported on Linux where it requires `taskset`, elsewhere a warning is printed and the
+    /// benchmark runs unpinned.
+    pub pin_to_core: Option<usize>,
+
+    /// Benchmarks configured with the same resource name never run at the same time, a later
+    /// one waits for the earlier to finish. For benchmarks that share something outside the
+    /// process, like a temporary file or a port, and would otherwise disturb each other's
+    /// measurements when run from several threads, as the test harness does.
+    /// Only coordinates within one process, separate processes aren't serialized
+    pub exclusive_resource: Option<&'static str>,
+    /// Treats this separator in labels as directory nesting when persisting results, so that
+    /// `"codec/encode/large"` with `Some("/")` is stored under `simple-bench/codec/encode/large`.
+    /// Each component is validated like a label by itself.
+    /// When `None`, labels containing path separators are rejected.
+    pub label_separator: Option<&'static str>,
+    /// What to do with labels that can't be used as a directory name
+    pub label_policy: LabelPolicy,
+    /// Whether persisted results are kept apart per git branch, see `GitBranch`
+    pub git_branch: GitBranch,
+    /// Also persists each run under this id, so that it can be read back with
+    /// [`crate::load_run`] and compared with [`crate::compare_runs`] long after later runs have
+    /// replaced it as the current and old run. Checked like a label, with the `label_policy`.
+    /// Every label benched with the same id is stored under it, like the arms of an experiment
+    pub run_id: Option<&'static str>,
+    /// How sampled results are encoded on disk, [`BinaryCodec`] by default. Switching codecs
+    /// makes results written with the other one unreadable until they've been replaced
+    pub codec: &'static dyn ResultCodec,
+}
+
+/// When to stop collecting samples
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StopWhen {
+    /// Samples are sized to fit in `measurement_time`, or capped by `max_iterations`
+    MeasurementTime,
+    /// Always collects `num_samples` samples, however long that takes. `measurement_time` is only
+    /// used to size the samples and `max_iterations` is ignored. When the samples won't fit in
+    /// `measurement_time` the expected duration, estimated from the warm up, is printed up front.
+    FixedSamples,
+}
+
+/// Whether results are persisted per git branch, so that branches don't overwrite each other's
+/// results. The branch is read with `git rev-parse --abbrev-ref HEAD`, if git isn't available or
+/// the head is detached results are persisted under the plain label as if `Ignore`d.
+/// Namespaced results are stored under `simple-bench/branches/<branch>/<label>`, with
+/// characters that can't be in a directory name replaced by `_`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GitBranch {
+    /// Results are shared between branches
+    Ignore,
+    /// Results are persisted and compared per branch
+    Namespace,
+    /// Results are persisted per branch and compared with those of the given branch, for
+    /// feature branches to compare with the baseline of `main`
+    CompareWith(&'static str),
+}
+
+/// How results persisted on other machines are compared
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Calibration {
+    /// Results are compared as they are, only meaningful on the same machine
+    None,
+    /// Scores this machine with a fixed reference workload, see `calibration_score`, and persists
+    /// the score with the results. A run persisted on another machine is scaled by the ratio of
+    /// the scores before comparing, and the comparison is labeled as normalized.
+    /// This is approximate, the reference workload can't stand in for every kind of code, only
+    /// use it to compare across machines when the same machine isn't available.
+    /// Machines are told apart by their hostname. Only the pretty and TAP outputs compare
+    /// normalized, every output persists the score.
+    Normalize,
+}
+
+/// Where a benchmark prints, see `BenchmarkConfig::output_stream`
+#[derive(Clone)]
+pub enum OutputStream {
+    /// The process' stdout
+    Stdout,
+    /// The process' stderr, to keep stdout clean for a program that benches itself and writes
+    /// its own data there
+    Stderr,
+    /// Any writer, like a file or a buffer. Failing to write to it is ignored. Colors are
+    /// written as the same escape codes as to a terminal
+    Writer(Arc<Mutex<dyn Write + Send>>),
+}
+
+impl std::fmt::Debug for OutputStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputStream::Stdout => f.write_str("Stdout"),
+            OutputStream::Stderr => f.write_str("Stderr"),
+            OutputStream::Writer(_) => f.write_str("Writer(..)"),
+        }
+    }
+}
+
+/// Where raw samples are streamed while benching
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SampleStream {
+    /// Samples aren't streamed
+    None,
+    /// One line per sample on stderr, `<index> <iterations> <elapsed_nanos>` separated by single
+    /// spaces. The index restarts from 0 with each benchmark.
+    /// Tee stderr into a processor while reading the summary on stdout:
+    /// `cargo bench 2> >(tee samples.txt >&2)`
+    Stderr,
+}
+
+impl SampleStream {
+    /// Writes the sample to the stream, if any
+    pub(crate) fn emit(self, sample: &SampleResult) {
+        match self {
+            SampleStream::None => {}
+            SampleStream::Stderr => eprintln!("{}", fmt_sample_line(sample)),
+        }
+    }
+}
+
+/// Keep in sync with the format documented on `SampleStream::Stderr`
+pub(crate) fn fmt_sample_line(sample: &SampleResult) -> String {
+    format!(
+        "{} {} {}",
+        sample.index, sample.iterations, sample.elapsed_nanos
+    )
+}
+
+/// How the benchmarking thread pauses for `settle_time`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Settle {
+    /// Sleeps, letting the core idle and cool down, for code that's measured from a rested state
+    Sleep,
+    /// Keeps the core busy with a spin loop, holding its clock up
+    Spin,
+}
+
+impl Settle {
+    pub(crate) fn settle(self, duration: Duration) {
+        match self {
+            Settle::Sleep => std::thread::sleep(duration),
+            Settle::Spin => {
+                let start = std::time::Instant::now();
+                while start.elapsed() < duration {
+                    std::hint::spin_loop();
+                }
+            }
+        }
+    }
+}
+
+/// How the benchmarking thread pauses between samples
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SamplePause {
+    /// Goes straight on to the next sample
+    None,
+    /// Yields to other threads, see [`std::thread::yield_now`]
+    Yield,
+    /// Sleeps for the duration
+    Sleep(Duration),
+}
+
+impl SamplePause {
+    /// Pauses and returns how long it took
+    pub(crate) fn pause(self) -> Duration {
+        let start = std::time::Instant::now();
+        match self {
+            SamplePause::None => return Duration::ZERO,
+            SamplePause::Yield => std::thread::yield_now(),
+            SamplePause::Sleep(duration) => std::thread::sleep(duration),
+        }
+        start.elapsed()
+    }
+}
+
+/// How benchmark results are printed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable colored output
+    Pretty,
+    /// [TAP](https://testanything.org/) for CI test runners, a benchmark fails if it's
+    /// significantly worse than the last persisted run. Call `print_tap_plan` after all
+    /// benchmarks have run.
+    #[cfg(feature = "tap")]
+    Tap,
+    /// Line-delimited JSON in cargo-criterion's message format, for tools that already parse it.
+    /// Progress output is left out so that the messages are the only lines printed, unless
+    /// something goes wrong.
+    #[cfg(feature = "criterion-json")]
+    CriterionJson,
+    /// Nothing is printed while benching, call `print_diff_text` after all benchmarks have run to
+    /// print an uncolored comparison with the last persisted runs, one aligned line per benchmark
+    /// sorted by label. For pasting into code review, where before and after reports diff cleanly.
+    DiffText,
+    /// A JSON object per benchmark on its own line, with every statistic and the raw samples,
+    /// for dashboards and scripts. Progress output is left out so that the objects are the only
+    /// lines printed, unless something goes wrong. Only benchmarks print JSON, timed iterators
+    /// have no configuration to select it with.
+    Json,
+}
+
+/// How many resamples the bootstrap does, see `BenchmarkConfig::num_resamples`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Resamples {
+    /// `max(10 000, 1000 * samples)`, fewer for small runs where more resamples add nothing and
+    /// enough for large ones to stay precise. 100 000 for the default 100 samples
+    Auto,
+    /// Exactly this many, 0 skips the bootstrap
+    Fixed(usize),
+}
+
+impl Resamples {
+    /// The number of resamples for a run of `samples` samples
+    pub(crate) fn count(self, samples: usize) -> usize {
+        match self {
+            Resamples::Auto => samples.saturating_mul(1000).max(10_000),
+            Resamples::Fixed(resamples) => resamples,
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Whether progress, like warming up, should be printed alongside the results
+    pub(crate) fn prints_progress(self) -> bool {
+        match self {
+            OutputFormat::Pretty => true,
+            #[cfg(feature = "tap")]
+            OutputFormat::Tap => true,
+            #[cfg(feature = "criterion-json")]
+            OutputFormat::CriterionJson => false,
+            OutputFormat::DiffText | OutputFormat::Json => false,
+        }
+    }
+}
+
+/// What to do with a label that contains characters that can't be used in a directory name,
+/// like `:` or `?`, or that ends with a dot or space
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LabelPolicy {
+    /// Bench under the shared `anonymous` label, distinct benchmarks with invalid labels will
+    /// overwrite each other's results
+    Fallback,
+    /// Replace the offending characters with `_`
+    Sanitize,
+    /// Panic instead of running the benchmark, every entry point that resolves the label or the
+    /// `run_id` documents this under `# Panics`
+    Reject,
+}
+
+/// Smallest changes of the mean, in percent, that count as a regression or an improvement.
+/// Regressions are often worth flagging at a lower threshold than improvements are worth
+/// celebrating at.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NoiseThreshold {
+    /// A slowdown of at least this many percent is a regression
+    pub regression_pct: f64,
+    /// A speedup of at least this many percent is an improvement
+    pub improvement_pct: f64,
+}
+
+impl NoiseThreshold {
+    /// The same threshold for regressions and improvements
+    #[must_use]
+    pub const fn symmetric(pct: f64) -> Self {
+        NoiseThreshold {
+            regression_pct: pct,
+            improvement_pct: pct,
+        }
+    }
+}
+
+/// A limit on how busy the machine may be when a benchmark starts, see
+/// `BenchmarkConfig::load_check`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LoadCheck {
+    /// The highest acceptable one minute load average divided by the number of cores,
+    /// 1.0 means that every core is busy
+    pub max_load_per_core: f64,
+    /// What to do if the load is higher
+    pub action: LoadAction,
+}
+
+/// Whether to check for the benched code being optimized away, see
+/// `BenchmarkConfig::elision_check`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ElisionCheck {
+    /// No check
+    Off,
+    /// Warns if the time per iteration kept halving while the warm up doubled its batches, which
+    /// real work can't do but a loop the optimizer removed does. Checked during warm up, before
+    /// any time is spent measuring
+    Warn,
+}
+
+/// Correction of the p-values when many comparisons are tested at once, see
+/// `BenchmarkConfig::family_correction`.
+/// Each test has a 5% chance of a false positive, with ten pairs that's a 40% chance that at
+/// least one of them is, so some winner gets declared even among identical implementations.
+/// Both keep the chance of any false positive in the family at 5%
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FamilyCorrection {
+    /// Multiplies every p-value by the number of comparisons, simple but conservative
+    Bonferroni,
+    /// [Holm's step-down method](https://en.wikipedia.org/wiki/Holm%E2%80%93Bonferroni_method),
+    /// multiplies the smallest p-value by the number of comparisons, the next by one less and so
+    /// on, finds at least as many real differences as `Bonferroni`
+    Holm,
+}
+
+/// When results are written to disk, see `BenchmarkConfig::persist`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Persist {
+    /// Each run is written, and the run before it rotated to the baseline, as soon as it's done
+    EachRun,
+    /// Runs are held in memory until [`crate::commit_session`] writes them all at once. Until
+    /// then every run compares with the baseline from before the session, and benching a label
+    /// twice doesn't compare the second run with the first. Only the last run of each label is
+    /// written, runs that are never committed are lost
+    OnCommit,
+}
+
+/// What to do with samples that took no measurable time, see
+/// `BenchmarkConfig::zero_time_samples`. A clock coarser than the sample, or work that was
+/// optimized away, reads as zero elapsed, which drags the mean down and leaves changes relative
+/// to it undefined, those print as n/a. Either way there's a warning
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ZeroTimeSamples {
+    /// Analyzes them like any other sample
+    Keep,
+    /// Leaves them out of the analysis, unless fewer than two samples would be left
+    Discard,
+}
+
+/// What to do with sample averages outside Tukey's fences, see `BenchmarkConfig::outlier_filter`.
+/// Mild outliers are more than 1.5 interquartile ranges outside the quartiles, severe ones more
+/// than 3. A single context switch or page fault during a sample makes it one
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OutlierFilter {
+    /// Analyzes them like any other sample
+    Keep,
+    /// Leaves severe outliers out of the mean, variance, and standard deviation, and out of the
+    /// comparison with the last run, which is then computed from the remaining samples of both
+    /// runs. The extremes, median, and percentiles are still those of all samples
+    DropSevere,
+}
+
+/// What the reported min and max are of, see `BenchmarkConfig::extremes`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Extremes {
+    /// The fastest and slowest mean time per iteration of a sample. Averaging over a sample
+    /// smooths out single slow iterations, so these are much closer to the mean than the
+    /// fastest and slowest iteration
+    SampleAverage,
+    /// Also reports the fastest and slowest single iteration, by timing every iteration of the
+    /// measurement individually. That costs two clock reads, tens of nanoseconds, per iteration
+    /// which inflates the mean of code that takes about as long
+    Iteration,
+}
+
+/// Whether the first call is measured by itself, see `BenchmarkConfig::cold_start`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ColdStart {
+    /// The first call is part of the warm up like any other
+    Ignore,
+    /// Times the first call alone, before warming up, and reports it next to the steady state
+    /// mean. One-time initialization like lazy statics and first touches of memory make it
+    /// slower, which is what a short lived process like a CLI tool pays on every run.
+    /// Only the very first call in the process is cold, earlier benchmarks of the same code or
+    /// tests that ran it warm it up too
+    Measure,
+}
+
+/// How stable the end of the warm up has to be, see `BenchmarkConfig::warm_up_check`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WarmUpCheck {
+    /// How far, in percent, the mean of the last batch of the warm up may be from the mean of
+    /// the whole warm up. The batches double in size, so the last one is about half of the warm up
+    pub tolerance: f64,
+    /// What to do if it's further
+    pub action: WarmUpAction,
+}
+
+/// What to do when the warm up didn't stabilize
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WarmUpAction {
+    /// Print a warning and bench anyway
+    Warn,
+    /// Warm up again for `warm_up_time`, at most this many times, and warn if it still didn't
+    /// stabilize. Only the last warm up plans the iterations
+    Extend(u32),
+}
+
+/// What to do when benching a debug build, see `BenchmarkConfig::debug_build`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DebugBuild {
+    /// Bench without a word, for benching the debug build on purpose
+    Ignore,
+    /// Print a warning once, before the first benchmark
+    Warn,
+    /// Panic instead of benching
+    Refuse,
+}
+
+/// What to do when the machine is too busy to bench
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LoadAction {
+    /// Print a warning and bench anyway
+    Warn,
+    /// Panic instead of benching
+    Refuse,
+}
+
+/// How a run's per sample averages are aggregated into a single number
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Aggregation {
+    /// The mean of all samples
+    Mean,
+    /// The mean after dropping this fraction of the samples from each end, `TrimmedMean(0.1)`
+    /// drops the fastest and slowest 10%. Reduces the influence of outliers.
+    /// The fraction is clamped below 0.5
+    TrimmedMean(f64),
+    /// The median of all samples. The significance test still uses all samples
+    Median,
+    /// The fastest sample. Noise can only add time, so for deterministic CPU bound code the
+    /// minimum is the estimate least affected by it. The mean is the better choice when the
+    /// variation is part of the cost, like with allocation, I/O, or contended locks, which the
+    /// minimum hides. The significance test still uses all samples
+    Minimum,
+}
+
+/// How counts are printed
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NumberFormat {
+    /// Rounded to the nearest magnitude in words, like `97.0 million`
+    Words,
+    /// Whole numbers with comma separated thousands, like `97,000,000`
+    Grouped,
+    /// Whole numbers without separators, like `97000000`
+    Plain,
 }
 
 impl Default for BenchmarkConfig {
     fn default() -> Self {
         BenchmarkConfig {
             measurement_time: Duration::from_secs(5),
-            num_resamples: 100_000,
+            num_resamples: Resamples::Auto,
             num_samples: 100,
             warm_up_time: Duration::from_secs(3),
+            elision_check: ElisionCheck::Warn,
+            zero_time_samples: ZeroTimeSamples::Keep,
+            extremes: Extremes::SampleAverage,
+            cold_start: ColdStart::Ignore,
+            warm_up_check: None,
+            settle_time: None,
+            settle_by: Settle::Sleep,
+            reuse_recent_baseline: None,
             dump_results_to_disk: true,
+            persist: Persist::EachRun,
             max_iterations: None,
+            stop_when: StopWhen::MeasurementTime,
+            min_iterations_per_sample: 1,
+            hard_time_limit: None,
+            emit_raw_line: false,
+            shuffle_inputs: false,
+            seed: None,
+            on_sample: None,
+            stream_samples: SampleStream::None,
+            between_samples: None,
+            pause_between_samples: SamplePause::None,
+            discard_first_samples: 0,
+            output_format: OutputFormat::Pretty,
+            output_stream: OutputStream::Stdout,
+            number_format: NumberFormat::Words,
+            change_decimals: 2,
+            calibration: Calibration::None,
+            noise_threshold: NoiseThreshold::symmetric(crate::output::NOISE_THRESHOLD),
+            family_correction: FamilyCorrection::Holm,
+            load_check: None,
+            debug_build: DebugBuild::Warn,
+            convergence_tolerance: None,
+            plot_width: None,
+            aggregation: Aggregation::Mean,
+            outlier_filter: OutlierFilter::Keep,
+            pin_to_core: None,
+            exclusive_resource: None,
+            label_separator: None,
+            label_policy: LabelPolicy::Fallback,
+            git_branch: GitBranch::Ignore,
+            run_id: None,
+            codec: &BinaryCodec,
         }
     }
 }
 
-pub(crate) fn calculate_iterations(
+/// Plans how many iterations each sample runs, the same way the benchmarks do, for custom
+/// harnesses that want to stay consistent with them, pre-allocate, or estimate how long a run takes.
+///
+/// Sample `i`, counting from 1, runs `i * d` iterations, a linear ramp like criterion's.
+/// The samples then differ in length, which lets a slope be fitted through their times, and the
+/// total is `d` times the triangular number `n * (n + 1) / 2`. `d` is picked so that the total
+/// takes `target_time` at `warmup_mean_execution_time` nanoseconds per iteration, but is at
+/// least 1. If even `d = 1` doesn't fit, a warning is printed and, unless `stop_when` is
+/// [`StopWhen::FixedSamples`], a suggestion to raise the target time.
+/// ```
+/// use std::time::Duration;
+/// use tiny_bench::{calculate_iterations, StopWhen};
+/// let iterations = calculate_iterations(10.0, 4, Duration::from_micros(1), StopWhen::MeasurementTime);
+/// assert_eq!(vec![10, 20, 30, 40], iterations);
+/// ```
+#[must_use]
+pub fn calculate_iterations(
     warmup_mean_execution_time: f64,
     num_samples: u64,
     target_time: Duration,
+    stop_when: StopWhen,
 ) -> Vec<u64> {
     let met = warmup_mean_execution_time;
     let m_ns = target_time.as_nanos();
     // Solve: [d + 2*d + 3*d + ... + n*d] * met = m_ns
 
-    let total_runs = num_samples * (num_samples + 1) / 2;
+    let total_runs = num_samples.saturating_mul(num_samples.saturating_add(1)) / 2;
     let d = ((m_ns as f64 / met / total_runs as f64).ceil() as u64).max(1);
     let expected_nanoseconds = total_runs as f64 * d as f64 * met;
     if d == 1 {
         let actual_time = Duration::from_nanos(expected_nanoseconds as u64);
-        println!(
+        if stop_when == StopWhen::FixedSamples {
+            if actual_time > target_time {
+                outln!(
+                    "{} Collecting all samples is expected to take {:.1?}",
+                    wrap_yellow(&format!(
+                        "Unable to complete {num_samples} samples in {target_time:.1?}"
+                    )),
+                    actual_time
+                );
+            }
+            return (1..=num_samples).collect();
+        }
+        outln!(
             "{} You may wish to increase target time to {:.1?} or lower the requested number of samples",
             wrap_yellow(&format!(
                 "Unable to complete {num_samples} samples in {target_time:.1?}"
@@ -63,7 +715,7 @@ pub(crate) fn calculate_iterations(
         );
     }
 
-    (1..=num_samples).map(|a| a * d).collect()
+    (1..=num_samples).map(|a| a.saturating_mul(d)).collect()
 }
 
 pub(crate) fn calculate_t_value(sample_a: &[f64], sample_b: &[f64]) -> f64 {
@@ -78,6 +730,20 @@ pub(crate) fn calculate_t_value(sample_a: &[f64], sample_b: &[f64]) -> f64 {
     mean_diff / d
 }
 
+/// [Paired t-test](https://en.wikipedia.org/wiki/Student%27s_t-test#Dependent_t-test_for_paired_samples),
+/// each value in `sample_a` is paired with the value at the same index in `sample_b`.
+/// More powerful than the unpaired test when both samples were measured on the same inputs
+pub(crate) fn calculate_paired_t_value(sample_a: &[f64], sample_b: &[f64]) -> f64 {
+    let differences = sample_a
+        .iter()
+        .zip(sample_b)
+        .map(|(a, b)| a - b)
+        .collect::<Vec<_>>();
+    let mean = calculate_mean(&differences);
+    let variance = calculate_variance(&differences, mean);
+    mean / (variance / differences.len() as f64).sqrt()
+}
+
 pub(crate) fn calculate_mean(a: &[f64]) -> f64 {
     a.iter().sum::<f64>() / a.len() as f64
 }
@@ -91,12 +757,16 @@ pub(crate) fn calculate_variance(sample: &[f64], mean: f64) -> f64 {
     sum / (sample.len() as f64 - 1f64) // use n - 1 when measuring variance from a sample
 }
 
-pub(crate) fn resample(sample_a: &[f64], sample_b: &[f64], times: usize) -> Vec<f64> {
+pub(crate) fn resample(
+    rng: &mut Rng,
+    sample_a: &[f64],
+    sample_b: &[f64],
+    times: usize,
+) -> Vec<f64> {
     let a_len = sample_a.len();
     let mut combined = Vec::with_capacity(a_len + sample_b.len());
     combined.extend_from_slice(sample_a);
     combined.extend_from_slice(sample_b);
-    let mut rng = Rng::new();
     let combined_len = combined.len();
     let mut distributions = Vec::new();
     for _ in 0..times {
@@ -113,19 +783,110 @@ pub(crate) fn resample(sample_a: &[f64], sample_b: &[f64], times: usize) -> Vec<
     distributions
 }
 
+/// Percentile bootstrap confidence interval of the mean of `sample`, which unlike the t-based
+/// interval doesn't assume that the mean is normally distributed
+pub(crate) fn bootstrap_mean_interval(
+    rng: &mut Rng,
+    sample: &[f64],
+    times: usize,
+    confidence: f64,
+) -> (f64, f64) {
+    let mut means = Vec::with_capacity(times);
+    for _ in 0..times {
+        let sum = (0..sample.len())
+            .map(|_| sample[(rng.next() % sample.len() as u64) as usize])
+            .sum::<f64>();
+        means.push(sum / sample.len() as f64);
+    }
+    means.sort_by(f64::total_cmp);
+    let tail = (1.0 - confidence) / 2.0;
+    let low = (tail * times as f64).floor() as usize;
+    let high = (((1.0 - tail) * times as f64).ceil() as usize).clamp(1, times) - 1;
+    (means[low.min(high)], means[high])
+}
+
+/// The 2.5%, 50% and 97.5% quantiles of a bootstrapped t-distribution, sorts it in place
+pub(crate) fn t_quantiles(distribution: &mut [f64]) -> TQuantiles {
+    distribution.sort_by(f64::total_cmp);
+    let quantile = |q: f64| {
+        let index = (q * (distribution.len() - 1) as f64).round() as usize;
+        distribution[index]
+    };
+    TQuantiles {
+        lower: quantile(0.025),
+        median: quantile(0.5),
+        upper: quantile(0.975),
+    }
+}
+
 pub(crate) fn calculate_p_value(total_t: f64, distribution: &[f64]) -> f64 {
+    // Neither run varied nor differed, no resample falls below NaN which would read as p = 0
+    if total_t.is_nan() {
+        return 1.0;
+    }
     let hits = distribution.iter().filter(|x| x < &&total_t).count();
     let tails = 2; // I don't know what this is, Two-tailed significance testing something something
     let min = std::cmp::min(hits, distribution.len() - hits);
     (min * tails) as f64 / distribution.len() as f64
 }
 
+/// Smallest change of the mean, in percent, that comparing two runs of `samples` samples each
+/// detects with the given power at the significance level, if the variance stays the same.
+/// Uses the normal approximation of the two sample t-test
+pub(crate) fn minimum_detectable_effect(
+    mean: f64,
+    variance: f64,
+    samples: usize,
+    significance_level: f64,
+    power: f64,
+) -> f64 {
+    let z = normal_quantile(1.0 - significance_level / 2.0) + normal_quantile(power);
+    z * (2.0 * variance / samples as f64).sqrt() / mean * 100.0
+}
+
 #[inline]
 pub(crate) fn calculate_median(sample: &mut [f64]) -> f64 {
     sample.sort_by(f64::total_cmp);
     sample.get(sample.len() / 2).copied().unwrap_or_default()
 }
 
+/// The `p`th percentile of sorted values, interpolated between the two nearest values.
+/// `p` is clamped to 0-100, and no values have a percentile of 0
+pub(crate) fn calculate_percentile(sorted: &[f64], p: f64) -> f64 {
+    let Some(last) = sorted.len().checked_sub(1) else {
+        return 0.0;
+    };
+    let rank = p.clamp(0.0, 100.0) / 100.0 * last as f64;
+    let below = rank.floor() as usize;
+    let above = rank.ceil() as usize;
+    sorted[below] + (sorted[above] - sorted[below]) * (rank - below as f64)
+}
+
+/// How many samples it took for the running aggregate to settle within `tolerance_pct` percent of
+/// its final value, or `None` if it only settled for the last tenth of the samples, or the last 2,
+/// which means more samples are needed. `values` must be in measured order
+pub(crate) fn converged_after(
+    values: &[f64],
+    tolerance_pct: f64,
+    aggregate: impl Fn(&[f64]) -> f64,
+) -> Option<usize> {
+    let running = (1..=values.len())
+        .map(|len| aggregate(&values[..len]))
+        .collect::<Vec<_>>();
+    let last = *running.last()?;
+    let settled = running
+        .iter()
+        .rposition(|value| ((value / last - 1.0) * 100.0).abs() > tolerance_pct)
+        .map_or(0, |outside| outside + 1);
+    let required_tail = (values.len() / 10).max(2);
+    (settled + required_tail <= values.len()).then_some(settled + 1)
+}
+
+/// Median of unsorted values, sorting a copy is cheap enough for the number of samples
+pub(crate) fn median_of(values: &[f64]) -> f64 {
+    calculate_median(&mut values.to_vec())
+}
+
 pub(crate) struct SamplingDataSimpleAnalysis {
     pub(crate) elapsed: u128,
     pub(crate) min: f64,
@@ -134,15 +895,229 @@ pub(crate) struct SamplingDataSimpleAnalysis {
     pub(crate) median: f64,
     pub(crate) variance: f64,
     pub(crate) stddev: f64,
+    pub(crate) p25: f64,
+    pub(crate) p75: f64,
+    pub(crate) p95: f64,
+    pub(crate) p99: f64,
+    pub(crate) outliers: Outliers,
     pub(crate) per_sample_average: Vec<f64>,
 }
 
+/// How many sample averages are outside Tukey's fences, see `OutlierFilter`
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub(crate) struct Outliers {
+    pub(crate) low_severe: usize,
+    pub(crate) low_mild: usize,
+    pub(crate) high_mild: usize,
+    pub(crate) high_severe: usize,
+    /// Whether the severe outliers were left out of the analysis
+    pub(crate) filtered: bool,
+}
+
+impl Outliers {
+    pub(crate) fn total(&self) -> usize {
+        self.low_severe + self.low_mild + self.high_mild + self.high_severe
+    }
+
+    pub(crate) fn severe(&self) -> usize {
+        self.low_severe + self.high_severe
+    }
+}
+
+impl SamplingDataSimpleAnalysis {
+    /// The `p`th percentile, 0-100, of the per sample averages
+    pub(crate) fn percentile(&self, p: f64) -> f64 {
+        calculate_percentile(&self.per_sample_average, p)
+    }
+
+    /// The values beyond which sample averages are outliers, `k` interquartile ranges outside the
+    /// quartiles
+    fn fences(&self, k: f64) -> (f64, f64) {
+        let iqr = self.p75 - self.p25;
+        (self.p25 - k * iqr, self.p75 + k * iqr)
+    }
+
+    /// Counts the sample averages outside of the inner and outer fences
+    pub(crate) fn classify_outliers(&self) -> Outliers {
+        let (low_mild, high_mild) = self.fences(1.5);
+        let (low_severe, high_severe) = self.fences(3.0);
+        let mut outliers = Outliers::default();
+        for &average in &self.per_sample_average {
+            if average < low_severe {
+                outliers.low_severe += 1;
+            } else if average < low_mild {
+                outliers.low_mild += 1;
+            } else if average > high_severe {
+                outliers.high_severe += 1;
+            } else if average > high_mild {
+                outliers.high_mild += 1;
+            }
+        }
+        outliers
+    }
+
+    /// Recomputes the mean, variance, and standard deviation without the severe outliers, which
+    /// are also dropped from the per sample averages that runs are compared by.
+    /// The extremes, median, and percentiles stay those of all samples
+    pub(crate) fn without_severe_outliers(mut self) -> Self {
+        let (low, high) = self.fences(3.0);
+        self.per_sample_average
+            .retain(|average| (low..=high).contains(average));
+        self.average = calculate_mean(&self.per_sample_average);
+        self.variance = calculate_variance(&self.per_sample_average, self.average);
+        self.stddev = self.variance.sqrt();
+        self.outliers.filtered = true;
+        self
+    }
+
+    /// The sorted per sample averages that the aggregation is computed from
+    pub(crate) fn aggregated_samples(&self, aggregation: Aggregation) -> &[f64] {
+        match aggregation {
+            Aggregation::Mean | Aggregation::Median | Aggregation::Minimum => {
+                &self.per_sample_average
+            }
+            Aggregation::TrimmedMean(fraction) => {
+                let len = self.per_sample_average.len();
+                let trim = (len as f64 * fraction.clamp(0.0, 0.49)).floor() as usize;
+                &self.per_sample_average[trim..len - trim]
+            }
+        }
+    }
+
+    /// The run's central tendency according to the aggregation
+    pub(crate) fn aggregate(&self, aggregation: Aggregation) -> f64 {
+        match aggregation {
+            Aggregation::Mean => self.average,
+            Aggregation::Median => self.median,
+            Aggregation::Minimum => self.min,
+            Aggregation::TrimmedMean(_) => calculate_mean(self.aggregated_samples(aggregation)),
+        }
+    }
+
+    /// Mean, variance, and count of the samples the aggregation is computed from
+    pub(crate) fn aggregated_summary(&self, aggregation: Aggregation) -> (f64, f64, f64) {
+        match aggregation {
+            Aggregation::Mean | Aggregation::Median | Aggregation::Minimum => (
+                self.average,
+                self.variance,
+                self.per_sample_average.len() as f64,
+            ),
+            Aggregation::TrimmedMean(_) => {
+                let samples = self.aggregated_samples(aggregation);
+                let mean = calculate_mean(samples);
+                (
+                    mean,
+                    calculate_variance(samples, mean),
+                    samples.len() as f64,
+                )
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::output::analysis::criterion::{
-        calculate_mean, calculate_t_value, calculate_variance,
+        calculate_mean, calculate_paired_t_value, calculate_t_value, calculate_variance,
+        minimum_detectable_effect, t_quantiles, Resamples,
     };
 
+    #[test]
+    fn finds_t_quantiles() {
+        let mut distribution = (0..=1000).rev().map(f64::from).collect::<Vec<_>>();
+        assert_eq!(
+            crate::benching::TQuantiles {
+                lower: 25.0,
+                median: 500.0,
+                upper: 975.0,
+            },
+            t_quantiles(&mut distribution)
+        );
+    }
+
+    #[test]
+    fn scales_auto_resamples() {
+        assert_eq!(10_000, Resamples::Auto.count(5));
+        assert_eq!(100_000, Resamples::Auto.count(100));
+        assert_eq!(1_000_000, Resamples::Auto.count(1000));
+        assert_eq!(0, Resamples::Fixed(0).count(1000));
+        assert_eq!(500, Resamples::Fixed(500).count(1000));
+    }
+
+    #[test]
+    fn bootstraps_mean_interval() {
+        use crate::output::analysis::criterion::bootstrap_mean_interval;
+        use crate::output::analysis::random::Rng;
+        let sample = [10.0, 11.0, 9.0, 10.5, 9.5, 30.0, 10.0, 10.2, 9.8, 10.0];
+        let mean = calculate_mean(&sample);
+        let (low, high) = bootstrap_mean_interval(&mut Rng::with_seed(7), &sample, 10_000, 0.95);
+        assert!(low < mean && mean < high);
+        assert!(low >= 9.0 && high <= 30.0);
+        // Skewed by the outlier, the interval reaches further above the mean than below it
+        assert!(high - mean > mean - low);
+        let (same_low, same_high) =
+            bootstrap_mean_interval(&mut Rng::with_seed(7), &sample, 10_000, 0.95);
+        assert_eq!((low, high), (same_low, same_high));
+    }
+
+    #[test]
+    fn formats_sample_line() {
+        use crate::benching::SampleResult;
+        use crate::output::analysis::criterion::fmt_sample_line;
+        let sample = SampleResult {
+            index: 3,
+            iterations: 1200,
+            elapsed_nanos: 45_678,
+        };
+        assert_eq!("3 1200 45678", fmt_sample_line(&sample));
+    }
+
+    #[test]
+    fn aggregates() {
+        use crate::benching::SamplingData;
+        use crate::output::analysis::criterion::Aggregation;
+        use crate::output::analysis::sample_data::simple_analyze_sampling_data;
+        let analysis = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![1; 10],
+            times: vec![10, 1, 2, 3, 4, 5, 6, 7, 8, 100],
+            metrics: Vec::new(),
+        });
+        assert!((analysis.aggregate(Aggregation::Mean) - 14.6).abs() < 0.000_001);
+        assert!((analysis.aggregate(Aggregation::Median) - 6.0).abs() < 0.000_001);
+        assert!((analysis.aggregate(Aggregation::Minimum) - 1.0).abs() < 0.000_001);
+        assert_eq!(
+            8,
+            analysis
+                .aggregated_samples(Aggregation::TrimmedMean(0.1))
+                .len()
+        );
+        assert!((analysis.aggregate(Aggregation::TrimmedMean(0.1)) - 5.625).abs() < 0.000_001);
+        assert_eq!(
+            2,
+            analysis
+                .aggregated_samples(Aggregation::TrimmedMean(0.9))
+                .len()
+        );
+    }
+
+    #[test]
+    fn finds_convergence() {
+        use crate::output::analysis::criterion::{converged_after, median_of};
+        let mut values = vec![200.0, 50.0];
+        values.extend([100.0; 18]);
+        assert_eq!(Some(7), converged_after(&values, 5.0, calculate_mean));
+        assert_eq!(Some(3), converged_after(&values, 5.0, median_of));
+        let diverging = (1..=20).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(None, converged_after(&diverging, 1.0, calculate_mean));
+    }
+
+    #[test]
+    fn calculates_minimum_detectable_effect() {
+        // (1.96 + 0.84) * sqrt(2 * 4 / 100) / 100 * 100
+        let mde = minimum_detectable_effect(100.0, 4.0, 100, 0.05, 0.8);
+        assert!((mde - 0.792_4).abs() < 0.001);
+    }
+
     #[test]
     fn calculates_mean() {
         let data = vec![46.0, 69.0, 32.0, 60.0, 52.0, 41.0];
@@ -164,4 +1139,12 @@ mod tests {
         ];
         assert!(calculate_t_value(&sample_a, &sample_b).abs() - 2.24787 < 0.0001);
     }
+
+    #[test]
+    fn calculate_paired_t() {
+        let sample_a = vec![30.0, 32.0, 35.0, 31.0, 33.0];
+        let sample_b = vec![28.0, 30.0, 32.0, 30.0, 30.0];
+        // Differences 2, 2, 3, 1, 3 -> mean 2.2, sample stddev ~0.8367
+        assert!((calculate_paired_t_value(&sample_a, &sample_b) - 5.879_747).abs() < 0.0001);
+    }
 }
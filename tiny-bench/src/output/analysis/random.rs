@@ -1,3 +1,4 @@
+use std::sync::{Mutex, PoisonError};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// [LCG](https://en.wikipedia.org/wiki/Linear_congruential_generator)
@@ -5,7 +6,32 @@ use std::time::{SystemTime, UNIX_EPOCH};
 const MOD: u128 = 2u128.pow(48);
 const A: u128 = 25_214_903_917;
 const C: u128 = 11;
+/// How far [`Rng::split`] jumps ahead, streams don't overlap for fewer draws than this.
+/// Far more than resampling draws, 100 000 resamples of 100 + 100 samples are 20 million draws,
+/// and leaves room for 65 536 splits before the 2^48 period wraps around
+const SPLIT_DISTANCE: u64 = 1 << 32;
 
+/// Seeds every generator that isn't seeded by its configuration, see [`set_global_seed`]
+static GLOBAL: Mutex<Option<Rng>> = Mutex::new(None);
+
+/// Seeds the shuffles and resamples of every following benchmark in the process, for
+/// reproducible statistics without setting `BenchmarkConfig::seed` on every configuration.
+/// Each use draws its own seed from this one, so they don't all repeat the same stream, and the
+/// sequence is the same as long as the benchmarks run in the same order.
+/// Only affects the statistical analysis, never what's measured.
+/// A configuration's own `seed` takes precedence.
+/// ```no_run
+/// use tiny_bench::{bench_labeled, set_global_seed};
+/// set_global_seed(1234);
+/// bench_labeled("my_benchmark", || {
+///     // Some code that should be benched
+/// });
+/// ```
+pub fn set_global_seed(seed: u64) {
+    *GLOBAL.lock().unwrap_or_else(PoisonError::into_inner) = Some(Rng::with_seed(seed));
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct Rng {
     seed: u64,
 }
@@ -19,17 +45,71 @@ impl Rng {
         }
     }
 
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        Rng { seed }
+    }
+
+    /// Seeded by `seed`, the global seed, or the system clock, in that order of precedence
+    pub(crate) fn from_seed_or_time(seed: Option<u64>) -> Self {
+        Rng::from_seed_or_global(seed, &GLOBAL)
+    }
+
+    fn from_seed_or_global(seed: Option<u64>, global: &Mutex<Option<Rng>>) -> Self {
+        if let Some(seed) = seed {
+            return Rng::with_seed(seed);
+        }
+        global
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+            .map_or_else(Rng::new, |global| Rng::with_seed(global.next()))
+    }
+
     pub(crate) fn next(&mut self) -> u64 {
         self.seed = ((A * u128::from(self.seed) + C) % MOD) as u64;
         self.seed
     }
+
+    /// Advances the generator as if `steps` numbers were drawn, in logarithmic time.
+    /// [Skip ahead](https://www.nayuki.io/page/fast-skipping-in-a-linear-congruential-generator)
+    pub(crate) fn jump(&mut self, mut steps: u64) {
+        let (mut acc_mult, mut acc_plus) = (1u128, 0u128);
+        let (mut cur_mult, mut cur_plus) = (A, C);
+        while steps > 0 {
+            if steps & 1 == 1 {
+                acc_mult = acc_mult * cur_mult % MOD;
+                acc_plus = (acc_plus * cur_mult + cur_plus) % MOD;
+            }
+            cur_plus = (cur_mult + 1) * cur_plus % MOD;
+            cur_mult = cur_mult * cur_mult % MOD;
+            steps >>= 1;
+        }
+        self.seed = ((acc_mult * u128::from(self.seed) + acc_plus) % MOD) as u64;
+    }
+
+    /// Returns a generator continuing from the current state and jumps this one ahead, so that
+    /// both produce independent, deterministic streams for use on separate threads or calls
+    pub(crate) fn split(&mut self) -> Rng {
+        let split = self.clone();
+        self.jump(SPLIT_DISTANCE);
+        split
+    }
+
+    /// [Fisher-Yates](https://en.wikipedia.org/wiki/Fisher%E2%80%93Yates_shuffle)
+    pub(crate) fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            // The low bits of an LCG with a power of two modulus have short periods, use the high ones
+            let j = ((self.next() >> 16) % (i as u64 + 1)) as usize;
+            slice.swap(i, j);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::collections::hash_map::Entry;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn test_lcg() {
@@ -50,4 +130,55 @@ mod tests {
         }
         eprintln!("{distr:?}");
     }
+
+    #[test]
+    fn shuffle_is_deterministic_with_seed() {
+        let mut a = (0..100).collect::<Vec<u32>>();
+        let mut b = a.clone();
+        Rng::with_seed(42).shuffle(&mut a);
+        Rng::with_seed(42).shuffle(&mut b);
+        assert_eq!(a, b);
+        assert_ne!((0..100).collect::<Vec<u32>>(), a);
+        a.sort_unstable();
+        assert_eq!((0..100).collect::<Vec<u32>>(), a);
+    }
+
+    #[test]
+    fn derives_seeds_from_global() {
+        let global = Mutex::new(Some(Rng::with_seed(42)));
+        let first = Rng::from_seed_or_global(None, &global).next();
+        let second = Rng::from_seed_or_global(None, &global).next();
+        assert_ne!(first, second);
+        // The configuration's seed takes precedence
+        assert_eq!(
+            Rng::with_seed(7).next(),
+            Rng::from_seed_or_global(Some(7), &global).next()
+        );
+        let replayed = Mutex::new(Some(Rng::with_seed(42)));
+        assert_eq!(first, Rng::from_seed_or_global(None, &replayed).next());
+        assert_eq!(second, Rng::from_seed_or_global(None, &replayed).next());
+    }
+
+    #[test]
+    fn jump_skips_draws() {
+        let mut stepped = Rng::with_seed(42);
+        for _ in 0..1000 {
+            stepped.next();
+        }
+        let mut jumped = Rng::with_seed(42);
+        jumped.jump(1000);
+        assert_eq!(stepped.next(), jumped.next());
+    }
+
+    #[test]
+    fn split_streams_dont_overlap() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Rng>();
+        let mut rng = Rng::with_seed(42);
+        let mut split = rng.split();
+        let draws = 100_000;
+        let split_draws = (0..draws).map(|_| split.next()).collect::<HashSet<_>>();
+        assert_eq!(draws, split_draws.len());
+        assert!((0..draws).all(|_| !split_draws.contains(&rng.next())));
+    }
 }
@@ -0,0 +1,65 @@
+/// [Simple linear regression](https://en.wikipedia.org/wiki/Simple_linear_regression) of the values
+/// over their indices, returns the slope and the coefficient of determination, R²
+pub(crate) fn linear_regression(values: &[f64]) -> (f64, f64) {
+    let indices = (0..values.len()).map(|x| x as f64).collect::<Vec<_>>();
+    let (_, slope, r_squared) = least_squares(&indices, values);
+    (slope, r_squared)
+}
+
+/// Least squares fit of `ys = intercept + slope * xs`, returns the intercept, the slope and the
+/// coefficient of determination, R². All zeroes if the xs don't vary
+pub(crate) fn least_squares(xs: &[f64], ys: &[f64]) -> (f64, f64, f64) {
+    let n = xs.len() as f64;
+    let x_mean = xs.iter().sum::<f64>() / n;
+    let y_mean = ys.iter().sum::<f64>() / n;
+    let mut covariance = 0.0;
+    let mut x_variance = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        let dx = x - x_mean;
+        covariance += dx * (y - y_mean);
+        x_variance += dx * dx;
+    }
+    if x_variance == 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let slope = covariance / x_variance;
+    let intercept = y_mean - slope * x_mean;
+    let mut residual_sum = 0.0;
+    let mut total_sum = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        residual_sum += (y - (intercept + slope * x)).powi(2);
+        total_sum += (y - y_mean).powi(2);
+    }
+    // All values equal, there's nothing for the line to explain
+    let r_squared = if total_sum == 0.0 {
+        0.0
+    } else {
+        1.0 - residual_sum / total_sum
+    };
+    (intercept, slope, r_squared)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_line() {
+        let (slope, r_squared) = linear_regression(&[1.0, 3.0, 5.0, 7.0]);
+        assert!((slope - 2.0).abs() < 0.000_001);
+        assert!((r_squared - 1.0).abs() < 0.000_001);
+        let (slope, r_squared) = linear_regression(&[1.0, 3.0, 2.0]);
+        assert!((slope - 0.5).abs() < 0.000_001);
+        assert!((r_squared - 0.25).abs() < 0.000_001);
+        assert_eq!((0.0, 0.0), linear_regression(&[4.0, 4.0, 4.0]));
+    }
+
+    #[test]
+    fn fits_line_over_xs() {
+        let (intercept, slope, r_squared) = least_squares(&[1.0, 2.0, 4.0], &[5.0, 7.0, 11.0]);
+        assert!((intercept - 3.0).abs() < 0.000_001);
+        assert!((slope - 2.0).abs() < 0.000_001);
+        assert!((r_squared - 1.0).abs() < 0.000_001);
+        assert_eq!((0.0, 0.0, 0.0), least_squares(&[2.0, 2.0], &[1.0, 3.0]));
+    }
+}
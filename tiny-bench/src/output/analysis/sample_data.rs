@@ -1,15 +1,29 @@
 use crate::benching::SamplingData;
 use crate::output::analysis::criterion::{
-    calculate_median, calculate_variance, SamplingDataSimpleAnalysis,
+    calculate_median, calculate_variance, BenchmarkConfig, OutlierFilter, Outliers,
+    SamplingDataSimpleAnalysis,
 };
 
+/// Analyzes the sampling data, leaving out severe outliers if the configuration filters them
+pub(crate) fn analyze_sampling_data(
+    sampling_data: &SamplingData,
+    cfg: &BenchmarkConfig,
+) -> SamplingDataSimpleAnalysis {
+    let analysis = simple_analyze_sampling_data(sampling_data);
+    if cfg.outlier_filter == OutlierFilter::DropSevere {
+        analysis.without_severe_outliers()
+    } else {
+        analysis
+    }
+}
+
 pub(crate) fn simple_analyze_sampling_data(
     sampling_data: &SamplingData,
 ) -> SamplingDataSimpleAnalysis {
     let mut min = f64::MAX;
     let mut max = 0f64;
     let mut total = 0f64;
-    let mut total_elapsed = 0;
+    let mut total_elapsed = 0u128;
     let mut sample_averages = Vec::with_capacity(sampling_data.samples.len());
     for (num_samples, elapsed_nanos) in sampling_data
         .samples
@@ -17,7 +31,12 @@ pub(crate) fn simple_analyze_sampling_data(
         .copied()
         .zip(sampling_data.times.iter().copied())
     {
-        let sample_average = elapsed_nanos as f64 / num_samples as f64;
+        // A sample without iterations has nothing to average, rather than NaN it took no time
+        let sample_average = if num_samples == 0 {
+            0.0
+        } else {
+            elapsed_nanos as f64 / num_samples as f64
+        };
         sample_averages.push(sample_average);
         if sample_average < min {
             min = sample_average;
@@ -26,13 +45,13 @@ pub(crate) fn simple_analyze_sampling_data(
             max = sample_average;
         }
         total += sample_average;
-        total_elapsed += elapsed_nanos;
+        total_elapsed = total_elapsed.saturating_add(elapsed_nanos);
     }
     let median = calculate_median(&mut sample_averages);
     let total_average = total / sampling_data.samples.len() as f64;
     let variance = calculate_variance(&sample_averages, total_average);
     let stddev = variance.sqrt();
-    SamplingDataSimpleAnalysis {
+    let mut analysis = SamplingDataSimpleAnalysis {
         elapsed: total_elapsed,
         min,
         max,
@@ -40,6 +59,95 @@ pub(crate) fn simple_analyze_sampling_data(
         median,
         variance,
         stddev,
+        p25: 0.0,
+        p75: 0.0,
+        p95: 0.0,
+        p99: 0.0,
+        outliers: Outliers::default(),
         per_sample_average: sample_averages,
+    };
+    // Sorted by calculating the median
+    [analysis.p25, analysis.p75, analysis.p95, analysis.p99] =
+        [25.0, 75.0, 95.0, 99.0].map(|p| analysis.percentile(p));
+    analysis.outliers = analysis.classify_outliers();
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_huge_totals() {
+        let analysis = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![u64::MAX, u64::MAX],
+            times: vec![u128::MAX - 1, 3],
+            metrics: Vec::new(),
+        });
+        assert_eq!(u128::MAX, analysis.elapsed);
+        let exact = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![5_000_000_000, 5_000_000_000],
+            times: vec![(1 << 53) + 1, 1 << 53],
+            metrics: Vec::new(),
+        });
+        assert_eq!((1 << 54) + 1, exact.elapsed);
+    }
+
+    #[test]
+    fn interpolates_percentiles() {
+        let analysis = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![1, 1, 1, 1, 1],
+            times: vec![50, 10, 40, 20, 30],
+            metrics: Vec::new(),
+        });
+        assert!((analysis.p25 - 20.0).abs() < f64::EPSILON);
+        assert!((analysis.p75 - 40.0).abs() < f64::EPSILON);
+        assert!((analysis.p95 - 48.0).abs() < 1e-9);
+        assert!((analysis.percentile(0.0) - 10.0).abs() < f64::EPSILON);
+        assert!((analysis.percentile(110.0) - 50.0).abs() < f64::EPSILON);
+        let single = simple_analyze_sampling_data(&SamplingData {
+            samples: vec![2],
+            times: vec![10],
+            metrics: Vec::new(),
+        });
+        assert!((single.p99 - 5.0).abs() < f64::EPSILON);
+        let empty = simple_analyze_sampling_data(&SamplingData {
+            samples: Vec::new(),
+            times: Vec::new(),
+            metrics: Vec::new(),
+        });
+        assert!(empty.p25.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn classifies_and_filters_outliers() {
+        let mut times = vec![100; 16];
+        times.extend([10, 130, 200]);
+        let sampling_data = SamplingData {
+            samples: vec![1; times.len()],
+            times,
+            metrics: Vec::new(),
+        };
+        let analysis = simple_analyze_sampling_data(&sampling_data);
+        let expected = Outliers {
+            low_severe: 1,
+            low_mild: 0,
+            high_mild: 0,
+            high_severe: 2,
+            filtered: false,
+        };
+        assert_eq!(expected, analysis.outliers);
+        let filtered = analyze_sampling_data(
+            &sampling_data,
+            &BenchmarkConfig {
+                outlier_filter: OutlierFilter::DropSevere,
+                ..BenchmarkConfig::default()
+            },
+        );
+        assert!(filtered.outliers.filtered);
+        assert_eq!(16, filtered.per_sample_average.len());
+        assert!((filtered.average - 100.0).abs() < f64::EPSILON);
+        assert!(filtered.stddev.abs() < f64::EPSILON);
+        assert!((filtered.max - 200.0).abs() < f64::EPSILON);
     }
 }
@@ -0,0 +1,165 @@
+//! Lightweight significance tests using the normal approximation of the t-distribution,
+//! used where there's no bootstrapped distribution to compare against.
+
+/// [Welch's t-test](https://en.wikipedia.org/wiki/Welch%27s_t-test), doesn't assume equal variances
+pub(crate) fn welch_t_value(
+    mean_a: f64,
+    variance_a: f64,
+    len_a: f64,
+    mean_b: f64,
+    variance_b: f64,
+    len_b: f64,
+) -> f64 {
+    (mean_a - mean_b) / (variance_a / len_a + variance_b / len_b).sqrt()
+}
+
+/// Two-tailed p-value approximating the t-distribution with the normal distribution,
+/// which is good enough at the iteration counts a timed run produces.
+/// 1 if `t` is NaN, which means that neither run varied nor differed, so there's nothing to test
+pub(crate) fn two_tailed_p_value(t: f64) -> f64 {
+    if t.is_nan() {
+        return 1.0;
+    }
+    erfc(t.abs() / std::f64::consts::SQRT_2)
+}
+
+/// Complementary error function, [Numerical Recipes](https://numerical.recipes/) `erfcc`
+/// with a fractional error below 1.2e-7
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let ans = t * poly.exp();
+    if x >= 0.0 {
+        ans
+    } else {
+        2.0 - ans
+    }
+}
+
+/// Corrects the p-values of a family of comparisons, returned in the same order
+#[cfg(feature = "bench")]
+pub(crate) fn correct_p_values(
+    p_values: &[f64],
+    correction: crate::output::analysis::criterion::FamilyCorrection,
+) -> Vec<f64> {
+    use crate::output::analysis::criterion::FamilyCorrection;
+    let family = p_values.len() as f64;
+    match correction {
+        FamilyCorrection::Bonferroni => p_values.iter().map(|p| (p * family).min(1.0)).collect(),
+        FamilyCorrection::Holm => {
+            let mut order = (0..p_values.len()).collect::<Vec<_>>();
+            order.sort_by(|&a, &b| p_values[a].total_cmp(&p_values[b]));
+            let mut corrected = vec![0.0; p_values.len()];
+            // Corrected p-values may not drop below a smaller p-value's, so the order is kept
+            let mut running_max = 0.0f64;
+            for (rank, index) in order.into_iter().enumerate() {
+                running_max = running_max.max((p_values[index] * (family - rank as f64)).min(1.0));
+                corrected[index] = running_max;
+            }
+            corrected
+        }
+    }
+}
+
+/// Inverse of the standard normal distribution's cumulative distribution function,
+/// [Acklam's algorithm](https://web.archive.org/web/20151030215612/http://home.online.no/~pjacklam/notes/invnorm/)
+/// with a relative error below 1.15e-9
+#[cfg(feature = "bench")]
+pub(crate) fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -39.696_830_286_653_76,
+        220.946_098_424_520_5,
+        -275.928_510_446_968_7,
+        138.357_751_867_269,
+        -30.664_798_066_147_16,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -54.476_098_798_224_06,
+        161.585_836_858_040_9,
+        -155.698_979_859_886_6,
+        66.801_311_887_719_72,
+        -13.280_681_552_885_72,
+    ];
+    const C: [f64; 6] = [
+        -0.007_784_894_002_430_293,
+        -0.322_396_458_041_136_5,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        0.007_784_695_709_041_462,
+        0.322_467_129_070_039_8,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.024_25;
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < P_LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calculates_p_value() {
+        assert!((two_tailed_p_value(1.96) - 0.05).abs() < 0.001);
+        assert!((two_tailed_p_value(-2.576) - 0.01).abs() < 0.001);
+        assert!((two_tailed_p_value(0.0) - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn calculates_normal_quantile() {
+        assert!((normal_quantile(0.975) - 1.959_964).abs() < 0.000_001);
+        assert!((normal_quantile(0.8) - 0.841_621).abs() < 0.000_001);
+        assert!((normal_quantile(0.01) + 2.326_348).abs() < 0.000_001);
+        assert!(normal_quantile(0.5).abs() < 0.000_001);
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn corrects_p_values() {
+        use crate::output::analysis::criterion::FamilyCorrection;
+        let p_values = [0.01, 0.04, 0.03, 0.5];
+        let close = |a: &[f64], b: &[f64]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-12);
+        assert!(close(
+            &[0.04, 0.16, 0.12, 1.0],
+            &correct_p_values(&p_values, FamilyCorrection::Bonferroni)
+        ));
+        assert!(close(
+            &[0.04, 0.09, 0.09, 0.5],
+            &correct_p_values(&p_values, FamilyCorrection::Holm)
+        ));
+        assert!(correct_p_values(&[], FamilyCorrection::Holm).is_empty());
+    }
+
+    #[test]
+    fn calculates_welch_t() {
+        let t = welch_t_value(20.0, 4.0, 100.0, 19.0, 9.0, 100.0);
+        assert!((t - 2.773_500_98).abs() < 0.000_001);
+    }
+}
@@ -0,0 +1,218 @@
+//! How sampled results are encoded on disk, separate from where they're stored and how they're
+//! rotated, which is `disk`'s business
+use crate::benching::{SampleResult, SamplingData};
+#[cfg(feature = "json-codec")]
+use crate::error::Error;
+use crate::error::Result;
+
+/// The samples of one run as they're persisted, what a [`ResultCodec`] encodes
+#[derive(Debug, Clone, Default)]
+pub struct PersistedSamples {
+    /// Each sample, in the order they were measured
+    pub samples: Vec<SampleResult>,
+    /// Total of the secondary metric over each sample's iterations, empty if the benchmark
+    /// doesn't measure one
+    pub metrics: Vec<u64>,
+}
+
+/// Turns persisted samples into bytes and back, selected with `BenchmarkConfig::codec`.
+/// Results are decoded with the configured codec, results written with another one can't be
+/// read and are treated like a corrupt file until they've been replaced
+pub trait ResultCodec: Send + Sync {
+    /// Encodes the samples
+    fn encode(&self, samples: &PersistedSamples) -> Vec<u8>;
+
+    /// Decodes what [`ResultCodec::encode`] produced
+    /// # Errors
+    /// If the bytes aren't valid for this codec
+    fn decode(&self, buf: &[u8]) -> Result<PersistedSamples>;
+}
+
+/// The default codec, a compact little-endian layout with a version byte and a checksum
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BinaryCodec;
+
+impl ResultCodec for BinaryCodec {
+    fn encode(&self, samples: &PersistedSamples) -> Vec<u8> {
+        crate::output::ser::ser_sampling_data(&to_sampling_data(samples))
+    }
+
+    fn decode(&self, buf: &[u8]) -> Result<PersistedSamples> {
+        crate::output::ser::try_de_sampling_data(buf).map(|data| from_sampling_data(&data))
+    }
+}
+
+/// A JSON object with an array per field, readable by other tools:
+///
+/// ```json
+/// {"iterations":[..],"elapsed_nanos":[..],"metrics":[..]}
+/// ```
+///
+/// Several times larger on disk than [`BinaryCodec`]
+#[cfg(feature = "json-codec")]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct JsonCodec;
+
+#[cfg(feature = "json-codec")]
+impl ResultCodec for JsonCodec {
+    fn encode(&self, samples: &PersistedSamples) -> Vec<u8> {
+        let join = |values: Vec<String>| values.join(",");
+        format!(
+            "{{\"iterations\":[{}],\"elapsed_nanos\":[{}],\"metrics\":[{}]}}\n",
+            join(
+                samples
+                    .samples
+                    .iter()
+                    .map(|s| s.iterations.to_string())
+                    .collect()
+            ),
+            join(
+                samples
+                    .samples
+                    .iter()
+                    .map(|s| s.elapsed_nanos.to_string())
+                    .collect()
+            ),
+            join(samples.metrics.iter().map(u64::to_string).collect()),
+        )
+        .into_bytes()
+    }
+
+    fn decode(&self, buf: &[u8]) -> Result<PersistedSamples> {
+        let json = std::str::from_utf8(buf)
+            .map_err(|e| Error::new(format!("Found malformed JSON results, cause {e}")))?;
+        let iterations = json_array::<u64>(json, "iterations")?;
+        let elapsed_nanos = json_array::<u128>(json, "elapsed_nanos")?;
+        let metrics = json_array::<u64>(json, "metrics")?;
+        if iterations.len() != elapsed_nanos.len()
+            || !(metrics.is_empty() || metrics.len() == iterations.len())
+        {
+            return Err(Error::new(format!(
+                "Found malformed JSON results, {} iterations, {} elapsed_nanos and {} metrics",
+                iterations.len(),
+                elapsed_nanos.len(),
+                metrics.len()
+            )));
+        }
+        Ok(PersistedSamples {
+            samples: iterations
+                .into_iter()
+                .zip(elapsed_nanos)
+                .enumerate()
+                .map(|(index, (iterations, elapsed_nanos))| SampleResult {
+                    index,
+                    iterations,
+                    elapsed_nanos,
+                })
+                .collect(),
+            metrics,
+        })
+    }
+}
+
+/// The integers of the array under `key`, the only shape [`JsonCodec`] writes
+#[cfg(feature = "json-codec")]
+fn json_array<T: std::str::FromStr>(json: &str, key: &str) -> Result<Vec<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    let quoted = format!("\"{key}\"");
+    let missing = || Error::new(format!("Found malformed JSON results, no array {quoted}"));
+    let after_key = &json[json.find(&quoted).ok_or_else(missing)? + quoted.len()..];
+    let after_colon = after_key
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or_else(missing)?;
+    let array = after_colon
+        .trim_start()
+        .strip_prefix('[')
+        .ok_or_else(missing)?;
+    let array = &array[..array.find(']').ok_or_else(missing)?];
+    array
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            value.parse().map_err(|e| {
+                Error::new(format!(
+                    "Found malformed JSON results, {value} in {quoted}, cause {e}"
+                ))
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn from_sampling_data(data: &SamplingData) -> PersistedSamples {
+    PersistedSamples {
+        samples: data
+            .samples
+            .iter()
+            .zip(&data.times)
+            .enumerate()
+            .map(|(index, (&iterations, &elapsed_nanos))| SampleResult {
+                index,
+                iterations,
+                elapsed_nanos,
+            })
+            .collect(),
+        metrics: data.metrics.clone(),
+    }
+}
+
+pub(crate) fn to_sampling_data(samples: &PersistedSamples) -> SamplingData {
+    SamplingData {
+        samples: samples.samples.iter().map(|s| s.iterations).collect(),
+        times: samples.samples.iter().map(|s| s.elapsed_nanos).collect(),
+        metrics: samples.metrics.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sampling_data() -> SamplingData {
+        SamplingData {
+            samples: vec![1, 2, 3],
+            times: vec![10, 20, u128::MAX],
+            metrics: vec![4, 5, 6],
+        }
+    }
+
+    #[test]
+    fn round_trips_binary() {
+        let data = sampling_data();
+        let encoded = BinaryCodec.encode(&from_sampling_data(&data));
+        assert_eq!(
+            data,
+            to_sampling_data(&BinaryCodec.decode(&encoded).unwrap())
+        );
+        assert!(BinaryCodec.decode(b"{}").is_err());
+    }
+
+    #[cfg(feature = "json-codec")]
+    #[test]
+    fn round_trips_json() {
+        let data = sampling_data();
+        let encoded = JsonCodec.encode(&from_sampling_data(&data));
+        assert_eq!(
+            format!(
+                "{{\"iterations\":[1,2,3],\"elapsed_nanos\":[10,20,{}],\"metrics\":[4,5,6]}}\n",
+                u128::MAX
+            ),
+            String::from_utf8(encoded.clone()).unwrap()
+        );
+        assert_eq!(data, to_sampling_data(&JsonCodec.decode(&encoded).unwrap()));
+        let spaced = b"{ \"iterations\" : [ 1 ], \"elapsed_nanos\": [2], \"metrics\": [] }";
+        let decoded = JsonCodec.decode(spaced).unwrap();
+        assert_eq!(1, decoded.samples[0].iterations);
+        assert_eq!(2, decoded.samples[0].elapsed_nanos);
+        assert!(decoded.metrics.is_empty());
+        assert!(JsonCodec
+            .decode(b"{\"iterations\":[1],\"elapsed_nanos\":[]}")
+            .is_err());
+        assert!(JsonCodec
+            .decode(&BinaryCodec.encode(&from_sampling_data(&data)))
+            .is_err());
+    }
+}
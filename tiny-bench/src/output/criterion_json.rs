@@ -0,0 +1,178 @@
+//! Line-delimited JSON in the message format of
+//! [cargo-criterion](https://github.com/bheisler/cargo-criterion) 1.1, which tools like
+//! [bencher](https://bencher.dev) already parse. Each benchmark prints one line:
+//!
+//! ```json
+//! {"reason":"benchmark-complete","id":"label","report_directory":null,
+//!  "iteration_count":[..],"measured_values":[..],"unit":"ns","throughput":[],
+//!  "typical":{"estimate":..,"lower_bound":..,"upper_bound":..,"unit":"ns"},
+//!  "mean":{..},"median":{..},"median_abs_dev":{..},"slope":null,"change":null}
+//! ```
+//!
+//! Times are in nanoseconds. There's no bootstrap here, the bounds of the mean are its 95%
+//! confidence interval from the normal approximation, the bounds of the median and its absolute
+//! deviation equal their estimates.
+use crate::benching::{RunComparison, SamplingData};
+use crate::output::analysis::criterion::{BenchmarkConfig, SamplingDataSimpleAnalysis};
+use crate::output::json::{fmt_json_number, fmt_json_string};
+use crate::output::stream::outln;
+use crate::output::{write_last_sampling, Output};
+#[cfg(feature = "timer")]
+use crate::timing::TimingData;
+
+/// z-value of a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Prints results as cargo-criterion `benchmark-complete` messages, see the module docs
+pub struct CriterionJsonStdout;
+
+impl Output for CriterionJsonStdout {
+    #[cfg(feature = "timer")]
+    fn dump_timing_data(&self, label: &'static str, data: TimingData) {
+        let mean = data.mean();
+        let half_width = Z_95 * (data.variance() / data.iterations as f64).sqrt();
+        outln!(
+            "{}",
+            fmt_message(
+                label,
+                &[data.iterations],
+                &[data.elapsed as f64],
+                Estimate::within(mean, mean - half_width, mean + half_width),
+                Estimate::exact(mean),
+                Estimate::exact(0.0),
+            )
+        );
+    }
+
+    fn dump_sampling_data(
+        &self,
+        label: &'static str,
+        sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
+        cfg: &BenchmarkConfig,
+        _total_iters: u128,
+    ) -> Option<RunComparison> {
+        let half_width =
+            Z_95 * (analysis.variance / analysis.per_sample_average.len() as f64).sqrt();
+        let mean = Estimate::within(
+            analysis.average,
+            analysis.average - half_width,
+            analysis.average + half_width,
+        );
+        let measured_values = sampling_data
+            .times
+            .iter()
+            .map(|time| *time as f64)
+            .collect::<Vec<_>>();
+        outln!(
+            "{}",
+            fmt_message(
+                label,
+                &sampling_data
+                    .samples
+                    .iter()
+                    .map(|samples| u128::from(*samples))
+                    .collect::<Vec<_>>(),
+                &measured_values,
+                mean,
+                Estimate::exact(analysis.median),
+                Estimate::exact(median_abs_dev(
+                    &analysis.per_sample_average,
+                    analysis.median
+                )),
+            )
+        );
+        if cfg.dump_results_to_disk {
+            write_last_sampling(label, cfg, sampling_data);
+        }
+        None
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Estimate {
+    point: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+}
+
+impl Estimate {
+    fn exact(point: f64) -> Self {
+        Self::within(point, point, point)
+    }
+
+    fn within(point: f64, lower_bound: f64, upper_bound: f64) -> Self {
+        Estimate {
+            point,
+            lower_bound,
+            upper_bound,
+        }
+    }
+
+    fn to_json(self) -> String {
+        format!(
+            "{{\"estimate\":{},\"lower_bound\":{},\"upper_bound\":{},\"unit\":\"ns\"}}",
+            fmt_json_number(self.point),
+            fmt_json_number(self.lower_bound),
+            fmt_json_number(self.upper_bound)
+        )
+    }
+}
+
+fn fmt_message(
+    label: &str,
+    iteration_count: &[u128],
+    measured_values: &[f64],
+    mean: Estimate,
+    median: Estimate,
+    median_abs_dev: Estimate,
+) -> String {
+    let iteration_count = iteration_count
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let measured_values = measured_values
+        .iter()
+        .map(|value| fmt_json_number(*value))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"reason\":\"benchmark-complete\",\"id\":{},\"report_directory\":null,\"iteration_count\":[{iteration_count}],\"measured_values\":[{measured_values}],\"unit\":\"ns\",\"throughput\":[],\"typical\":{},\"mean\":{},\"median\":{},\"median_abs_dev\":{},\"slope\":null,\"change\":null}}",
+        fmt_json_string(label),
+        mean.to_json(),
+        mean.to_json(),
+        median.to_json(),
+        median_abs_dev.to_json(),
+    )
+}
+
+/// Median of the absolute deviations from the median
+fn median_abs_dev(values: &[f64], median: f64) -> f64 {
+    let mut deviations = values
+        .iter()
+        .map(|value| (value - median).abs())
+        .collect::<Vec<_>>();
+    crate::output::analysis::criterion::calculate_median(&mut deviations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_message() {
+        let message = fmt_message(
+            "my \"bench\"",
+            &[10, 20],
+            &[100.0, 210.5],
+            Estimate::within(10.25, 10.0, 10.5),
+            Estimate::exact(10.0),
+            Estimate::exact(0.5),
+        );
+        assert_eq!(
+            "{\"reason\":\"benchmark-complete\",\"id\":\"my \\\"bench\\\"\",\"report_directory\":null,\"iteration_count\":[10,20],\"measured_values\":[100,210.5],\"unit\":\"ns\",\"throughput\":[],\"typical\":{\"estimate\":10.25,\"lower_bound\":10,\"upper_bound\":10.5,\"unit\":\"ns\"},\"mean\":{\"estimate\":10.25,\"lower_bound\":10,\"upper_bound\":10.5,\"unit\":\"ns\"},\"median\":{\"estimate\":10,\"lower_bound\":10,\"upper_bound\":10,\"unit\":\"ns\"},\"median_abs_dev\":{\"estimate\":0.5,\"lower_bound\":0.5,\"upper_bound\":0.5,\"unit\":\"ns\"},\"slope\":null,\"change\":null}",
+            message
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Plain text comparisons meant to be pasted into a code review, one aligned line per benchmark
+//! and sorted by label, so that the same benchmarks line up in a diff between two reports:
+//!
+//! ```text
+//! decode  1.20µs -> 1.10µs  (-8.33%, p=0.00)
+//! encode  2.31µs -> 2.35µs  (+1.73%, p=0.41)
+//! new            -> 3.00µs
+//! ```
+use crate::benching::{RunComparison, SamplingData};
+use crate::output::analysis::criterion::{BenchmarkConfig, SamplingDataSimpleAnalysis};
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::stream::{out, outln};
+use crate::output::{
+    compare_sampling, fmt_signed_change, fmt_time, read_last_sampling, write_last_sampling, Output,
+};
+#[cfg(feature = "timer")]
+use crate::timing::TimingData;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Comparisons recorded so far in this process, printed by `print_diff_text`
+static RECORDED: Mutex<Vec<DiffLine>> = Mutex::new(Vec::new());
+
+/// Records each benchmark's comparison with the last persisted run without printing it,
+/// see the module docs
+pub struct DiffTextStdout;
+
+struct DiffLine {
+    label: &'static str,
+    old_mean: Option<f64>,
+    mean: f64,
+    /// Change in percent and its p-value
+    change: Option<(f64, f64)>,
+}
+
+impl Output for DiffTextStdout {
+    #[cfg(feature = "timer")]
+    fn dump_timing_data(&self, label: &'static str, data: TimingData) {
+        record(DiffLine {
+            label,
+            old_mean: None,
+            mean: data.mean(),
+            change: None,
+        });
+    }
+
+    fn dump_sampling_data(
+        &self,
+        label: &'static str,
+        sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
+        cfg: &BenchmarkConfig,
+        _total_iters: u128,
+    ) -> Option<RunComparison> {
+        let mut comparison = None;
+        let mut line = DiffLine {
+            label,
+            old_mean: None,
+            mean: analysis.average,
+            change: None,
+        };
+        if cfg.dump_results_to_disk {
+            match read_last_sampling(label, cfg) {
+                Ok(Some((last, _normalization))) => {
+                    let old_analysis = analyze_sampling_data(&last, cfg);
+                    let cmp = compare_sampling(analysis, &old_analysis, cfg);
+                    line.old_mean = Some(old_analysis.average);
+                    line.change = Some((cmp.mean.mean, cmp.p));
+                    comparison = Some(cmp.to_run_comparison());
+                }
+                Err(e) => outln!("Failed to read last sample of {label}, cause {e}"),
+                Ok(None) => {}
+            }
+            write_last_sampling(label, cfg, sampling_data);
+        }
+        record(line);
+        comparison
+    }
+}
+
+fn record(line: DiffLine) {
+    RECORDED
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .push(line);
+}
+
+fn fmt_diff_text(lines: &mut [DiffLine]) -> String {
+    lines.sort_by(|a, b| a.label.cmp(b.label).then(a.mean.total_cmp(&b.mean)));
+    let label_width = lines
+        .iter()
+        .map(|l| l.label.chars().count())
+        .max()
+        .unwrap_or(0);
+    let old_width = lines
+        .iter()
+        .filter_map(|l| l.old_mean.map(|mean| fmt_time(mean).chars().count()))
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for line in lines.iter() {
+        let old = line.old_mean.map(fmt_time).unwrap_or_default();
+        let _ = write!(
+            out,
+            "{:label_width$}  {old:>old_width$} -> {}",
+            line.label,
+            fmt_time(line.mean)
+        );
+        if let Some((change, p)) = line.change {
+            let _ = write!(out, "  ({}, p={p:.2})", fmt_signed_change(change));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Prints the comparisons of all benchmarks run with `OutputFormat::DiffText` so far, aligned,
+/// uncolored, and sorted by label. Call it once after all benchmarks have run.
+pub fn print_diff_text() {
+    let mut lines = RECORDED
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    out!("{}", fmt_diff_text(&mut lines));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fmt_diff_text, DiffLine};
+
+    #[test]
+    fn formats_aligned_and_sorted() {
+        let mut lines = vec![
+            DiffLine {
+                label: "new",
+                old_mean: None,
+                mean: 3000.0,
+                change: None,
+            },
+            DiffLine {
+                label: "decode",
+                old_mean: Some(1200.0),
+                mean: 1100.0,
+                change: Some((-8.333, 0.001)),
+            },
+            DiffLine {
+                label: "enc",
+                old_mean: Some(20.0),
+                mean: 20.4,
+                change: Some((2.0, 0.41)),
+            },
+        ];
+        assert_eq!(
+            "decode   1.20µs -> 1.10µs  (-8.33%, p=0.00)\n\
+             enc     20.00ns -> 20.40ns  (+2.00%, p=0.41)\n\
+             new             -> 3.00µs\n",
+            fmt_diff_text(&mut lines)
+        );
+    }
+}
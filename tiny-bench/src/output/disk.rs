@@ -1,27 +1,144 @@
 #[cfg(feature = "bench")]
 use crate::benching::SamplingData;
 use crate::error::{Error, Result};
+#[cfg(all(feature = "bench", test))]
+use crate::output::codec::BinaryCodec;
+#[cfg(feature = "bench")]
+use crate::output::codec::{from_sampling_data, to_sampling_data, ResultCodec};
+use crate::output::stream::outln;
 use crate::output::{wrap_high_insensity_red, wrap_yellow};
 #[cfg(feature = "timer")]
 use crate::timing::TimingData;
 use std::ffi::OsStr;
 use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[cfg(feature = "timer")]
 const CURRENT_RESULTS: &str = "current-results";
-#[cfg(feature = "timer")]
 const OLD_RESULTS: &str = "old-results";
 
-#[cfg(feature = "bench")]
 const CURRENT_SAMPLE: &str = "current-sample";
 
-#[cfg(feature = "bench")]
 const OLD_SAMPLE: &str = "old-sample";
 
+/// The mean of every persisted run in nanoseconds, one per line, oldest first
+const HISTORY: &str = "history";
+
+/// The calibration score and name of the machine the current sample was persisted on,
+/// see `Calibration::Normalize`
+const CALIBRATION: &str = "calibration";
+
+/// The sorted per-iteration latencies of the last `bench_latency_cdf` run
+const CURRENT_LATENCY_CDF: &str = "current-latency-cdf";
+
+const OLD_LATENCY_CDF: &str = "old-latency-cdf";
+
+/// The environment the current sample was persisted from, see `environment`
+const ENVIRONMENT: &str = "environment";
+
+/// A directory containing any of these holds a label's results
+const PERSISTED_FILES: [&str; 9] = [
+    CURRENT_RESULTS,
+    OLD_RESULTS,
+    CURRENT_SAMPLE,
+    OLD_SAMPLE,
+    HISTORY,
+    CALIBRATION,
+    CURRENT_LATENCY_CDF,
+    OLD_LATENCY_CDF,
+    ENVIRONMENT,
+];
+
+const RESULTS_DIR: &str = "simple-bench";
+
+/// Parent of the results namespaced per git branch, see `GitBranch`
+#[cfg(feature = "bench")]
+pub(crate) const BRANCHES_DIR: &str = "branches";
+
+/// Parent of the results persisted per run id, see `BenchmarkConfig::run_id`
+#[cfg(feature = "bench")]
+pub(crate) const RUNS_DIR: &str = "runs";
+
+/// Lists the labels of all persisted results, sorted. Nested labels have their components
+/// separated by `/`, whichever separator they were persisted with.
+/// # Errors
+/// If the target directory can't be found or the results can't be read
+pub fn list_persisted() -> Result<Vec<String>> {
+    let root = find_results_root()?;
+    let mut labels = Vec::new();
+    if root.is_dir() {
+        collect_labels(&root, &mut Vec::new(), &mut labels)?;
+    }
+    labels.sort();
+    Ok(labels)
+}
+
+fn collect_labels(
+    dir: &Path,
+    components: &mut Vec<String>,
+    labels: &mut Vec<String>,
+) -> Result<()> {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        Error::new(format!(
+            "Failed to read directory {}, cause {e}",
+            dir.display()
+        ))
+    })?;
+    let mut is_label = false;
+    for entry in entries {
+        let entry = entry.map_err(|e| {
+            Error::new(format!(
+                "Failed to read entry in {}, cause {e}",
+                dir.display()
+            ))
+        })?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() {
+            components.push(name);
+            collect_labels(&entry.path(), components, labels)?;
+            components.pop();
+        } else if PERSISTED_FILES.contains(&name.as_str()) {
+            is_label = true;
+        }
+    }
+    if is_label && !components.is_empty() {
+        labels.push(components.join("/"));
+    }
+    Ok(())
+}
+
+/// Removes the persisted results of a label, as listed by [`list_persisted`].
+/// Nested labels are removed along with their results.
+/// # Errors
+/// If the label is invalid or its results can't be removed
+pub fn clear_persisted(label: &str) -> Result<()> {
+    let components = label_components(label, Some("/"))?;
+    let dir = components
+        .iter()
+        .fold(find_results_root()?, |dir, component| dir.join(component));
+    remove_dir(&dir)
+}
+
+/// Removes all persisted results
+/// # Errors
+/// If the results can't be removed
+pub fn clear_all_persisted() -> Result<()> {
+    remove_dir(&find_results_root()?)
+}
+
+fn remove_dir(dir: &Path) -> Result<()> {
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(Error::new(format!(
+            "Failed to remove {}, cause {e}",
+            dir.display()
+        ))),
+    }
+}
+
 #[cfg(feature = "timer")]
 pub(crate) fn try_read_last_results(label: &'static str) -> Result<Option<TimingData>> {
-    let maybe_data = try_read(label, CURRENT_RESULTS)?;
+    let maybe_data = try_read(label, None, CURRENT_RESULTS)?;
     if let Some(data) = maybe_data {
         Ok(Some(crate::output::ser::try_de_timing_data(&data)?))
     } else {
@@ -33,119 +150,428 @@ pub(crate) fn try_read_last_results(label: &'static str) -> Result<Option<Timing
 pub(crate) fn try_write_results(label: &'static str, data: TimingData) {
     if let Err(e) = try_write(
         label,
+        None,
         &crate::output::ser::ser_timing_data(data),
         CURRENT_RESULTS,
         OLD_RESULTS,
     ) {
-        println!(
+        outln!(
             "{} {e}",
             wrap_high_insensity_red("Failed to write timing data, cause")
         );
     }
 }
 
-#[cfg(feature = "bench")]
-pub(crate) fn try_write_last_simpling(label: &'static str, data: &SamplingData) {
-    if let Err(e) = try_write(
-        label,
-        &crate::output::ser::ser_sampling_data(data),
-        CURRENT_SAMPLE,
-        OLD_SAMPLE,
-    ) {
-        println!(
+#[cfg(all(feature = "bench", test))]
+pub(crate) fn try_write_last_simpling(
+    label: &'static str,
+    separator: Option<&'static str>,
+    data: &SamplingData,
+) {
+    if let Err(e) = write_last_simpling(label, separator, data, &BinaryCodec)
+        .and_then(|()| append_history(label, separator, data))
+    {
+        outln!(
             "{} {e}",
             wrap_high_insensity_red("Failed to write sampling data, cause:")
         );
     }
 }
 
+/// Rotates the current sample to the old one and writes the data encoded with the codec in its
+/// place
+#[cfg(feature = "bench")]
+pub(crate) fn write_last_simpling(
+    label: &'static str,
+    separator: Option<&'static str>,
+    data: &SamplingData,
+    codec: &dyn ResultCodec,
+) -> Result<()> {
+    try_write(
+        label,
+        separator,
+        &codec.encode(&from_sampling_data(data)),
+        CURRENT_SAMPLE,
+        OLD_SAMPLE,
+    )
+}
+
+/// Appends the mean of the data to the history of persisted means
+#[cfg(feature = "bench")]
+pub(crate) fn append_history(
+    label: &'static str,
+    separator: Option<&'static str>,
+    data: &SamplingData,
+) -> Result<()> {
+    use std::io::Write;
+    let mean = crate::output::analysis::criterion::calculate_mean(&data.per_sample_average());
+    let components = label_components(label, separator)
+        .map_err(|e| Error::new(format!("{e}, cannot write to disk.")))?;
+    let history = find_or_create_result_parent_dir(&components)?.join(HISTORY);
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&history)
+        .and_then(|mut file| writeln!(file, "{mean}"))
+        .map_err(|e| {
+            Error::new(format!(
+                "Failed to append to history at {}, cause {e}",
+                history.display()
+            ))
+        })
+}
+
+/// Reads the means of all persisted runs, oldest first
+#[cfg(feature = "bench")]
+pub(crate) fn try_read_history(
+    label: &'static str,
+    separator: Option<&'static str>,
+) -> Result<Vec<f64>> {
+    let Some(data) = try_read(label, separator, HISTORY)? else {
+        return Ok(Vec::new());
+    };
+    String::from_utf8_lossy(&data)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.trim()
+                .parse()
+                .map_err(|e| Error::new(format!("Malformed history entry {line}, cause {e}")))
+        })
+        .collect()
+}
+
 fn try_write(
     label: &'static str,
+    separator: Option<&'static str>,
     data: &[u8],
     current_file_name: &str,
     old_file_name: &'static str,
 ) -> Result<()> {
-    if label.contains(std::path::is_separator) {
-        return Err(Error::new(format!(
-            "Label {label} contains a path separator, cannot write to disk."
-        )));
-    }
-    let parent_dir = find_or_create_result_parent_dir(label)?;
+    let components = label_components(label, separator)
+        .map_err(|e| Error::new(format!("{e}, cannot write to disk.")))?;
+    let parent_dir = find_or_create_result_parent_dir(&components)?;
     std::fs::create_dir_all(&parent_dir).map_err(|e| {
         Error::new(format!(
-            "Failed to create output directory {parent_dir:?}, cause {e}, will not write results"
+            "Failed to create output directory {}, cause {e}, will not write results",
+            parent_dir.display()
         ))
     })?;
 
+    // Written in full before anything is moved, so that an interrupted write never leaves a
+    // truncated current file behind, at worst the old file and a stray temporary one
+    let temporary = parent_dir.join(format!(".{current_file_name}.tmp"));
+    write_synced(&temporary, data).map_err(|e| {
+        Error::new(format!(
+            "Failed to write benchmark-data to {}, cause {e}",
+            temporary.display()
+        ))
+    })?;
     let latest_persisted = parent_dir.join(current_file_name);
     if std::fs::metadata(&latest_persisted).is_ok() {
         let old_file = parent_dir.join(old_file_name);
         if let Err(e) = std::fs::rename(&latest_persisted, &old_file) {
-            println!(
-                "{} from {latest_persisted:?} to {old_file:?}, cause {e}, will try to overwrite.",
-                wrap_yellow("Failed to move old sample")
+            outln!(
+                "{} from {} to {}, cause {e}, will try to overwrite.",
+                wrap_yellow("Failed to move old sample"),
+                latest_persisted.display(),
+                old_file.display()
             );
         }
     }
-    std::fs::write(&latest_persisted, data).map_err(|e| {
+    std::fs::rename(&temporary, &latest_persisted).map_err(|e| {
         Error::new(format!(
-            "Failed to write benchmark-data to {latest_persisted:?}, cause {e}"
+            "Failed to move benchmark-data from {} to {}, cause {e}",
+            temporary.display(),
+            latest_persisted.display()
         ))
     })
 }
 
-fn try_read(label: &'static str, current_file_name: &'static str) -> Result<Option<Vec<u8>>> {
-    if label.contains(std::path::is_separator) {
-        return Err(Error::new(format!(
-            "Label {label} contains a path separator, cannot read old data from disk."
-        )));
-    }
-    let parent_dir = find_or_create_result_parent_dir(label)?;
+/// Writes the data to a temporary file in `dir` and renames it into place, so that an interrupted
+/// write never leaves a truncated file behind, like the results
+#[cfg(feature = "bench")]
+fn write_atomically(dir: &Path, file_name: &str, data: &[u8]) -> std::io::Result<()> {
+    let temporary = dir.join(format!(".{file_name}.tmp"));
+    write_synced(&temporary, data)?;
+    std::fs::rename(&temporary, dir.join(file_name))
+}
+
+/// Writes and flushes the data to the device, so that a rename after it can't be persisted
+/// before the contents are
+fn write_synced(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut file = std::fs::File::create(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+fn try_read(
+    label: &'static str,
+    separator: Option<&'static str>,
+    current_file_name: &'static str,
+) -> Result<Option<Vec<u8>>> {
+    let components = label_components(label, separator)
+        .map_err(|e| Error::new(format!("{e}, cannot read old data from disk.")))?;
+    let parent_dir = find_or_create_result_parent_dir(&components)?;
     let latest_persisted_path = parent_dir.join(current_file_name);
     match std::fs::read(&latest_persisted_path) {
         Ok(bytes) => Ok(Some(bytes)),
         Err(e) => match e.kind() {
             ErrorKind::NotFound => Ok(None),
             _ => Err(Error::new(format!(
-                "Failed to read file at {latest_persisted_path:?}, cause: {e}"
+                "Failed to read file at {}, cause: {e}",
+                latest_persisted_path.display()
             ))),
         },
     }
 }
 
 #[cfg(feature = "bench")]
-pub(crate) fn try_read_last_simpling(label: &'static str) -> Result<Option<SamplingData>> {
-    let maybe_data = try_read(label, CURRENT_SAMPLE)?;
+pub(crate) fn try_read_last_simpling(
+    label: &'static str,
+    separator: Option<&'static str>,
+    codec: &dyn ResultCodec,
+) -> Result<Option<SamplingData>> {
+    let maybe_data = try_read(label, separator, CURRENT_SAMPLE)?;
+    if let Some(data) = maybe_data {
+        Ok(Some(to_sampling_data(&codec.decode(&data)?)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The sample that the current one replaced, the baseline it was compared with
+#[cfg(feature = "bench")]
+pub(crate) fn try_read_old_simpling(
+    label: &'static str,
+    separator: Option<&'static str>,
+    codec: &dyn ResultCodec,
+) -> Result<Option<SamplingData>> {
+    let maybe_data = try_read(label, separator, OLD_SAMPLE)?;
     if let Some(data) = maybe_data {
-        Ok(Some(crate::output::ser::try_de_sampling_data(&data)?))
+        Ok(Some(to_sampling_data(&codec.decode(&data)?)))
     } else {
         Ok(None)
     }
 }
 
-fn find_or_create_result_parent_dir(label: &'static str) -> Result<PathBuf> {
+/// Persists the calibration score and name of this machine alongside the current sample
+#[cfg(feature = "bench")]
+pub(crate) fn try_write_calibration(
+    label: &'static str,
+    separator: Option<&'static str>,
+    score: f64,
+    machine: &str,
+) {
+    let components = match label_components(label, separator) {
+        Ok(components) => components,
+        Err(e) => {
+            outln!(
+                "{} {e}",
+                wrap_high_insensity_red("Failed to write calibration, cause:")
+            );
+            return;
+        }
+    };
+    if let Err(e) = find_or_create_result_parent_dir(&components).and_then(|dir| {
+        let path = dir.join(CALIBRATION);
+        std::fs::write(&path, format!("{score} {machine}\n")).map_err(|e| {
+            Error::new(format!(
+                "Failed to write calibration to {}, cause {e}",
+                path.display()
+            ))
+        })
+    }) {
+        outln!(
+            "{} {e}",
+            wrap_high_insensity_red("Failed to write calibration, cause:")
+        );
+    }
+}
+
+/// The calibration score and machine name persisted with the current sample, if any
+#[cfg(feature = "bench")]
+pub(crate) fn try_read_calibration(
+    label: &'static str,
+    separator: Option<&'static str>,
+) -> Result<Option<(f64, String)>> {
+    let Some(data) = try_read(label, separator, CALIBRATION)? else {
+        return Ok(None);
+    };
+    let data = String::from_utf8_lossy(&data);
+    let (score, machine) = data
+        .trim()
+        .split_once(' ')
+        .ok_or_else(|| Error::new(format!("Malformed calibration {}", data.trim())))?;
+    let score = score
+        .parse()
+        .map_err(|e| Error::new(format!("Malformed calibration {}, cause {e}", data.trim())))?;
+    Ok(Some((score, machine.to_string())))
+}
+
+/// Persists the environment of this process alongside the current sample
+#[cfg(feature = "bench")]
+pub(crate) fn try_write_environment(
+    label: &'static str,
+    separator: Option<&'static str>,
+    environment: &crate::benching::Environment,
+) {
+    if let Err(e) = label_components(label, separator)
+        .and_then(|components| find_or_create_result_parent_dir(&components))
+        .and_then(|dir| {
+            let path = dir.join(ENVIRONMENT);
+            write_atomically(&dir, ENVIRONMENT, environment.to_lines().as_bytes()).map_err(|e| {
+                Error::new(format!(
+                    "Failed to write environment to {}, cause {e}",
+                    path.display()
+                ))
+            })
+        })
+    {
+        outln!(
+            "{} {e}",
+            wrap_high_insensity_red("Failed to write environment, cause:")
+        );
+    }
+}
+
+/// The environment persisted with the current sample, if any
+#[cfg(feature = "bench")]
+pub(crate) fn try_read_environment(
+    label: &'static str,
+    separator: Option<&'static str>,
+) -> Result<Option<crate::benching::Environment>> {
+    let Some(data) = try_read(label, separator, ENVIRONMENT)? else {
+        return Ok(None);
+    };
+    crate::benching::Environment::try_from_lines(&String::from_utf8_lossy(&data)).map(Some)
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn try_write_latency_cdf(
+    label: &'static str,
+    separator: Option<&'static str>,
+    cdf: &crate::benching::LatencyCdf,
+) {
+    if let Err(e) = try_write(
+        label,
+        separator,
+        &crate::output::ser::ser_latencies(cdf.latencies()),
+        CURRENT_LATENCY_CDF,
+        OLD_LATENCY_CDF,
+    ) {
+        outln!(
+            "{} {e}",
+            wrap_high_insensity_red("Failed to write latency distribution, cause:")
+        );
+    }
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn try_read_latency_cdf(
+    label: &'static str,
+    separator: Option<&'static str>,
+) -> Result<Option<crate::benching::LatencyCdf>> {
+    let Some(data) = try_read(label, separator, CURRENT_LATENCY_CDF)? else {
+        return Ok(None);
+    };
+    Ok(Some(crate::benching::LatencyCdf::new(
+        crate::output::ser::try_de_latencies(&data)?,
+    )))
+}
+
+/// The checked out git branch, `None` if git isn't available or the head is detached.
+/// Asked once per process
+#[cfg(feature = "bench")]
+pub(crate) fn git_branch() -> Option<&'static str> {
+    static BRANCH: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    BRANCH
+        .get_or_init(|| {
+            let output = std::process::Command::new("git")
+                .args(["rev-parse", "--abbrev-ref", "HEAD"])
+                .stderr(std::process::Stdio::null())
+                .output()
+                .ok()
+                .filter(|output| output.status.success())?;
+            let branch = String::from_utf8(output.stdout).ok()?.trim().to_string();
+            (!branch.is_empty() && branch != "HEAD").then_some(branch)
+        })
+        .as_deref()
+}
+
+/// How long ago the last sampling data of the label was persisted
+#[cfg(feature = "bench")]
+pub(crate) fn try_last_simpling_age(
+    label: &'static str,
+    separator: Option<&'static str>,
+) -> Result<Option<std::time::Duration>> {
+    let components = label_components(label, separator)?;
+    let path = find_or_create_result_parent_dir(&components)?.join(CURRENT_SAMPLE);
+    match std::fs::metadata(&path).and_then(|metadata| metadata.modified()) {
+        // A modification time in the future counts as just written
+        Ok(modified) => Ok(Some(modified.elapsed().unwrap_or_default())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::new(format!(
+            "Failed to read the age of {}, cause: {e}",
+            path.display()
+        ))),
+    }
+}
+
+/// Splits the label into the directories its results are stored under,
+/// a label is a single directory unless a separator is configured
+fn label_components<'a>(label: &'a str, separator: Option<&str>) -> Result<Vec<&'a str>> {
+    let components = match separator {
+        Some(separator) if !separator.is_empty() => label.split(separator).collect::<Vec<_>>(),
+        _ => vec![label],
+    };
+    for component in &components {
+        if component.contains(std::path::is_separator) {
+            return Err(Error::new(format!(
+                "Label {label} contains a path separator"
+            )));
+        }
+        if component.is_empty() || *component == "." || *component == ".." {
+            return Err(Error::new(format!(
+                "Label {label} contains an empty or relative path component"
+            )));
+        }
+    }
+    Ok(components)
+}
+
+fn find_or_create_result_parent_dir(components: &[&str]) -> Result<PathBuf> {
+    let result_parent_dir = components
+        .iter()
+        .fold(find_results_root()?, |dir, component| dir.join(component));
+
+    std::fs::create_dir_all(&result_parent_dir).map_err(|e| {
+        Error::new(format!(
+            "Failed to create output directory {}, cause {e}",
+            result_parent_dir.display()
+        ))
+    })?;
+    Ok(result_parent_dir)
+}
+
+/// The directory all results are persisted under, it may not exist yet
+fn find_results_root() -> Result<PathBuf> {
     let target = find_target()?;
     let pb = PathBuf::from(&target);
     let target_buf = std::fs::metadata(&pb).map_err(|e| {
         Error::new(format!(
-            "Failed to check metadata for target dir {target:?}, cause {e}"
+            "Failed to check metadata for target dir {}, cause {e}",
+            target.display()
         ))
     })?;
     if !target_buf.is_dir() {
         return Err(Error::new(format!(
-            "Expected target directory {pb:?} is not a directory"
+            "Expected target directory {} is not a directory",
+            pb.display()
         )));
     }
-    let all_results_dir = pb.join("simple-bench");
-
-    let result_parent_dir = all_results_dir.join(label);
-
-    std::fs::create_dir_all(&result_parent_dir).map_err(|e| {
-        Error::new(format!(
-            "Failed to create output directory {result_parent_dir:?}, cause {e}"
-        ))
-    })?;
-    Ok(result_parent_dir)
+    Ok(pb.join(RESULTS_DIR))
 }
 
 fn find_target() -> Result<PathBuf> {
@@ -159,7 +585,7 @@ fn find_target() -> Result<PathBuf> {
     while let Some(parent) = cur.parent() {
         let last = parent
             .components()
-            .last()
+            .next_back()
             .ok_or_else(|| Error::new("Could not find target directory to place output"))?;
         if last.as_os_str() == target_os_str {
             return Ok(parent.to_path_buf());
@@ -184,6 +610,7 @@ mod tests {
             max_nanos: 5,
             elapsed: 10,
             iterations: 15,
+            squared_deviations: 20.0,
         };
         try_write_results(label, rd1);
         assert_eq!(rd1, try_read_last_results(label).unwrap().unwrap());
@@ -192,11 +619,26 @@ mod tests {
             max_nanos: 105,
             elapsed: 110,
             iterations: 115,
+            squared_deviations: 120.0,
         };
         try_write_results(label, rd2);
         assert_eq!(rd2, try_read_last_results(label).unwrap().unwrap());
     }
 
+    #[test]
+    #[cfg(feature = "bench")]
+    fn can_dump_and_read_environment() {
+        let label = "environment_label";
+        let environment = crate::benching::environment();
+        try_write_environment(label, None, environment);
+        assert_eq!(
+            Some(environment),
+            try_read_environment(label, None).unwrap().as_ref()
+        );
+        let dir = find_or_create_result_parent_dir(&[label]).unwrap();
+        assert!(!dir.join(format!(".{ENVIRONMENT}.tmp")).exists());
+    }
+
     #[test]
     #[cfg(feature = "bench")]
     fn can_dump_and_read_samples() {
@@ -204,14 +646,167 @@ mod tests {
         let s1 = SamplingData {
             samples: vec![1, 2, 3, 4, 5],
             times: vec![6, 7, 8, 9, 10],
+            metrics: Vec::new(),
         };
-        try_write_last_simpling(label, &s1);
-        assert_eq!(s1, try_read_last_simpling(label).unwrap().unwrap());
+        try_write_last_simpling(label, None, &s1);
+        assert_eq!(
+            s1,
+            try_read_last_simpling(label, None, &BinaryCodec)
+                .unwrap()
+                .unwrap()
+        );
         let s2 = SamplingData {
             samples: vec![5, 4, 3, 2, 1],
             times: vec![10, 9, 8, 7, 6],
+            metrics: Vec::new(),
+        };
+        try_write_last_simpling(label, None, &s2);
+        assert_eq!(
+            s2,
+            try_read_last_simpling(label, None, &BinaryCodec)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn can_dump_and_read_nested_samples() {
+        let label = "nested::sample::label";
+        let s1 = SamplingData {
+            samples: vec![1, 2, 3],
+            times: vec![4, 5, 6],
+            metrics: Vec::new(),
+        };
+        try_write_last_simpling(label, Some("::"), &s1);
+        assert_eq!(
+            s1,
+            try_read_last_simpling(label, Some("::"), &BinaryCodec)
+                .unwrap()
+                .unwrap()
+        );
+        let dir = find_or_create_result_parent_dir(&["nested", "sample", "label"]).unwrap();
+        assert!(dir.join(CURRENT_SAMPLE).is_file());
+    }
+
+    #[test]
+    #[cfg(feature = "json-codec")]
+    fn can_dump_and_read_json_samples() {
+        use crate::output::codec::JsonCodec;
+        let label = "json_label";
+        let s1 = SamplingData {
+            samples: vec![1, 2],
+            times: vec![3, 4],
+            metrics: vec![5, 6],
+        };
+        write_last_simpling(label, None, &s1, &JsonCodec).unwrap();
+        assert_eq!(
+            s1,
+            try_read_last_simpling(label, None, &JsonCodec)
+                .unwrap()
+                .unwrap()
+        );
+        let dir = find_or_create_result_parent_dir(&[label]).unwrap();
+        assert!(std::fs::read_to_string(dir.join(CURRENT_SAMPLE))
+            .unwrap()
+            .starts_with("{\"iterations\":[1,2]"));
+        assert!(try_read_last_simpling(label, None, &BinaryCodec).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn replaces_current_sample_without_leftovers() {
+        let label = "atomic_label";
+        let s1 = SamplingData {
+            samples: vec![1],
+            times: vec![2],
+            metrics: Vec::new(),
+        };
+        let s2 = SamplingData {
+            samples: vec![3],
+            times: vec![4],
+            metrics: Vec::new(),
         };
-        try_write_last_simpling(label, &s2);
-        assert_eq!(s2, try_read_last_simpling(label).unwrap().unwrap());
+        try_write_last_simpling(label, None, &s1);
+        try_write_last_simpling(label, None, &s2);
+        let dir = find_or_create_result_parent_dir(&[label]).unwrap();
+        assert!(!dir.join(format!(".{CURRENT_SAMPLE}.tmp")).exists());
+        let old =
+            crate::output::ser::try_de_sampling_data(&std::fs::read(dir.join(OLD_SAMPLE)).unwrap());
+        assert_eq!(s1, old.unwrap());
+        assert_eq!(
+            s2,
+            try_read_last_simpling(label, None, &BinaryCodec)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn can_write_and_read_latency_cdf() {
+        let label = "latency_cdf_label";
+        let cdf = crate::benching::LatencyCdf::new(vec![30, 10, 20]);
+        try_write_latency_cdf(label, None, &cdf);
+        assert_eq!(Some(cdf), try_read_latency_cdf(label, None).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn can_write_and_read_calibration() {
+        let label = "calibration_label";
+        try_write_calibration(label, None, 1234.5, "ci-runner-3");
+        assert_eq!(
+            Some((1234.5, "ci-runner-3".to_string())),
+            try_read_calibration(label, None).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn appends_to_history() {
+        let label = "history_label";
+        let dir = find_or_create_result_parent_dir(&[label]).unwrap();
+        let _ = std::fs::remove_file(dir.join(HISTORY));
+        let data = SamplingData {
+            samples: vec![1, 2],
+            times: vec![10, 40],
+            metrics: Vec::new(),
+        };
+        try_write_last_simpling(label, None, &data);
+        try_write_last_simpling(label, None, &data);
+        assert_eq!(vec![15.0, 15.0], try_read_history(label, None).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "bench")]
+    fn lists_and_clears_persisted() {
+        let data = SamplingData {
+            samples: vec![1],
+            times: vec![2],
+            metrics: Vec::new(),
+        };
+        try_write_last_simpling("listed::label", Some("::"), &data);
+        assert!(list_persisted()
+            .unwrap()
+            .contains(&"listed/label".to_string()));
+        clear_persisted("listed/label").unwrap();
+        assert!(!list_persisted()
+            .unwrap()
+            .contains(&"listed/label".to_string()));
+        clear_persisted("listed/label").unwrap();
+        assert!(clear_persisted("../listed").is_err());
+    }
+
+    #[test]
+    fn splits_label_components() {
+        assert_eq!(
+            vec!["codec", "encode", "large"],
+            label_components("codec/encode/large", Some("/")).unwrap()
+        );
+        assert!(label_components("codec/encode", Some("::")).is_err());
+        assert!(label_components("codec/encode", None).is_err());
+        assert!(label_components("codec//encode", Some("/")).is_err());
+        assert!(label_components("codec/../encode", Some("/")).is_err());
     }
 }
@@ -0,0 +1,233 @@
+//! One JSON object per benchmark on its own line, with every statistic of the analysis and the
+//! raw samples, for dashboards and scripts:
+//!
+//! ```json
+//! {"label":"decode","total_iters":5050,"elapsed":101000,"mean":20.1,"median":20.0,
+//!  "min":18.5,"max":25.0,"variance":1.2,"stddev":1.1,"p25":19.5,"p75":20.5,"p95":23.0,
+//!  "p99":24.8,"outliers":{"low_severe":0,"low_mild":0,"high_mild":1,"high_severe":0,
+//!  "filtered":false},"samples":[1,2,..],"times":[20,41,..],
+//!  "change":{"min":-1.2,"mean":-2.5,"max":0.3,"p":0.01,"regressed":false,"improved":true}}
+//! ```
+//!
+//! Times are in nanoseconds, changes in percent. `change` is null unless there's a persisted run
+//! to compare with, and numbers that aren't finite are null.
+use crate::benching::{RunComparison, SamplingData};
+use crate::output::analysis::criterion::{BenchmarkConfig, SamplingDataSimpleAnalysis};
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::stream::outln;
+use crate::output::{compare_sampling, read_last_sampling, write_last_sampling, Output};
+#[cfg(feature = "timer")]
+use crate::timing::TimingData;
+use std::fmt::Write;
+
+/// Prints each result as a single line JSON object, see the module docs
+pub struct JsonStdout;
+
+impl Output for JsonStdout {
+    /// Only benchmarks print JSON, see `OutputFormat::Json`, timed iterators always print with
+    /// the stdout outputs
+    #[cfg(feature = "timer")]
+    fn dump_timing_data(&self, _label: &'static str, _data: TimingData) {
+        unreachable!("timed iterators don't print JSON");
+    }
+
+    fn dump_sampling_data(
+        &self,
+        label: &'static str,
+        sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
+        cfg: &BenchmarkConfig,
+        total_iters: u128,
+    ) -> Option<RunComparison> {
+        let mut comparison = None;
+        if cfg.dump_results_to_disk {
+            match read_last_sampling(label, cfg) {
+                Ok(Some((last, _normalization))) => {
+                    let old_analysis = analyze_sampling_data(&last, cfg);
+                    comparison =
+                        Some(compare_sampling(analysis, &old_analysis, cfg).to_run_comparison());
+                }
+                Err(e) => outln!("Failed to read last sample of {label}, cause {e}"),
+                Ok(None) => {}
+            }
+            write_last_sampling(label, cfg, sampling_data);
+        }
+        outln!(
+            "{}",
+            fmt_sampling(
+                label,
+                sampling_data,
+                analysis,
+                total_iters,
+                comparison.as_ref()
+            )
+        );
+        comparison
+    }
+}
+
+fn fmt_sampling(
+    label: &str,
+    sampling_data: &SamplingData,
+    analysis: &SamplingDataSimpleAnalysis,
+    total_iters: u128,
+    comparison: Option<&RunComparison>,
+) -> String {
+    let mut json = JsonObject::new();
+    json.string("label", label);
+    json.raw("total_iters", total_iters);
+    json.raw("elapsed", analysis.elapsed);
+    for (key, value) in [
+        ("mean", analysis.average),
+        ("median", analysis.median),
+        ("min", analysis.min),
+        ("max", analysis.max),
+        ("variance", analysis.variance),
+        ("stddev", analysis.stddev),
+        ("p25", analysis.p25),
+        ("p75", analysis.p75),
+        ("p95", analysis.p95),
+        ("p99", analysis.p99),
+    ] {
+        json.number(key, value);
+    }
+    let outliers = &analysis.outliers;
+    let mut outliers_json = JsonObject::new();
+    outliers_json.raw("low_severe", outliers.low_severe);
+    outliers_json.raw("low_mild", outliers.low_mild);
+    outliers_json.raw("high_mild", outliers.high_mild);
+    outliers_json.raw("high_severe", outliers.high_severe);
+    outliers_json.raw("filtered", outliers.filtered);
+    json.raw("outliers", outliers_json.finish());
+    json.raw("samples", fmt_array(&sampling_data.samples));
+    json.raw("times", fmt_array(&sampling_data.times));
+    match comparison {
+        Some(cmp) => {
+            let mut change = JsonObject::new();
+            change.number("min", cmp.min_change);
+            change.number("mean", cmp.mean_change);
+            change.number("max", cmp.max_change);
+            change.number("p", cmp.p);
+            change.raw("regressed", cmp.regressed);
+            change.raw("improved", cmp.improved);
+            json.raw("change", change.finish());
+        }
+        None => json.raw("change", "null"),
+    }
+    json.finish()
+}
+
+/// Writes the members of an object in the order they're added
+struct JsonObject(String);
+
+impl JsonObject {
+    fn new() -> Self {
+        JsonObject(String::from("{"))
+    }
+
+    fn key(&mut self, key: &str) {
+        if self.0.len() > 1 {
+            self.0.push(',');
+        }
+        self.0.push_str(&fmt_json_string(key));
+        self.0.push(':');
+    }
+
+    /// A value that's already valid JSON, like an integer, a boolean, or a nested object
+    fn raw(&mut self, key: &str, value: impl std::fmt::Display) {
+        self.key(key);
+        let _ = write!(self.0, "{value}");
+    }
+
+    fn number(&mut self, key: &str, value: f64) {
+        self.key(key);
+        self.0.push_str(&fmt_json_number(value));
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        self.key(key);
+        self.0.push_str(&fmt_json_string(value));
+    }
+
+    fn finish(mut self) -> String {
+        self.0.push('}');
+        self.0
+    }
+}
+
+fn fmt_array<T: ToString>(values: &[T]) -> String {
+    let values = values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("[{values}]")
+}
+
+pub(crate) fn fmt_json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ch if u32::from(ch) < 32 => {
+                let _ = write!(escaped, "\\u{:04x}", u32::from(ch));
+            }
+            ch => escaped.push(ch),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// JSON has no representation of NaN or infinity
+pub(crate) fn fmt_json_number(num: f64) -> String {
+    if num.is_finite() {
+        num.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::analysis::sample_data::simple_analyze_sampling_data;
+
+    #[test]
+    fn formats_sampling() {
+        let sampling_data = SamplingData {
+            samples: vec![1, 2],
+            times: vec![10, 30],
+            metrics: Vec::new(),
+        };
+        let analysis = simple_analyze_sampling_data(&sampling_data);
+        let comparison = RunComparison {
+            min_change: -1.0,
+            mean_change: f64::NAN,
+            max_change: 2.5,
+            p: 0.5,
+            t: 0.0,
+            t_quantiles: None,
+            regressed: false,
+            improved: false,
+            metric_change: None,
+            metric_p: None,
+        };
+        assert_eq!(
+            "{\"label\":\"a \\\"b\\\"\",\"total_iters\":3,\"elapsed\":40,\"mean\":12.5,\"median\":15,\"min\":10,\"max\":15,\"variance\":12.5,\"stddev\":3.5355339059327378,\"p25\":11.25,\"p75\":13.75,\"p95\":14.75,\"p99\":14.95,\"outliers\":{\"low_severe\":0,\"low_mild\":0,\"high_mild\":0,\"high_severe\":0,\"filtered\":false},\"samples\":[1,2],\"times\":[10,30],\"change\":{\"min\":-1,\"mean\":null,\"max\":2.5,\"p\":0.5,\"regressed\":false,\"improved\":false}}",
+            fmt_sampling("a \"b\"", &sampling_data, &analysis, 3, Some(&comparison))
+        );
+        assert!(fmt_sampling("a", &sampling_data, &analysis, 3, None).ends_with("\"change\":null}"));
+    }
+
+    #[test]
+    fn escapes_json() {
+        assert_eq!("\"a\\\\b\\u0001\"", fmt_json_string("a\\b\u{1}"));
+        assert_eq!("null", fmt_json_number(f64::NAN));
+    }
+}
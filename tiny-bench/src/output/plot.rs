@@ -0,0 +1,93 @@
+//! Line plots drawn with [braille](https://en.wikipedia.org/wiki/Braille_Patterns) characters,
+//! each character is a grid of 2 by 4 dots, which gives four times the vertical resolution of a
+//! character per row
+use crate::output::fmt_time;
+
+/// Rows of characters in a plot, 16 dots high
+pub(crate) const PLOT_ROWS: usize = 4;
+
+/// The dot bit of each column and row within a braille character, the top row is 0
+const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// Plots the values in order, at most `width` characters wide and `rows` characters high.
+/// Values are averaged into two columns per character if there are more than fit, the first row
+/// is labeled with the maximum and the last with the minimum of what's plotted
+pub(crate) fn fmt_braille_plot(values: &[f64], width: usize, rows: usize) -> String {
+    let columns = values.len().min(width * 2);
+    if columns == 0 || rows == 0 {
+        return String::new();
+    }
+    let points = (0..columns)
+        .map(|column| {
+            let bucket =
+                &values[column * values.len() / columns..(column + 1) * values.len() / columns];
+            bucket.iter().sum::<f64>() / bucket.len() as f64
+        })
+        .collect::<Vec<_>>();
+    let min = points.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = points.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let height = rows * 4;
+    // Dot row of each point, counted from the top
+    let dot_rows = points
+        .iter()
+        .map(|point| {
+            let above_min = if max > min {
+                ((point - min) / (max - min) * (height - 1) as f64).round() as usize
+            } else {
+                height / 2
+            };
+            height - 1 - above_min
+        })
+        .collect::<Vec<_>>();
+    let mut grid = vec![vec![0u32; columns.div_ceil(2)]; rows];
+    for (column, &dot_row) in dot_rows.iter().enumerate() {
+        // Connect to the previous point so that jumps read as a line rather than scattered dots
+        let previous = if column > 0 {
+            dot_rows[column - 1]
+        } else {
+            dot_row
+        };
+        for row in previous.min(dot_row)..=previous.max(dot_row) {
+            grid[row / 4][column / 2] |= DOTS[column % 2][row % 4];
+        }
+    }
+    let mut out = String::new();
+    for (index, row) in grid.iter().enumerate() {
+        out.extend(
+            row.iter()
+                .map(|dots| char::from_u32(0x2800 + dots).unwrap_or(' ')),
+        );
+        if index == 0 {
+            out.push(' ');
+            out.push_str(&fmt_time(max));
+        } else if index == rows - 1 {
+            out.push(' ');
+            out.push_str(&fmt_time(min));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fmt_braille_plot;
+
+    #[test]
+    fn plots_rising_line() {
+        assert_eq!(
+            "⠀⠀⣠⠞ 7.00ns\n⣠⠞⠁⠀ 0.00ns\n",
+            fmt_braille_plot(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0], 4, 2)
+        );
+    }
+
+    #[test]
+    fn averages_into_columns_and_centers_constants() {
+        let plot = fmt_braille_plot(&[5.0; 100], 10, 2);
+        assert_eq!(2, plot.lines().count());
+        assert!(plot
+            .lines()
+            .all(|line| line.chars().take_while(|c| *c != ' ').count() == 10));
+        assert!(fmt_braille_plot(&[], 10, 2).is_empty());
+    }
+}
@@ -4,22 +4,96 @@ use crate::error::{Error, Result};
 #[cfg(feature = "timer")]
 use crate::timing::TimingData;
 
+/// Bumped whenever the persisted layout changes, written as the first byte of every file.
+/// Version 1 added the leading version byte and the trailing CRC32.
+const FORMAT_VERSION: u8 = 1;
+/// Version byte + trailing CRC32
+const ENVELOPE_LEN: usize = 1 + 4;
+
+/// [CRC32](https://en.wikipedia.org/wiki/Cyclic_redundancy_check) with the IEEE polynomial,
+/// same as zlib and png
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+fn crc32(buf: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in buf {
+        crc = CRC32_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// Prefixes the payload with the format version and appends a checksum over both
+fn seal(mut v: Vec<u8>) -> Vec<u8> {
+    v.insert(0, FORMAT_VERSION);
+    let checksum = crc32(&v);
+    v.extend_from_slice(&checksum.to_le_bytes());
+    v
+}
+
+/// Checks version and checksum, returning the payload between them
+fn try_unseal(buf: &[u8]) -> Result<&[u8]> {
+    let buf_len = buf.len();
+    if buf_len < ENVELOPE_LEN {
+        return Err(Error::new(format!(
+            "Found malformed serialized data, length too short {buf_len}"
+        )));
+    }
+    if buf[0] != FORMAT_VERSION {
+        return Err(Error::new(format!(
+            "Found serialized data with unsupported format version {}, expected {FORMAT_VERSION}",
+            buf[0]
+        )));
+    }
+    let (content, checksum) = buf.split_at(buf_len - 4);
+    // Split at len - 4, always 4 bytes
+    let expected = u32::from_le_bytes(checksum.try_into().unwrap());
+    let actual = crc32(content);
+    if expected != actual {
+        return Err(Error::new(format!(
+            "Found corrupted serialized data, checksum mismatch. Expected {expected:#010x} found {actual:#010x}"
+        )));
+    }
+    Ok(&content[1..])
+}
+
 /// We'll just turn it into bytes for now, nano-format is a pain to eyeball anyways
 #[cfg(feature = "timer")]
 pub(crate) fn ser_timing_data(run_data: TimingData) -> Vec<u8> {
-    let mut v = Vec::with_capacity(16 * 4);
+    let mut v = Vec::with_capacity(16 * 4 + 8);
     v.extend_from_slice(&run_data.min_nanos.to_le_bytes());
     v.extend_from_slice(&run_data.max_nanos.to_le_bytes());
     v.extend_from_slice(&run_data.elapsed.to_le_bytes());
     v.extend_from_slice(&run_data.iterations.to_le_bytes());
-    v
+    v.extend_from_slice(&run_data.squared_deviations.to_le_bytes());
+    seal(v)
 }
 
 #[cfg(feature = "timer")]
 pub(crate) fn try_de_timing_data(buf: &[u8]) -> Result<TimingData> {
-    if buf.len() != 64 {
+    let buf = try_unseal(buf)?;
+    if buf.len() != 72 {
         return Err(Error::new(format!(
-            "Unexpected buffer len for serialized timing data, expected 64 but got {}",
+            "Unexpected buffer len for serialized timing data, expected 72 but got {}",
             buf.len()
         )));
     }
@@ -28,11 +102,13 @@ pub(crate) fn try_de_timing_data(buf: &[u8]) -> Result<TimingData> {
     let max_nanos = u128::from_le_bytes(buf[16..32].try_into().ok().unwrap());
     let elapsed = u128::from_le_bytes(buf[32..48].try_into().ok().unwrap());
     let iterations = u128::from_le_bytes(buf[48..64].try_into().ok().unwrap());
+    let squared_deviations = f64::from_le_bytes(buf[64..72].try_into().ok().unwrap());
     Ok(TimingData {
         min_nanos,
         max_nanos,
         elapsed,
         iterations,
+        squared_deviations,
     })
 }
 
@@ -47,11 +123,17 @@ pub(crate) fn ser_sampling_data(sampling_data: &SamplingData) -> Vec<u8> {
     for time in &sampling_data.times {
         v.extend_from_slice(&time.to_le_bytes());
     }
-    v
+    // Whether a secondary metric per sample follows
+    v.push(u8::from(!sampling_data.metrics.is_empty()));
+    for metric in &sampling_data.metrics {
+        v.extend_from_slice(&metric.to_le_bytes());
+    }
+    seal(v)
 }
 
 #[cfg(feature = "bench")]
 pub(crate) fn try_de_sampling_data(buf: &[u8]) -> Result<SamplingData> {
+    let buf = try_unseal(buf)?;
     let buf_len = buf.len();
     if buf_len < 8 {
         return Err(Error::new(format!(
@@ -62,7 +144,24 @@ pub(crate) fn try_de_sampling_data(buf: &[u8]) -> Result<SamplingData> {
     let len = u64::from_le_bytes(buf[..8].try_into().unwrap());
     let mut samples = Vec::with_capacity(len as usize);
     let mut times = Vec::with_capacity(len as usize);
-    let expected_total_len = 8 + len * 16 + len * 8;
+    let times_end = 8 + len * 16 + len * 8;
+    // Runs persisted before the metrics flag end right after the times
+    let has_metrics = match buf.get(times_end as usize) {
+        None | Some(0) => false,
+        Some(1) => true,
+        Some(flag) => {
+            return Err(Error::new(format!(
+                "Found malformed serialized data, unexpected metrics flag {flag}"
+            )))
+        }
+    };
+    let expected_total_len = if buf_len as u64 == times_end {
+        times_end
+    } else if has_metrics {
+        times_end + 1 + len * 8
+    } else {
+        times_end + 1
+    };
     if buf_len as u64 != expected_total_len {
         return Err(Error::new(format!("Found malformed serialized data, unexpected length. Expected {expected_total_len} found {buf_len}")));
     }
@@ -82,12 +181,299 @@ pub(crate) fn try_de_sampling_data(buf: &[u8]) -> Result<SamplingData> {
                 .unwrap(),
         ));
     }
-    Ok(SamplingData { samples, times })
+    let metrics = if has_metrics {
+        // Length checked, every chunk is 8 bytes
+        buf[times_end as usize + 1..]
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(SamplingData {
+        samples,
+        times,
+        metrics,
+    })
+}
+
+/// Identifies a session file, before anything else in the payload
+#[cfg(feature = "bench")]
+const SESSION_MAGIC: &[u8] = b"tiny-bench session";
+
+/// The results of a session with the machine it ran on and when it was exported, every result's
+/// label, statistics and comparison in order. Strings and the per sample averages are prefixed by
+/// their length and options by whether they're there
+#[cfg(feature = "bench")]
+pub(crate) fn ser_session(session: &crate::benching::Session) -> Vec<u8> {
+    let mut v = SESSION_MAGIC.to_vec();
+    v.extend_from_slice(&session.exported_at_secs.to_le_bytes());
+    put_str(&mut v, &session.machine);
+    v.extend_from_slice(&(session.results.len() as u64).to_le_bytes());
+    for result in &session.results {
+        let stats = &result.stats;
+        put_str(&mut v, result.label);
+        v.extend_from_slice(&stats.total_iters.to_le_bytes());
+        v.extend_from_slice(&stats.elapsed.to_le_bytes());
+        for value in [
+            stats.mean,
+            stats.median,
+            stats.min,
+            stats.max,
+            stats.variance,
+            stats.stddev,
+        ] {
+            v.extend_from_slice(&value.to_le_bytes());
+        }
+        v.extend_from_slice(&(stats.per_sample_average.len() as u64).to_le_bytes());
+        for value in &stats.per_sample_average {
+            v.extend_from_slice(&value.to_le_bytes());
+        }
+        v.extend_from_slice(&stats.warmup_iterations.to_le_bytes());
+        v.extend_from_slice(&stats.warmup_elapsed.as_nanos().to_le_bytes());
+        v.extend_from_slice(&stats.estimated_mean_ns.to_le_bytes());
+        put_opt_f64(&mut v, stats.mean_confidence_interval.map(|(low, _)| low));
+        put_opt_f64(&mut v, stats.mean_confidence_interval.map(|(_, high)| high));
+        v.extend_from_slice(&stats.throughput_per_sec.to_le_bytes());
+        put_opt_f64(&mut v, stats.metric_per_iteration);
+        put_opt_f64(
+            &mut v,
+            stats
+                .cold_start
+                .map(|cold_start| cold_start.as_nanos() as f64),
+        );
+        match &result.comparison {
+            Some(cmp) => {
+                v.push(1);
+                for value in [
+                    cmp.min_change,
+                    cmp.mean_change,
+                    cmp.max_change,
+                    cmp.p,
+                    cmp.t,
+                ] {
+                    v.extend_from_slice(&value.to_le_bytes());
+                }
+                put_opt_f64(&mut v, cmp.t_quantiles.map(|q| q.lower));
+                put_opt_f64(&mut v, cmp.t_quantiles.map(|q| q.median));
+                put_opt_f64(&mut v, cmp.t_quantiles.map(|q| q.upper));
+                v.push(u8::from(cmp.regressed));
+                v.push(u8::from(cmp.improved));
+                put_opt_f64(&mut v, cmp.metric_change);
+                put_opt_f64(&mut v, cmp.metric_p);
+            }
+            None => v.push(0),
+        }
+    }
+    seal(v)
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn try_de_session(buf: &[u8]) -> Result<crate::benching::Session> {
+    use crate::benching::{BenchResult, BenchStats, RunComparison, Session, TQuantiles};
+    let mut r = Reader(try_unseal(buf)?);
+    if r.take(SESSION_MAGIC.len())? != SESSION_MAGIC {
+        return Err(Error::new("Not a tiny-bench session file"));
+    }
+    let exported_at_secs = r.u64()?;
+    let machine = r.string()?;
+    let len = r.u64()?;
+    let mut results = Vec::new();
+    for _ in 0..len {
+        // Labels are static everywhere, leaking the labels of an imported session is fine
+        let label: &'static str = Box::leak(r.string()?.into_boxed_str());
+        let total_iters = r.u128()?;
+        let elapsed = r.u128()?;
+        let (mean, median, min, max) = (r.f64()?, r.f64()?, r.f64()?, r.f64()?);
+        let (variance, stddev) = (r.f64()?, r.f64()?);
+        let per_sample_average = (0..r.u64()?).map(|_| r.f64()).collect::<Result<Vec<_>>>()?;
+        let warmup_iterations = r.u128()?;
+        let warmup_elapsed = crate::benching::nanos_to_duration(r.u128()? as f64);
+        let estimated_mean_ns = r.f64()?;
+        let mean_confidence_interval = r.opt_f64()?.zip(r.opt_f64()?);
+        let throughput_per_sec = r.f64()?;
+        let metric_per_iteration = r.opt_f64()?;
+        let cold_start = r.opt_f64()?.map(crate::benching::nanos_to_duration);
+        let comparison = if r.u8()? == 1 {
+            Some(RunComparison {
+                min_change: r.f64()?,
+                mean_change: r.f64()?,
+                max_change: r.f64()?,
+                p: r.f64()?,
+                t: r.f64()?,
+                t_quantiles: match (r.opt_f64()?, r.opt_f64()?, r.opt_f64()?) {
+                    (Some(lower), Some(median), Some(upper)) => Some(TQuantiles {
+                        lower,
+                        median,
+                        upper,
+                    }),
+                    _ => None,
+                },
+                regressed: r.u8()? == 1,
+                improved: r.u8()? == 1,
+                metric_change: r.opt_f64()?,
+                metric_p: r.opt_f64()?,
+            })
+        } else {
+            None
+        };
+        results.push(BenchResult {
+            label,
+            stats: BenchStats {
+                total_iters,
+                elapsed,
+                mean,
+                median,
+                min,
+                max,
+                variance,
+                stddev,
+                per_sample_average,
+                warmup_iterations,
+                warmup_elapsed,
+                estimated_mean_ns,
+                mean_confidence_interval,
+                throughput_per_sec,
+                metric_per_iteration,
+                cold_start,
+            },
+            comparison,
+        });
+    }
+    if !r.0.is_empty() {
+        return Err(Error::new(format!(
+            "Found malformed session, {} unexpected trailing bytes",
+            r.0.len()
+        )));
+    }
+    Ok(Session {
+        machine,
+        exported_at_secs,
+        results,
+    })
+}
+
+#[cfg(feature = "bench")]
+fn put_str(v: &mut Vec<u8>, s: &str) {
+    v.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    v.extend_from_slice(s.as_bytes());
+}
+
+#[cfg(feature = "bench")]
+fn put_opt_f64(v: &mut Vec<u8>, value: Option<f64>) {
+    match value {
+        Some(value) => {
+            v.push(1);
+            v.extend_from_slice(&value.to_le_bytes());
+        }
+        None => v.push(0),
+    }
+}
+
+/// Reads values in order from a payload, erroring instead of panicking when it's too short
+#[cfg(feature = "bench")]
+struct Reader<'a>(&'a [u8]);
+
+#[cfg(feature = "bench")]
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(Error::new(format!(
+                "Found malformed serialized data, expected {len} more bytes but found {}",
+                self.0.len()
+            )));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    // Took exactly as many bytes as converted, the conversions can't fail
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn u128(&mut self) -> Result<u128> {
+        Ok(u128::from_le_bytes(self.take(16)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn opt_f64(&mut self) -> Result<Option<f64>> {
+        Ok(if self.u8()? == 1 {
+            Some(self.f64()?)
+        } else {
+            None
+        })
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u64()?;
+        let bytes = self.take(usize::try_from(len).unwrap_or(usize::MAX))?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|e| Error::new(format!("Found malformed string, cause {e}")))
+    }
+}
+
+/// Sorted latencies, the count followed by each one
+#[cfg(feature = "bench")]
+pub(crate) fn ser_latencies(latencies: &[u64]) -> Vec<u8> {
+    let mut v = Vec::with_capacity(8 + latencies.len() * 8);
+    v.extend_from_slice(&(latencies.len() as u64).to_le_bytes());
+    for latency in latencies {
+        v.extend_from_slice(&latency.to_le_bytes());
+    }
+    seal(v)
+}
+
+#[cfg(feature = "bench")]
+pub(crate) fn try_de_latencies(buf: &[u8]) -> Result<Vec<u64>> {
+    let buf = try_unseal(buf)?;
+    let buf_len = buf.len();
+    if buf_len < 8 {
+        return Err(Error::new(format!(
+            "Found malformed serialized data, length too short {buf_len}"
+        )));
+    }
+    let len = u64::from_le_bytes(buf[..8].try_into().unwrap());
+    let expected_total_len = 8 + len * 8;
+    if buf_len as u64 != expected_total_len {
+        return Err(Error::new(format!("Found malformed serialized data, unexpected length. Expected {expected_total_len} found {buf_len}")));
+    }
+    // Length checked, every chunk is 8 bytes
+    Ok(buf[8..]
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
 
+    #[test]
+    fn crc32_matches_reference() {
+        assert_eq!(0xCBF4_3926, super::crc32(b"123456789"));
+        assert_eq!(0, super::crc32(b""));
+    }
+
+    #[test]
+    fn rejects_corrupted_data() {
+        let mut sealed = super::seal(vec![1, 2, 3, 4]);
+        assert_eq!(&[1, 2, 3, 4], super::try_unseal(&sealed).unwrap());
+        sealed[2] ^= 0b0001_0000;
+        assert!(super::try_unseal(&sealed).is_err());
+        let mut wrong_version = super::seal(vec![1, 2, 3, 4]);
+        wrong_version[0] = super::FORMAT_VERSION + 1;
+        assert!(super::try_unseal(&wrong_version).is_err());
+        assert!(super::try_unseal(&[super::FORMAT_VERSION, 0, 0]).is_err());
+    }
+
     #[test]
     #[cfg(feature = "timer")]
     fn can_ser_de_timing() {
@@ -95,11 +481,13 @@ mod tests {
         let max_nanos = u128::MAX;
         let elapsed = 555_555;
         let iterations = 99_959_599_959;
+        let squared_deviations = 1234.5;
         let rd = super::TimingData {
             min_nanos,
             max_nanos,
             elapsed,
             iterations,
+            squared_deviations,
         };
         assert_eq!(
             rd,
@@ -107,16 +495,96 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "bench")]
+    fn can_ser_de_session() {
+        use crate::benching::{BenchResult, BenchStats, RunComparison, Session};
+        let stats = BenchStats {
+            total_iters: 100,
+            elapsed: 2000,
+            mean: 20.0,
+            median: 19.5,
+            min: 18.0,
+            max: 25.0,
+            variance: 4.0,
+            stddev: 2.0,
+            per_sample_average: vec![18.0, 25.0, 19.5],
+            warmup_iterations: 50,
+            warmup_elapsed: std::time::Duration::from_nanos(1234),
+            estimated_mean_ns: 20.5,
+            mean_confidence_interval: Some((19.0, 21.0)),
+            throughput_per_sec: 5e7,
+            metric_per_iteration: None,
+            cold_start: Some(std::time::Duration::from_micros(3)),
+        };
+        let session = Session {
+            machine: "ci-runner".to_string(),
+            exported_at_secs: 1_700_000_000,
+            results: vec![
+                BenchResult {
+                    label: "decode",
+                    stats: stats.clone(),
+                    comparison: Some(RunComparison {
+                        min_change: -1.0,
+                        mean_change: -2.0,
+                        max_change: 3.0,
+                        p: 0.04,
+                        t: -2.1,
+                        t_quantiles: Some(crate::benching::TQuantiles {
+                            lower: -1.9,
+                            median: 0.0,
+                            upper: 2.0,
+                        }),
+                        regressed: false,
+                        improved: true,
+                        metric_change: Some(1.5),
+                        metric_p: Some(0.2),
+                    }),
+                },
+                BenchResult {
+                    label: "encode",
+                    stats,
+                    comparison: None,
+                },
+            ],
+        };
+        let bytes = super::ser_session(&session);
+        let read = super::try_de_session(&bytes).unwrap();
+        assert_eq!(format!("{session:?}"), format!("{read:?}"));
+        assert!(super::try_de_session(&super::ser_latencies(&[1, 2])).is_err());
+    }
+
     #[test]
     #[cfg(feature = "bench")]
     fn can_ser_de_sampling() {
         let sampling = super::SamplingData {
             samples: vec![5, 6, 7, 8, 9, 10],
             times: vec![15, 16, 17, 18, 19, 20],
+            metrics: Vec::new(),
         };
         assert_eq!(
             sampling,
             super::try_de_sampling_data(&super::ser_sampling_data(&sampling)).unwrap()
         );
+        // Persisted before the metrics flag
+        let mut legacy = 6u64.to_le_bytes().to_vec();
+        for sample in &sampling.samples {
+            legacy.extend_from_slice(&sample.to_le_bytes());
+        }
+        for time in &sampling.times {
+            legacy.extend_from_slice(&time.to_le_bytes());
+        }
+        assert_eq!(
+            sampling,
+            super::try_de_sampling_data(&super::seal(legacy)).unwrap()
+        );
+        let with_metrics = super::SamplingData {
+            metrics: vec![25, 26, 27, 28, 29, 30],
+            ..sampling
+        };
+        assert_eq!(
+            with_metrics,
+            super::try_de_sampling_data(&super::ser_sampling_data(&with_metrics)).unwrap()
+        );
     }
 }
@@ -0,0 +1,113 @@
+//! Where printed output goes, chosen per benchmark with `BenchmarkConfig::output_stream` and
+//! written through [`outln`] instead of `println`
+#[cfg(feature = "bench")]
+use crate::output::analysis::criterion::OutputStream;
+#[cfg(feature = "bench")]
+use std::cell::RefCell;
+use std::fmt::Arguments;
+
+#[cfg(feature = "bench")]
+thread_local! {
+    /// The stream of the benchmark running on this thread, stdout outside of any benchmark
+    static CURRENT: RefCell<OutputStream> = const { RefCell::new(OutputStream::Stdout) };
+}
+
+/// Prints to the stream of the running benchmark, like `print`
+#[cfg(feature = "bench")]
+macro_rules! out {
+    ($($arg:tt)*) => {
+        $crate::output::stream::write_output(format_args!($($arg)*))
+    };
+}
+
+/// Prints a line to the stream of the running benchmark, like `println`
+macro_rules! outln {
+    () => {
+        $crate::output::stream::write_output(format_args!("\n"))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::stream::write_output(format_args!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+#[cfg(feature = "bench")]
+pub(crate) use out;
+pub(crate) use outln;
+
+/// Writes to the stream of the running benchmark. Stdout and stderr go through the standard
+/// macros so that the test harness still captures them, failing to write to a custom writer is
+/// ignored like a closed pipe would be
+pub(crate) fn write_output(args: Arguments<'_>) {
+    #[cfg(feature = "bench")]
+    CURRENT.with_borrow(|stream| match stream {
+        OutputStream::Stdout => print!("{args}"),
+        OutputStream::Stderr => eprint!("{args}"),
+        OutputStream::Writer(writer) => {
+            let mut writer = writer
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            if writer.write_fmt(args).is_ok() {
+                let _ = writer.flush();
+            }
+        }
+    });
+    #[cfg(not(feature = "bench"))]
+    print!("{args}");
+}
+
+/// Whether the stream of the running benchmark is a terminal, custom writers never are
+#[cfg(feature = "bench")]
+pub(crate) fn is_terminal() -> bool {
+    use std::io::IsTerminal;
+    CURRENT.with_borrow(|stream| match stream {
+        OutputStream::Stdout => std::io::stdout().is_terminal(),
+        OutputStream::Stderr => std::io::stderr().is_terminal(),
+        OutputStream::Writer(_) => false,
+    })
+}
+
+/// Routes output on this thread to the stream until the guard is dropped, then back to where it
+/// went before, so that benchmarks calling each other restore their caller's stream
+#[cfg(feature = "bench")]
+pub(crate) fn redirect(stream: &OutputStream) -> Redirect {
+    Redirect(Some(CURRENT.replace(stream.clone())))
+}
+
+#[cfg(feature = "bench")]
+pub(crate) struct Redirect(Option<OutputStream>);
+
+#[cfg(feature = "bench")]
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            CURRENT.set(previous);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "bench")]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn writes_to_redirected_stream() {
+        let buf = Arc::new(Mutex::new(Vec::new()));
+        {
+            let _outer = redirect(&OutputStream::Writer(buf.clone()));
+            outln!("outer {}", 1);
+            {
+                let _inner = redirect(&OutputStream::Stderr);
+                outln!("inner");
+            }
+            out!("back");
+            outln!();
+            assert!(!is_terminal());
+        }
+        assert_eq!(
+            "outer 1\nback\n",
+            String::from_utf8(buf.lock().unwrap().clone()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use crate::benching::{RunComparison, SamplingData};
+use crate::output::analysis::criterion::{BenchmarkConfig, SamplingDataSimpleAnalysis};
+use crate::output::analysis::sample_data::analyze_sampling_data;
+use crate::output::stream::outln;
+use crate::output::{
+    compare_sampling, fmt_change, fmt_time, read_last_sampling, write_last_sampling, Output,
+};
+#[cfg(feature = "timer")]
+use crate::timing::TimingData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// TAP test numbers have to be unique and increasing over the whole process
+static TEST_NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+/// Prints results as [TAP](https://testanything.org/tap-version-13-specification.html) so that CI
+/// systems can show benchmarks next to unit tests. Each benchmark is a test which passes unless
+/// it's significantly worse than the last persisted run.
+/// Other output is left as is, TAP consumers ignore lines they don't understand.
+pub struct TapStdout;
+
+impl Output for TapStdout {
+    #[cfg(feature = "timer")]
+    fn dump_timing_data(&self, label: &'static str, data: TimingData) {
+        outln!("# mean {}", fmt_time(data.mean()));
+        outln!("{}", fmt_tap_line(next_test_number(), label, None));
+    }
+
+    fn dump_sampling_data(
+        &self,
+        label: &'static str,
+        sampling_data: &SamplingData,
+        analysis: &SamplingDataSimpleAnalysis,
+        cfg: &BenchmarkConfig,
+        _total_iters: u128,
+    ) -> Option<RunComparison> {
+        outln!(
+            "# mean {} median {} stddev {}",
+            fmt_time(analysis.average),
+            fmt_time(analysis.median),
+            fmt_time(analysis.stddev)
+        );
+        let mut regression = None;
+        let mut comparison = None;
+        if cfg.dump_results_to_disk {
+            match read_last_sampling(label, cfg) {
+                Ok(Some((last, normalization))) => {
+                    let old_analysis = analyze_sampling_data(&last, cfg);
+                    let cmp = compare_sampling(analysis, &old_analysis, cfg);
+                    if let Some(normalization) = &normalization {
+                        outln!(
+                            "# normalized with a run from {} by the calibration score ratio {:.2}, comparisons across machines are approximate",
+                            normalization.machine, normalization.ratio
+                        );
+                    }
+                    outln!(
+                        "# change {} (p = {:.2})",
+                        fmt_change(cmp.mean.mean, cfg.change_decimals),
+                        cmp.p
+                    );
+                    comparison = Some(cmp.to_run_comparison());
+                    if cmp.is_regression() {
+                        regression = Some(format!(
+                            "regressed by {} (p = {:.2})",
+                            fmt_change(cmp.mean.mean, cfg.change_decimals),
+                            cmp.p
+                        ));
+                    }
+                }
+                Err(e) => outln!("# Failed to read last sample, cause {e}"),
+                Ok(None) => {}
+            }
+            write_last_sampling(label, cfg, sampling_data);
+        }
+        outln!(
+            "{}",
+            fmt_tap_line(next_test_number(), label, regression.as_deref())
+        );
+        comparison
+    }
+}
+
+fn next_test_number() -> usize {
+    TEST_NUMBER.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+fn fmt_tap_line(number: usize, label: &str, regression: Option<&str>) -> String {
+    // '#' starts a directive in TAP, keep it out of the description
+    let description = label.replace('#', "_");
+    match regression {
+        Some(reason) => {
+            format!("not ok {number} - {description}\n  ---\n  message: {reason}\n  ...")
+        }
+        None => format!("ok {number} - {description}"),
+    }
+}
+
+/// Prints the TAP plan, `1..N` where N is the number of benchmarks reported so far.
+/// Call it once after all benchmarks have run, TAP allows the plan to come last.
+pub fn print_tap_plan() {
+    outln!("1..{}", TEST_NUMBER.load(Ordering::Relaxed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fmt_tap_line;
+
+    #[test]
+    fn formats_tap_line() {
+        assert_eq!("ok 1 - my bench", fmt_tap_line(1, "my bench", None));
+        assert_eq!("ok 2 - my _1 bench", fmt_tap_line(2, "my #1 bench", None));
+        assert_eq!(
+            "not ok 3 - slow\n  ---\n  message: regressed\n  ...",
+            fmt_tap_line(3, "slow", Some("regressed"))
+        );
+    }
+}
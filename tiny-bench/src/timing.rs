@@ -1,6 +1,7 @@
 use crate::output;
 use crate::output::{
     fallback_to_anonymous_on_invalid_label, ComparedStdout, LabeledOutput, Output, SimpleStdout,
+    SlowStdout,
 };
 use std::time::{Duration, Instant};
 
@@ -17,6 +18,23 @@ pub fn run_timed<T, F: FnMut() -> T>(mut closure: F) -> Duration {
     Instant::now().duration_since(start)
 }
 
+/// Runs some `FnOnce` closure and returns both its result and the time it took
+/// ```
+/// use std::time::Duration;
+/// use tiny_bench::run_timed_value;
+/// let (value, time) = run_timed_value(|| {
+///     std::thread::sleep(Duration::from_micros(5));
+///     5
+/// });
+/// assert_eq!(5, value);
+/// assert!(time.as_micros() >= 5);
+/// ```
+pub fn run_timed_value<T, F: FnOnce() -> T>(closure: F) -> (T, Duration) {
+    let start = Instant::now();
+    let value = closure();
+    (value, Instant::now().duration_since(start))
+}
+
 /// Runs some closure `n` times and returns the data gathered
 /// ```
 /// use std::time::Duration;
@@ -25,27 +43,75 @@ pub fn run_timed<T, F: FnMut() -> T>(mut closure: F) -> Duration {
 /// data.pretty_print();
 /// ```
 pub fn run_timed_times<T, F: FnMut() -> T>(iterations: usize, mut closure: F) -> TimingData {
-    let mut elapsed = Duration::ZERO;
-    let mut min_nanos = u128::MAX;
-    let mut max_nanos = 0;
+    let mut acc = Accumulator::new();
     for _ in 0..iterations {
         let start = Instant::now();
         closure();
-        let run_elapsed = Instant::now().duration_since(start);
-        let run_elapsed_nanos = run_elapsed.as_nanos();
-        if run_elapsed_nanos < min_nanos {
-            min_nanos = run_elapsed_nanos;
-        }
-        if run_elapsed_nanos > max_nanos {
-            max_nanos = run_elapsed_nanos;
-        }
-        elapsed += run_elapsed;
+        acc.push(Instant::now().duration_since(start));
     }
-    TimingData {
-        iterations: iterations as u128,
-        min_nanos,
-        max_nanos,
-        elapsed: elapsed.as_nanos(),
+    acc.finish()
+}
+
+/// Runs some closure `n` times and returns the duration of every run, in order, for analyzing
+/// them yourself or reporting them with `report_durations`
+/// ```
+/// use std::time::Duration;
+/// use tiny_bench::run_timed_times_collect;
+/// let durations = run_timed_times_collect(100, || std::thread::sleep(Duration::from_micros(1)));
+/// assert_eq!(100, durations.len());
+/// ```
+pub fn run_timed_times_collect<T, F: FnMut() -> T>(
+    iterations: usize,
+    mut closure: F,
+) -> Vec<Duration> {
+    let mut durations = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        closure();
+        durations.push(Instant::now().duration_since(start));
+    }
+    durations
+}
+
+/// Runs some closure `iterations_per_thread` times on each of `threads` threads concurrently.
+/// Useful for measuring thread-safe code under contention, or for collecting many independent
+/// samples faster.
+/// # Panics
+/// If the closure panics on any of the threads
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+/// use tiny_bench::run_timed_times_parallel;
+/// let counter = AtomicU64::new(0);
+/// let data = run_timed_times_parallel(4, 100, || counter.fetch_add(1, Ordering::Relaxed));
+/// assert_eq!(400, counter.load(Ordering::Relaxed));
+/// assert_eq!(400, data.aggregate.iterations);
+/// data.pretty_print();
+/// ```
+pub fn run_timed_times_parallel<T, F: Fn() -> T + Sync>(
+    threads: usize,
+    iterations_per_thread: usize,
+    closure: F,
+) -> ParallelTimingData {
+    let start = Instant::now();
+    let per_thread = std::thread::scope(|s| {
+        let handles = (0..threads)
+            .map(|_| s.spawn(|| run_timed_times(iterations_per_thread, &closure)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("Timed closure panicked"))
+            .collect::<Vec<_>>()
+    });
+    let wall_elapsed = Instant::now().duration_since(start).as_nanos();
+    let aggregate = per_thread
+        .iter()
+        .copied()
+        .reduce(|a, b| a.merge(&b))
+        .unwrap_or_else(|| Accumulator::new().finish());
+    ParallelTimingData {
+        aggregate,
+        per_thread,
+        wall_elapsed,
     }
 }
 
@@ -71,36 +137,19 @@ pub fn run_timed_from_iterator<T, R, F: FnMut(R) -> T, It>(
 where
     It: Iterator<Item = R>,
 {
-    let mut elapsed = Duration::ZERO;
-    let mut min_nanos = u128::MAX;
-    let mut max_nanos = 0;
-    let mut iterations = 0;
+    let mut acc = Accumulator::new();
     for v in iterator {
         let start = Instant::now();
         closure(v);
-        let run_elapsed = Instant::now().duration_since(start);
-        let run_elapsed_nanos = run_elapsed.as_nanos();
-        if run_elapsed_nanos < min_nanos {
-            min_nanos = run_elapsed_nanos;
-        }
-        if run_elapsed_nanos > max_nanos {
-            max_nanos = run_elapsed_nanos;
-        }
-        elapsed += run_elapsed;
-        iterations += 1;
-    }
-    TimingData {
-        iterations,
-        min_nanos,
-        max_nanos,
-        elapsed: elapsed.as_nanos(),
+        acc.push(Instant::now().duration_since(start));
     }
+    acc.finish()
 }
 
 /// Data collected after a timed run
 #[derive(Copy, Clone, Debug)]
 #[cfg(feature = "timer")]
-#[cfg_attr(test, derive(Eq, PartialEq))]
+#[cfg_attr(test, derive(PartialEq))]
 pub struct TimingData {
     /// The last amount of time elapsed for an iteration
     pub min_nanos: u128,
@@ -110,10 +159,92 @@ pub struct TimingData {
     pub elapsed: u128,
     /// How many iterations were ran
     pub iterations: u128,
+    /// The sum of each iteration's squared difference from the mean, in nanoseconds squared.
+    /// Kept so that runs can be compared statistically without keeping every iteration's time,
+    /// read it through `variance`
+    pub(crate) squared_deviations: f64,
 }
 
 #[cfg(feature = "timer")]
 impl TimingData {
+    /// Data of a run with the given sample `variance` of its iteration times, in nanoseconds squared
+    /// ```
+    /// use tiny_bench::TimingData;
+    /// let data = TimingData::new(5, 10, 100, 15, 4.0);
+    /// assert!((data.variance() - 4.0).abs() < 1e-9);
+    /// ```
+    #[must_use]
+    pub fn new(
+        min_nanos: u128,
+        max_nanos: u128,
+        elapsed: u128,
+        iterations: u128,
+        variance: f64,
+    ) -> TimingData {
+        TimingData {
+            min_nanos,
+            max_nanos,
+            elapsed,
+            iterations,
+            squared_deviations: variance * iterations.saturating_sub(1) as f64,
+        }
+    }
+
+    /// Combines two runs into one, keeping the extremes of both and summing elapsed time and
+    /// iterations
+    /// ```
+    /// use tiny_bench::TimingData;
+    /// let a = TimingData::new(5, 10, 100, 15, 0.0);
+    /// let b = TimingData::new(3, 8, 50, 10, 0.0);
+    /// let merged = a.merge(&b);
+    /// assert_eq!(3, merged.min_nanos);
+    /// assert_eq!(10, merged.max_nanos);
+    /// assert_eq!(150, merged.elapsed);
+    /// assert_eq!(25, merged.iterations);
+    /// ```
+    #[must_use]
+    pub fn merge(&self, other: &TimingData) -> TimingData {
+        let iterations = self.iterations.saturating_add(other.iterations);
+        // Chan et al. parallel variance algorithm
+        let squared_deviations = if iterations == 0 {
+            0.0
+        } else {
+            let delta = other.mean() - self.mean();
+            self.squared_deviations
+                + other.squared_deviations
+                + delta * delta * self.iterations as f64 * other.iterations as f64
+                    / iterations as f64
+        };
+        TimingData {
+            min_nanos: self.min_nanos.min(other.min_nanos),
+            max_nanos: self.max_nanos.max(other.max_nanos),
+            elapsed: self.elapsed.saturating_add(other.elapsed),
+            iterations,
+            squared_deviations,
+        }
+    }
+
+    /// Mean time per iteration in nanoseconds
+    #[must_use]
+    pub fn mean(&self) -> f64 {
+        if self.iterations == 0 {
+            0.0
+        } else {
+            self.elapsed as f64 / self.iterations as f64
+        }
+    }
+
+    /// Sample variance of the iteration times in nanoseconds squared,
+    /// 0 if fewer than two iterations were ran
+    #[must_use]
+    pub fn variance(&self) -> f64 {
+        if self.iterations < 2 {
+            0.0
+        } else {
+            self.squared_deviations / (self.iterations - 1) as f64
+        }
+    }
+
     /// Print the data with pretty colors to stdout
     pub fn pretty_print(&self) {
         output::print_timer_header("anonymous", self);
@@ -125,6 +256,40 @@ impl TimingData {
     }
 }
 
+/// Data collected after a timed run spread across multiple threads
+#[derive(Clone, Debug)]
+#[cfg(feature = "timer")]
+pub struct ParallelTimingData {
+    /// All threads' data merged, `elapsed` is the sum of time spent in the closure on all threads
+    pub aggregate: TimingData,
+    /// Data for each thread in spawn order
+    pub per_thread: Vec<TimingData>,
+    /// Wall-clock time from spawning the first thread until the last one was joined
+    pub wall_elapsed: u128,
+}
+
+#[cfg(feature = "timer")]
+impl ParallelTimingData {
+    /// How many iterations all threads completed per second of wall-clock time
+    #[must_use]
+    pub fn throughput_per_sec(&self) -> f64 {
+        self.aggregate.iterations as f64 / self.wall_elapsed as f64 * 1_000_000_000f64
+    }
+
+    /// Print the aggregate and per-thread data with pretty colors to stdout
+    pub fn pretty_print(&self) {
+        self.aggregate.pretty_print();
+        output::print_throughput(self.throughput_per_sec(), self.per_thread.len());
+        for data in &self.per_thread {
+            output::timer_print_elapsed(
+                data.min_nanos as f64,
+                data.elapsed as f64 / data.iterations as f64,
+                data.max_nanos as f64,
+            );
+        }
+    }
+}
+
 /// A trait for allowing iterators to be used as timers
 pub trait Timeable<It, T>: Sized
 where
@@ -162,6 +327,24 @@ where
 
     /// Time this iterator with a custom label to separate different runs for comparison
     fn timed_persisted_labeled(self, label: &'static str) -> TimedIterator<It, T, ComparedStdout>;
+
+    /// Time this iterator with a specified label, but only print the results, with a warning, if
+    /// the mean time per item is over the threshold. For keeping an eye on iterators in long
+    /// running code without printing every time they run as expected
+    /// ```
+    /// use std::time::Duration;
+    /// use tiny_bench::Timeable;
+    /// let v: Vec<i32> = (0..100)
+    ///     .timed_if_slower_than(Duration::from_millis(1), "my_iterator_test")
+    ///     .collect();
+    /// // Prints nothing unless an item took over a millisecond on average
+    /// assert_eq!(100, v.len());
+    /// ```
+    fn timed_if_slower_than(
+        self,
+        threshold: Duration,
+        label: &'static str,
+    ) -> TimedIterator<It, T, SlowStdout>;
 }
 
 impl<It, T> Timeable<It, T> for It
@@ -184,6 +367,20 @@ where
             ),
         )
     }
+
+    fn timed_if_slower_than(
+        self,
+        threshold: Duration,
+        label: &'static str,
+    ) -> TimedIterator<It, T, SlowStdout> {
+        TimedIterator::new(
+            self,
+            LabeledOutput::new(
+                fallback_to_anonymous_on_invalid_label(label),
+                SlowStdout { threshold },
+            ),
+        )
+    }
 }
 
 /// An iterator that wraps another iterator and times each call to `next`
@@ -192,10 +389,7 @@ where
     It: Iterator<Item = T>,
 {
     inner: It,
-    iterations: u128,
-    min_nanos: u128,
-    max_nanos: u128,
-    elapsed: Duration,
+    acc: Accumulator,
     out: LabeledOutput<O>,
 }
 
@@ -206,10 +400,7 @@ where
     fn new(inner: It, out: LabeledOutput<O>) -> Self {
         TimedIterator {
             inner,
-            iterations: 0,
-            min_nanos: u128::MAX,
-            max_nanos: 0,
-            elapsed: Duration::ZERO,
+            acc: Accumulator::new(),
             out,
         }
     }
@@ -227,32 +418,132 @@ where
         let maybe_item = self.inner.next();
         let run_elapsed = Instant::now().duration_since(start);
         if let Some(item) = maybe_item {
-            let run_elapsed_nanos = run_elapsed.as_nanos();
-            if run_elapsed_nanos < self.min_nanos {
-                self.min_nanos = run_elapsed_nanos;
-            }
-            if run_elapsed_nanos > self.max_nanos {
-                self.max_nanos = run_elapsed_nanos;
-            }
-            self.elapsed += run_elapsed;
-            self.iterations += 1;
+            self.acc.push(run_elapsed);
             Some(item)
         } else {
-            self.out.dump(TimingData {
-                min_nanos: self.min_nanos,
-                max_nanos: self.max_nanos,
-                elapsed: self.elapsed.as_nanos(),
-                iterations: self.iterations,
-            });
+            self.out.dump(self.acc.finish());
             None
         }
     }
 }
 
+/// Tracks the iteration times of a timed run,
+/// the variance is calculated online using [Welford's algorithm](https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Welford's_online_algorithm)
+struct Accumulator {
+    min_nanos: u128,
+    max_nanos: u128,
+    elapsed: Duration,
+    iterations: u128,
+    mean: f64,
+    squared_deviations: f64,
+}
+
+impl Accumulator {
+    fn new() -> Self {
+        Self {
+            min_nanos: u128::MAX,
+            max_nanos: 0,
+            elapsed: Duration::ZERO,
+            iterations: 0,
+            mean: 0.0,
+            squared_deviations: 0.0,
+        }
+    }
+
+    fn push(&mut self, run_elapsed: Duration) {
+        let run_elapsed_nanos = run_elapsed.as_nanos();
+        if run_elapsed_nanos < self.min_nanos {
+            self.min_nanos = run_elapsed_nanos;
+        }
+        if run_elapsed_nanos > self.max_nanos {
+            self.max_nanos = run_elapsed_nanos;
+        }
+        self.elapsed += run_elapsed;
+        self.iterations += 1;
+        let x = run_elapsed_nanos as f64;
+        let delta = x - self.mean;
+        self.mean += delta / self.iterations as f64;
+        self.squared_deviations += delta * (x - self.mean);
+    }
+
+    fn finish(&self) -> TimingData {
+        TimingData {
+            min_nanos: self.min_nanos,
+            max_nanos: self.max_nanos,
+            elapsed: self.elapsed.as_nanos(),
+            iterations: self.iterations,
+            squared_deviations: self.squared_deviations,
+        }
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "timer")]
 mod tests {
-    use crate::timing::Timeable;
+    use crate::timing::{run_timed_times_collect, Accumulator, Timeable, TimingData};
+    use std::time::Duration;
+
+    #[test]
+    fn accumulates_variance() {
+        let mut a = Accumulator::new();
+        let mut b = Accumulator::new();
+        let mut all = Accumulator::new();
+        for (i, nanos) in [46, 69, 32, 60, 52, 41].into_iter().enumerate() {
+            let d = Duration::from_nanos(nanos);
+            if i < 2 {
+                a.push(d);
+            } else {
+                b.push(d);
+            }
+            all.push(d);
+        }
+        let all = all.finish();
+        assert!((all.mean() - 50.0).abs() < 0.000_001);
+        assert!((all.variance() - 177.2).abs() < 0.000_01);
+        let merged = a.finish().merge(&b.finish());
+        assert!((merged.variance() - all.variance()).abs() < 0.000_01);
+    }
+
+    #[test]
+    fn merges_huge_totals() {
+        let huge = TimingData {
+            min_nanos: 1,
+            max_nanos: 1,
+            elapsed: u128::MAX - 1,
+            iterations: u128::MAX - 1,
+            squared_deviations: 0.0,
+        };
+        let merged = huge.merge(&huge);
+        assert_eq!(u128::MAX, merged.elapsed);
+        assert_eq!(u128::MAX, merged.iterations);
+    }
+
+    #[test]
+    fn collects_every_duration() {
+        let mut calls = 0;
+        let durations = run_timed_times_collect(10, || {
+            calls += 1;
+            std::thread::sleep(Duration::from_micros(calls));
+        });
+        assert_eq!(10, durations.len());
+        assert!(durations
+            .iter()
+            .zip(1..)
+            .all(|(duration, micros)| *duration >= Duration::from_micros(micros)));
+    }
+
+    #[test]
+    fn time_slow_iterator() {
+        let fast: Vec<i32> = (0..100)
+            .timed_if_slower_than(Duration::from_secs(1), "fast")
+            .collect();
+        assert_eq!(100, fast.len());
+        let slow: Vec<i32> = (0..2)
+            .inspect(|_| std::thread::sleep(Duration::from_millis(1)))
+            .timed_if_slower_than(Duration::ZERO, "slow")
+            .collect();
+        assert_eq!(2, slow.len());
+    }
 
     #[test]
     fn time_iterator() {